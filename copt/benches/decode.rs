@@ -0,0 +1,89 @@
+use bytes::{Buf, BytesMut};
+use copt::error::Error;
+use copt::test_utils::NopError;
+use copt::CoptDecoder;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use tokio_util::codec::Decoder;
+
+const FRAMES: usize = 1000;
+const PAYLOAD_LEN: usize = 32;
+
+/// A payload decoder splitting fixed length chunks.
+struct ChunkDecoder(usize);
+
+impl Decoder for ChunkDecoder {
+    type Item = BytesMut;
+    type Error = NopError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < self.0 {
+            return Ok(None);
+        }
+        Ok(Some(src.split_to(self.0)))
+    }
+}
+
+/// 1000 dt data frames back to back
+fn stream() -> BytesMut {
+    let mut src = BytesMut::with_capacity(FRAMES * (3 + PAYLOAD_LEN));
+    for index in 0..FRAMES {
+        src.extend_from_slice(&[0x02, 0xf0, 0x80]);
+        src.extend_from_slice(&[index as u8; PAYLOAD_LEN]);
+    }
+    src
+}
+
+/// The dt data branch as it was, cloning the whole buffer before decoding
+/// the payload.
+fn decode_cloned(
+    decoder: &mut ChunkDecoder,
+    src: &mut BytesMut,
+) -> Result<Option<(u8, BytesMut)>, Error> {
+    let Some(length) = src.first() else {
+        return Ok(None);
+    };
+    let length = *length as usize + 1;
+    if src.len() < length {
+        return Ok(None);
+    }
+    let mut sub_src = src.clone().split_off(length);
+    let pre_length = sub_src.len();
+    let Some(f) = decoder.decode(&mut sub_src)? else {
+        return Ok(None);
+    };
+    let sub_length = pre_length - sub_src.len();
+    let mut src = src.split_to(length + sub_length).split_off(2);
+    Ok(Some((src.get_u8(), f)))
+}
+
+fn dt_data_stream(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dt_data_stream");
+    group.bench_function("clone", |b| {
+        b.iter_batched(
+            stream,
+            |mut src| {
+                let mut decoder = ChunkDecoder(PAYLOAD_LEN);
+                while let Some(frame) = decode_cloned(&mut decoder, &mut src).unwrap() {
+                    black_box(frame);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("split", |b| {
+        b.iter_batched(
+            stream,
+            |mut src| {
+                let mut decoder = CoptDecoder::new(ChunkDecoder(PAYLOAD_LEN));
+                while let Some(frame) = decoder.decode(&mut src).unwrap() {
+                    black_box(frame);
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, dt_data_stream);
+criterion_main!(benches);
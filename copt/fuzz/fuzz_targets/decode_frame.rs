@@ -0,0 +1,14 @@
+#![no_main]
+
+use bytes::BytesMut;
+use copt::{test_utils::NopDecoder, CoptDecoder};
+use libfuzzer_sys::fuzz_target;
+use tokio_util::codec::Decoder;
+
+// run with: cargo +nightly fuzz run decode_frame
+fuzz_target!(|data: &[u8]| {
+    let mut decoder = CoptDecoder::new(NopDecoder);
+    let mut src = BytesMut::from(data);
+    // any of Ok(None), Ok(Some(_)) and Err(_) is fine, only panics are bugs
+    let _ = decoder.decode(&mut src);
+});
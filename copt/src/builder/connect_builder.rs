@@ -1,8 +1,9 @@
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
+use crate::error::Result;
 use crate::packet::{ConnectComm, CoptFrame, PduType};
-use crate::Parameter;
+use crate::{ConnectionType, Parameter, TpduSize};
 
 pub struct ConnectBuilder<F> {
     destination_ref: [u8; 2],
@@ -23,22 +24,37 @@ impl<F> Default for ConnectBuilder<F> {
             extended_formats: false,
             no_explicit_flow_control: false,
             parameters: vec![],
-            phantom_data: PhantomData::default(),
+            phantom_data: PhantomData,
         }
     }
 }
 
 impl<F: Debug + Eq + PartialEq> ConnectBuilder<F> {
+    /// The connect of a s7-300/400 cpu in the canonical order of the
+    /// parameters: the tpdu size, the source tsap of PG and the destination
+    /// tsap of the rack and slot, see `Parameter::s7_dst_tsap`, which fails
+    /// for a rack above 7 or a slot above 31.
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn standard(rack: u8, slot: u8, tpdu_size: TpduSize) -> Result<Self> {
+        Ok(Self::default()
+            .push_parameter(Parameter::new_tpdu_size(tpdu_size))
+            .connection_type(ConnectionType::PG)
+            .push_parameter(Parameter::s7_dst_tsap(rack, slot)?))
+    }
+
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn source_ref(mut self, source_ref: [u8; 2]) -> Self {
         self.source_ref = source_ref;
         self
     }
 
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn destination_ref(mut self, destination_ref: [u8; 2]) -> Self {
         self.destination_ref = destination_ref;
         self
     }
 
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn class_and_others(
         mut self,
         class: u8,
@@ -51,6 +67,23 @@ impl<F: Debug + Eq + PartialEq> ConnectBuilder<F> {
         self
     }
 
+    /// Set the source tsap to the one of the connection type, replacing a
+    /// source tsap pushed before in its place.
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn connection_type(mut self, connection_type: ConnectionType) -> Self {
+        let tsap = Parameter::src_tsap_of(connection_type);
+        match self
+            .parameters
+            .iter_mut()
+            .find(|x| matches!(x, Parameter::SrcTsap(_)))
+        {
+            Some(parameter) => *parameter = tsap,
+            None => self.parameters.push(tsap),
+        }
+        self
+    }
+
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn push_parameter(mut self, parameter: Parameter) -> Self {
         self.parameters.push(parameter);
         self
@@ -69,6 +102,7 @@ impl<F: Debug + Eq + PartialEq> ConnectBuilder<F> {
 
         CoptFrame {
             pdu_type: PduType::ConnectRequest(ConnectComm {
+                credit: 0,
                 destination_ref,
                 source_ref,
                 class,
@@ -92,6 +126,7 @@ impl<F: Debug + Eq + PartialEq> ConnectBuilder<F> {
 
         CoptFrame {
             pdu_type: PduType::ConnectConfirm(ConnectComm {
+                credit: 0,
                 destination_ref,
                 source_ref,
                 class,
@@ -102,3 +137,61 @@ impl<F: Debug + Eq + PartialEq> ConnectBuilder<F> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ConnectBuilder;
+    use crate::{ConnectionType, Parameter, PduType, TpduSize};
+
+    #[test]
+    fn test_standard() {
+        let frame = ConnectBuilder::<()>::standard(0, 2, TpduSize::L1024)
+            .unwrap()
+            .build_to_request();
+        let PduType::ConnectRequest(comm) = frame.pdu_type else {
+            unreachable!()
+        };
+        assert_eq!(
+            comm.parameters,
+            vec![
+                Parameter::TpduSize(TpduSize::L1024),
+                Parameter::SrcTsap(vec![0x01, 0x00]),
+                Parameter::DstTsap(vec![0x03, 0x02]),
+            ]
+        );
+        assert_eq!(comm.length(), 17);
+    }
+
+    #[test]
+    fn test_connection_type() {
+        for (connection_type, tsap) in [
+            (ConnectionType::PG, [0x01, 0x00]),
+            (ConnectionType::OP, [0x02, 0x00]),
+            (ConnectionType::Basic, [0x03, 0x00]),
+        ] {
+            let frame = ConnectBuilder::<()>::standard(0, 2, TpduSize::L1024)
+                .unwrap()
+                .connection_type(connection_type)
+                .build_to_request();
+            let PduType::ConnectRequest(comm) = frame.pdu_type else {
+                unreachable!()
+            };
+            assert_eq!(
+                comm.parameters,
+                vec![
+                    Parameter::TpduSize(TpduSize::L1024),
+                    Parameter::SrcTsap(tsap.to_vec()),
+                    Parameter::DstTsap(vec![0x03, 0x02]),
+                ]
+            );
+        }
+
+        let frame = ConnectBuilder::<()>::default()
+            .connection_type(ConnectionType::OP)
+            .build_to_request();
+        let PduType::ConnectRequest(comm) = frame.pdu_type else {
+            unreachable!()
+        };
+        assert_eq!(comm.parameters, vec![Parameter::SrcTsap(vec![0x02, 0x00])]);
+    }
+}
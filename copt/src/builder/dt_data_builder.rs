@@ -6,6 +6,7 @@ pub struct DtDataBuilder<F> {
 }
 
 impl<F: Debug + Eq + PartialEq> DtDataBuilder<F> {
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn new(payload: F) -> Self {
         Self { payload }
     }
@@ -1,12 +1,46 @@
-use bytes::{Buf, BytesMut};
+use bytes::BytesMut;
 use std::fmt::Debug;
 use tokio_util::codec::Decoder;
 
 use crate::error::{Error, ToCoptError};
-use crate::packet::{ConnectComm, CoptFrame, DtData, PduType};
+use crate::packet::{ConnectComm, CoptFrame, DisconnectComm, DtData, PduType};
 
-#[derive(Default)]
-pub struct CoptDecoder<D>(pub D);
+pub struct CoptDecoder<D> {
+    decoder: D,
+    /// frames longer than it, the header and the dt data payload, are
+    /// rejected
+    pub max_frame_length: usize,
+}
+
+impl<D> CoptDecoder<D> {
+    pub fn new(decoder: D) -> Self {
+        Self {
+            decoder,
+            max_frame_length: u16::MAX as usize,
+        }
+    }
+
+    pub fn with_max_frame_length(mut self, max_frame_length: usize) -> Self {
+        self.max_frame_length = max_frame_length;
+        self
+    }
+
+    fn check_frame_length(&self, length: usize) -> Result<(), Error> {
+        if length > self.max_frame_length {
+            return Err(Error::ProtocolError {
+                context: "frame length exceeded",
+                detail: format!("{} bytes, the limit is {}", length, self.max_frame_length),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<D: Default> Default for CoptDecoder<D> {
+    fn default() -> Self {
+        Self::new(D::default())
+    }
+}
 
 impl<F: Debug + Eq + PartialEq, D: Decoder<Item = F>> Decoder for CoptDecoder<D>
 where
@@ -19,36 +53,73 @@ where
         &mut self,
         src: &mut BytesMut,
     ) -> std::result::Result<Option<Self::Item>, Self::Error> {
-        let (Some(length), Some(pdu_type)) = (src.get(0), src.get(1)) else {
+        let (Some(length), Some(pdu_type)) = (src.first(), src.get(1)) else {
             return Ok(None);
         };
         let length = *length as usize + 1;
+        self.check_frame_length(length)?;
         if src.len() < length || length < 2 {
             return Ok(None);
         };
+        // the code of connect request/confirm is the high nibble, the low
+        // nibble is the credit
+        let credit = *pdu_type & 0x0f;
         match *pdu_type {
-            // 0x0e?
-            0xe0 => {
+            0xe0..=0xef => {
+                let mut src = src.split_to(length).split_off(2);
+                Ok(Some(CoptFrame {
+                    pdu_type: PduType::ConnectRequest(decode_connect_comm(credit, &mut src)?),
+                }))
+            }
+            0xd0..=0xdf => {
                 let mut src = src.split_to(length).split_off(2);
                 Ok(Some(CoptFrame {
-                    pdu_type: PduType::ConnectRequest(ConnectComm::decode(&mut src)?),
+                    pdu_type: PduType::ConnectConfirm(decode_connect_comm(credit, &mut src)?),
                 }))
             }
-            0xd0 => {
+            0x80 => {
                 let mut src = src.split_to(length).split_off(2);
                 Ok(Some(CoptFrame {
-                    pdu_type: PduType::ConnectConfirm(ConnectComm::decode(&mut src)?),
+                    pdu_type: PduType::DisconnectRequest(DisconnectComm::decode(&mut src)?),
+                }))
+            }
+            0xc0 => {
+                let mut src = src.split_to(length).split_off(2);
+                Ok(Some(CoptFrame {
+                    pdu_type: PduType::DisconnectConfirm(DisconnectComm::decode_confirm(&mut src)?),
                 }))
             }
             0xf0 => {
-                let mut sub_src = src.clone().split_off(length);
-                let pre_length = sub_src.len();
-                let Some(f) = self.0.decode(&mut sub_src)? else {
-                    return Err(Error::Other("decode fail".to_string()));
+                if length < 3 {
+                    return Err(Error::ProtocolError {
+                        context: "dt data",
+                        detail: format!("invalid length indicator: {}", length - 1),
+                    });
+                }
+                // decode the payload in place behind the header, split_off
+                // and unsplit of the contiguous halves copy nothing
+                let mut payload = src.split_off(length);
+                let buffered = payload.len();
+                let f = match self.decoder.decode(&mut payload) {
+                    Ok(Some(f)) => f,
+                    rs => {
+                        // the payload decoder consumes nothing until the
+                        // frame is complete, so src is left as it was
+                        src.unsplit(payload);
+                        // dt data has no length of payload, wait for more
+                        // data unless the buffered part is already too long
+                        rs?;
+                        self.check_frame_length(length + buffered)?;
+                        return Ok(None);
+                    }
                 };
-                let sub_length = pre_length - sub_src.len();
-                let mut src = src.split_to(length + sub_length).split_off(2);
-                let merge = src.get_u8();
+                if let Err(e) = self.check_frame_length(length + buffered - payload.len()) {
+                    src.unsplit(payload);
+                    return Err(e);
+                }
+                let merge = src[2];
+                // drop the header, leave the bytes after the payload
+                *src = payload;
                 let tpdu_number = merge & 0b0111_1111;
                 let last_data_unit = merge & 0b1000_0000 > 0;
                 Ok(Some(CoptFrame {
@@ -59,9 +130,232 @@ where
                     }),
                 }))
             }
-            _ => {
-                return Err(Error::Other(format!("not support pdu type: {}", pdu_type)));
-            }
+            _ => Err(Error::UnknownPduType(*pdu_type)),
         }
     }
+
+    /// a stream closed in the middle of a frame is an error rather than the
+    /// end of the frames
+    fn decode_eof(
+        &mut self,
+        buf: &mut BytesMut,
+    ) -> std::result::Result<Option<Self::Item>, Self::Error> {
+        match self.decode(buf)? {
+            Some(frame) => Ok(Some(frame)),
+            None if buf.is_empty() => Ok(None),
+            None => Err(Error::ProtocolError {
+                context: "unexpected EOF",
+                detail: format!("{} bytes remaining", buf.len()),
+            }),
+        }
+    }
+}
+
+/// decode ConnectComm and check that all the declared bytes are consumed
+fn decode_connect_comm(credit: u8, src: &mut BytesMut) -> Result<ConnectComm, Error> {
+    let declared = src.len();
+    let comm = ConnectComm {
+        credit,
+        ..ConnectComm::decode(src)?
+    };
+    let consumed = declared - src.len();
+    if consumed != declared {
+        return Err(Error::ProtocolError {
+            context: "connect comm",
+            detail: format!(
+                "consumed {} bytes, but declared {} bytes",
+                consumed, declared
+            ),
+        });
+    }
+    Ok(comm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::NopDecoder;
+    use crate::Parameter;
+
+    fn decode(bytes: &[u8]) -> Result<Option<CoptFrame<()>>, Error> {
+        let mut decoder = CoptDecoder::new(NopDecoder);
+        let mut src = BytesMut::from(bytes);
+        decoder.decode(&mut src)
+    }
+
+    #[test]
+    fn test_decode_length_longer_than_data() {
+        assert!(matches!(decode(&[0xff, 0xe0]), Ok(None)));
+    }
+
+    #[test]
+    fn test_decode_unknown_pdu_type() {
+        assert!(matches!(
+            decode(&[0x01, 0x00]),
+            Err(Error::UnknownPduType(0x00))
+        ));
+    }
+
+    #[test]
+    fn test_decode_other_parameter_code() {
+        let Ok(Some(frame)) = decode(&[
+            0x0c, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc6, 0x01, 0x01, 0xc0, 0x01, 0x0a,
+        ]) else {
+            unreachable!()
+        };
+        let PduType::ConnectRequest(comm) = frame.pdu_type else {
+            unreachable!()
+        };
+        assert_eq!(
+            comm.parameters,
+            vec![
+                Parameter::Other {
+                    code: 0xc6,
+                    data: vec![0x01]
+                },
+                Parameter::TpduSize(crate::TpduSize::L1024),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_tsap_length_overrun() {
+        assert!(matches!(
+            decode(&[0x09, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc1, 0xc8, 0x01]),
+            Err(Error::InsufficientData {
+                needed: 202,
+                got: 3
+            })
+        ));
+        assert!(decode(&[0x09, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc1, 0xff, 0x01]).is_err());
+    }
+
+    #[test]
+    fn test_decode_empty_tpdu_size() {
+        assert!(matches!(
+            decode(&[0x08, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc0, 0x00]),
+            Err(Error::InsufficientData { needed: 1, got: 0 })
+        ));
+        assert!(matches!(
+            decode(&[0x09, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc0, 0x01, 0x0e]),
+            Err(Error::InvalidTpduSize(0x0e))
+        ));
+    }
+
+    #[test]
+    fn test_decode_trailing_0xc2() {
+        let Ok(Some(frame)) = decode(&[0x07, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc2]) else {
+            unreachable!()
+        };
+        let PduType::ConnectRequest(comm) = frame.pdu_type else {
+            unreachable!()
+        };
+        assert!(comm.parameters.is_empty());
+    }
+
+    #[test]
+    fn test_decode_short_dt_data() {
+        assert!(matches!(
+            decode(&[0x01, 0xf0]),
+            Err(Error::ProtocolError { .. })
+        ));
+        assert!(matches!(decode(&[0x02, 0xf0, 0x80]), Ok(None)));
+    }
+
+    #[test]
+    fn test_decode_partial_dt_data_keeps_src() {
+        let bytes = [0x02, 0xf0, 0x80, 0x32, 0x01];
+        let mut src = BytesMut::from(bytes.as_ref());
+        assert!(matches!(
+            CoptDecoder::new(NopDecoder).decode(&mut src),
+            Ok(None)
+        ));
+        assert_eq!(src.as_ref(), bytes.as_ref());
+    }
+
+    #[test]
+    fn test_decode_max_frame_length() {
+        let mut decoder = CoptDecoder::new(NopDecoder).with_max_frame_length(100);
+        // a dt data frame of 1000 bytes, the payload is not complete yet
+        let mut bytes = vec![0x02, 0xf0, 0x80];
+        bytes.resize(1000, 0);
+        assert!(matches!(
+            decoder.decode(&mut BytesMut::from(bytes.as_slice())),
+            Err(Error::ProtocolError { context: "frame length exceeded", detail })
+                if detail == "1000 bytes, the limit is 100"
+        ));
+        // the length indicator alone exceeds the limit
+        let mut src = BytesMut::from([0xc8, 0xe0].as_ref());
+        assert!(matches!(
+            decoder.decode(&mut src),
+            Err(Error::ProtocolError {
+                context: "frame length exceeded",
+                ..
+            })
+        ));
+        assert!(matches!(
+            decoder.decode(&mut BytesMut::from([0x02, 0xf0, 0x80, 0x32].as_ref())),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn test_decode_eof_partial_connect_request() {
+        let mut decoder = CoptDecoder::new(NopDecoder);
+        let mut src = BytesMut::from([0x11, 0xe0, 0x00, 0x00, 0x00, 0x01].as_ref());
+        assert!(matches!(
+            decoder.decode_eof(&mut src),
+            Err(Error::ProtocolError { context: "unexpected EOF", detail }) if detail == "6 bytes remaining"
+        ));
+        assert!(matches!(decoder.decode_eof(&mut BytesMut::new()), Ok(None)));
+    }
+
+    #[test]
+    fn test_decode_disconnect_request() {
+        let Ok(Some(frame)) = decode(&[0x06, 0x80, 0x00, 0x01, 0x00, 0x02, 0x80]) else {
+            unreachable!()
+        };
+        assert_eq!(
+            frame,
+            CoptFrame::disconnect_request([0x00, 0x01], [0x00, 0x02])
+        );
+        // with the additional information
+        let Ok(Some(frame)) = decode(&[0x09, 0x80, 0x00, 0x01, 0x00, 0x02, 0x80, 0xe0, 0x01, 0x00])
+        else {
+            unreachable!()
+        };
+        assert_eq!(frame.length(), 6);
+        assert!(matches!(
+            decode(&[0x04, 0x80, 0x00, 0x01, 0x00]),
+            Err(Error::InsufficientData { needed: 5, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_decode_disconnect_confirm() {
+        let Ok(Some(frame)) = decode(&[0x05, 0xc0, 0x00, 0x02, 0x00, 0x01]) else {
+            unreachable!()
+        };
+        assert_eq!(
+            frame,
+            CoptFrame::disconnect_confirm([0x00, 0x02], [0x00, 0x01])
+        );
+        assert!(matches!(
+            decode(&[0x04, 0xc0, 0x00, 0x02, 0x00]),
+            Err(Error::InsufficientData { needed: 4, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_decode_declared_length_too_long() {
+        let rs = decode(&[
+            0x12, 0xe0, 0x00, 0x00, 0x00, 0x01, 0x00, 0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01, 0x00,
+            0xc2, 0x02, 0x02, 0x01, 0x02,
+        ]);
+        let Err(Error::ProtocolError { context, detail }) = rs else {
+            unreachable!()
+        };
+        assert_eq!(context, "connect comm");
+        assert_eq!(detail, "consumed 16 bytes, but declared 17 bytes");
+    }
 }
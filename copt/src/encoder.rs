@@ -4,6 +4,7 @@ use tokio_util::codec::Encoder;
 
 use crate::error::*;
 use crate::packet::{CoptFrame, PduType};
+use crate::DtDataBuilder;
 
 #[derive(Default)]
 pub struct CoptEncoder<E>(pub E);
@@ -19,25 +20,97 @@ where
         item: CoptFrame<F>,
         dst: &mut BytesMut,
     ) -> std::result::Result<(), Self::Error> {
-        dst.put_u8(item.length());
+        if let PduType::ConnectRequest(conn) | PduType::ConnectConfirm(conn) = &item.pdu_type {
+            conn.validate()?;
+        }
+        let length = item.pdu_type.encoded_len();
+        if length > u8::MAX as usize {
+            return Err(Error::FrameTooLong { length });
+        }
+        dst.put_u8(length as u8);
         match item.pdu_type {
             PduType::ConnectRequest(conn) => {
-                dst.put_u8(0xe0);
+                dst.put_u8(0xe0 | conn.credit & 0x0f);
                 conn.encode(dst);
                 Ok(())
             }
             PduType::ConnectConfirm(conn) => {
-                dst.put_u8(0xd0);
+                dst.put_u8(0xd0 | conn.credit & 0x0f);
                 conn.encode(dst);
                 Ok(())
             }
             PduType::DtData(conn) => {
                 dst.put_u8(0xf0);
-                let merge =
-                    conn.tpdu_number >> 1 | if conn.last_data_unit { 0b1000_0000 } else { 0 };
+                let merge = conn.tpdu_number & 0b0111_1111
+                    | if conn.last_data_unit { 0b1000_0000 } else { 0 };
                 dst.put_u8(merge);
                 Ok(self.0.encode(conn.payload, dst)?)
             }
+            PduType::DisconnectRequest(conn) => {
+                dst.put_u8(0x80);
+                conn.encode(dst);
+                Ok(())
+            }
+            PduType::DisconnectConfirm(conn) => {
+                dst.put_u8(0xc0);
+                conn.encode_confirm(dst);
+                Ok(())
+            }
         }
     }
 }
+
+/// Split a payload exceeding the negotiated tpdu size into dt data frames of
+/// at most `max_fragment_size` payload bytes. The tpdu numbers count up from 0
+/// and only the last frame is marked as the last data unit.
+pub fn dt_data_fragments<F>(payload: &[u8], max_fragment_size: usize) -> Result<Vec<CoptFrame<F>>>
+where
+    F: Debug + Eq + PartialEq + for<'a> From<&'a [u8]>,
+{
+    if max_fragment_size == 0 {
+        return Err(Error::Other("max fragment size is 0".to_string()));
+    }
+    let count = payload.len().div_ceil(max_fragment_size).max(1);
+    Ok((0..count)
+        .map(|index| {
+            let start = index * max_fragment_size;
+            let end = payload.len().min(start + max_fragment_size);
+            DtDataBuilder::new(F::from(&payload[start..end]))
+                .build((index % 128) as u8, index + 1 == count)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dt_data_fragments() {
+        let payload: Vec<u8> = (0..1000).map(|x| x as u8).collect();
+        let frames = dt_data_fragments::<Vec<u8>>(&payload, 256).unwrap();
+        assert_eq!(frames.len(), 4);
+        let mut joined = Vec::new();
+        for (index, frame) in frames.into_iter().enumerate() {
+            let PduType::DtData(data) = frame.pdu_type else {
+                unreachable!()
+            };
+            assert_eq!(data.tpdu_number(), index as u8);
+            assert_eq!(data.last_data_unit(), index == 3);
+            let fragment = data.payload();
+            assert_eq!(fragment.len(), if index == 3 { 232 } else { 256 });
+            joined.extend_from_slice(&fragment);
+        }
+        assert_eq!(joined, payload);
+    }
+
+    #[test]
+    fn test_dt_data_fragments_small_payload() {
+        let frames = dt_data_fragments::<Vec<u8>>(&[], 256).unwrap();
+        assert_eq!(
+            frames,
+            vec![CoptFrame::builder_of_dt_data(vec![]).build(0, true)]
+        );
+        assert!(dt_data_fragments::<Vec<u8>>(&[0x32], 0).is_err());
+    }
+}
@@ -10,12 +10,41 @@ pub enum Error {
     #[error(transparent)]
     IoErr(#[from] io::Error),
 
+    #[error("protocol error of {context}: {detail}")]
+    ProtocolError {
+        context: &'static str,
+        detail: String,
+    },
+
+    #[error("insufficient data: need {needed} bytes, got {got} bytes")]
+    InsufficientData { needed: usize, got: usize },
+
+    #[error("unknown pdu type: {0:#04x}")]
+    UnknownPduType(u8),
+
+    #[error("invalid tpdu size: {0:#04x}")]
+    InvalidTpduSize(u8),
+
+    /// the header exceeds the one byte length indicator
+    #[error("copt header of {length} bytes exceeds the length indicator")]
+    FrameTooLong { length: usize },
+
     #[error("{0}")]
     Other(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// the kind of the io error, kept from the payload decoder
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            Error::IoErr(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
 pub trait ToCoptError {
     fn to_err(self) -> Error;
 }
@@ -28,12 +57,15 @@ impl<T: ToCoptError> From<T> for Error {
 
 impl ToTpktError for Error {
     fn to_err(self) -> tpkt::Error {
-        tpkt::Error::Error(self.to_string())
+        match self {
+            Error::IoErr(e) => tpkt::Error::IoErr(e),
+            e => tpkt::Error::Error(e.to_string()),
+        }
     }
 }
 
 impl From<TryFromPrimitiveError<TpduSize>> for Error {
     fn from(value: TryFromPrimitiveError<TpduSize>) -> Self {
-        Self::Other(value.to_string())
+        Self::InvalidTpduSize(value.number)
     }
 }
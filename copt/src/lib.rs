@@ -1,7 +1,7 @@
 pub mod error;
 
 mod packet;
-pub use packet::{ConnectComm, CoptFrame, DtData, PduType};
+pub use packet::{ConnectComm, CoptFrame, DisconnectComm, DtData, PduType, TransportClass};
 
 pub mod builder;
 use builder::*;
@@ -10,7 +10,10 @@ pub mod decoder;
 pub use decoder::CoptDecoder;
 
 pub mod encoder;
-pub use encoder::CoptEncoder;
+pub use encoder::{dt_data_fragments, CoptEncoder};
 
 pub mod parameter;
-pub use parameter::{Parameter, TpduSize};
+pub use parameter::{ConnectionType, Parameter, TpduSize};
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
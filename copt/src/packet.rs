@@ -1,12 +1,14 @@
 use std::fmt::Debug;
 
 use bytes::{Buf, BufMut, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use crate::builder::ConnectBuilder;
 use crate::error::{Error, Result};
 use crate::{DtDataBuilder, Parameter};
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CoptFrame<F: Debug + Eq + PartialEq> {
     pub pdu_type: PduType<F>,
 }
@@ -23,16 +25,112 @@ impl<F: Debug + Eq + PartialEq> CoptFrame<F> {
     pub fn length(&self) -> u8 {
         self.pdu_type.length()
     }
+
+    /// close the connection normally, the references are those of the
+    /// connect request and confirm
+    pub fn disconnect_request(destination_ref: [u8; 2], source_ref: [u8; 2]) -> Self {
+        Self {
+            pdu_type: PduType::DisconnectRequest(DisconnectComm {
+                destination_ref,
+                source_ref,
+                reason: DisconnectComm::REASON_NORMAL,
+            }),
+        }
+    }
+
+    /// confirm the disconnect request, the references are swapped from those
+    /// of the request
+    pub fn disconnect_confirm(destination_ref: [u8; 2], source_ref: [u8; 2]) -> Self {
+        Self {
+            pdu_type: PduType::DisconnectConfirm(DisconnectComm {
+                destination_ref,
+                source_ref,
+                reason: 0,
+            }),
+        }
+    }
+
+    pub fn is_dt_data(&self) -> bool {
+        matches!(self.pdu_type, PduType::DtData(_))
+    }
+
+    pub fn is_connect_request(&self) -> bool {
+        matches!(self.pdu_type, PduType::ConnectRequest(_))
+    }
+
+    pub fn is_connect_confirm(&self) -> bool {
+        matches!(self.pdu_type, PduType::ConnectConfirm(_))
+    }
+
+    pub fn as_dt_data(&self) -> Option<&DtData<F>> {
+        match &self.pdu_type {
+            PduType::DtData(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn as_connect_request(&self) -> Option<&ConnectComm> {
+        match &self.pdu_type {
+            PduType::ConnectRequest(comm) => Some(comm),
+            _ => None,
+        }
+    }
+
+    pub fn as_connect_confirm(&self) -> Option<&ConnectComm> {
+        match &self.pdu_type {
+            PduType::ConnectConfirm(comm) => Some(comm),
+            _ => None,
+        }
+    }
+
+    pub fn into_dt_data(self) -> Option<DtData<F>> {
+        match self.pdu_type {
+            PduType::DtData(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    pub fn into_connect_request(self) -> Option<ConnectComm> {
+        match self.pdu_type {
+            PduType::ConnectRequest(comm) => Some(comm),
+            _ => None,
+        }
+    }
+
+    pub fn into_connect_confirm(self) -> Option<ConnectComm> {
+        match self.pdu_type {
+            PduType::ConnectConfirm(comm) => Some(comm),
+            _ => None,
+        }
+    }
+
+    /// transform the payload of dt data, the other pdu types carry no
+    /// payload and are kept as they are
+    pub fn map<G: Debug + Eq + PartialEq, Fun: FnOnce(F) -> G>(self, f: Fun) -> CoptFrame<G> {
+        let pdu_type = match self.pdu_type {
+            PduType::ConnectRequest(comm) => PduType::ConnectRequest(comm),
+            PduType::ConnectConfirm(comm) => PduType::ConnectConfirm(comm),
+            PduType::DtData(data) => PduType::DtData(data.map_payload(f)),
+            PduType::DisconnectRequest(comm) => PduType::DisconnectRequest(comm),
+            PduType::DisconnectConfirm(comm) => PduType::DisconnectConfirm(comm),
+        };
+        CoptFrame { pdu_type }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum PduType<F: Debug + Eq + PartialEq> {
-    /// 0x0e
+    /// 0xe0, the code 0x0e in the high nibble and the credit in the low
     ConnectRequest(ConnectComm),
-    /// 0x0d
+    /// 0xd0, the code 0x0d in the high nibble and the credit in the low
     ConnectConfirm(ConnectComm),
-    /// 0x0f
+    /// 0xf0
     DtData(DtData<F>),
+    /// 0x80
+    DisconnectRequest(DisconnectComm),
+    /// 0xc0, which carries no reason, the reason is decoded as 0
+    DisconnectConfirm(DisconnectComm),
 }
 
 impl<F: Debug + Eq + PartialEq> PduType<F> {
@@ -41,11 +139,46 @@ impl<F: Debug + Eq + PartialEq> PduType<F> {
             PduType::ConnectRequest(conn) => conn.length(),
             PduType::ConnectConfirm(conn) => conn.length(),
             PduType::DtData(_) => 2,
+            PduType::DisconnectRequest(_) => DisconnectComm::LENGTH,
+            PduType::DisconnectConfirm(_) => DisconnectComm::CONFIRM_LENGTH,
+        }
+    }
+
+    /// the value of the length indicator, which may exceed a u8
+    pub(crate) fn encoded_len(&self) -> usize {
+        match self {
+            PduType::ConnectRequest(conn) | PduType::ConnectConfirm(conn) => conn.encoded_len(),
+            _ => self.length() as usize,
         }
     }
+
+    /// the transport class of connect request/confirm
+    pub fn transport_class(&self) -> Option<TransportClass> {
+        match self {
+            PduType::ConnectRequest(conn) => Some(conn.transport_class()),
+            PduType::ConnectConfirm(conn) => Some(conn.transport_class()),
+            PduType::DtData(_) | PduType::DisconnectRequest(_) | PduType::DisconnectConfirm(_) => {
+                None
+            }
+        }
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.3.3
+#[derive(Debug, Clone, Copy, Eq, PartialEq, FromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum TransportClass {
+    Class0 = 0,
+    Class1 = 1,
+    Class2 = 2,
+    Class3 = 3,
+    Class4 = 4,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DtData<F: Debug + Eq + PartialEq> {
     pub(crate) tpdu_number: u8,
     pub(crate) last_data_unit: bool,
@@ -64,11 +197,27 @@ impl<F: Debug + Eq + PartialEq> DtData<F> {
     pub fn payload(self) -> F {
         self.payload
     }
+
+    /// transform the payload, the tpdu number and the last data unit are
+    /// kept
+    pub fn map_payload<G: Debug + Eq + PartialEq, Fun: FnOnce(F) -> G>(self, f: Fun) -> DtData<G> {
+        DtData {
+            tpdu_number: self.tpdu_number,
+            last_data_unit: self.last_data_unit,
+            payload: f(self.payload),
+        }
+    }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ConnectComm {
+    /// the initial credit (CDT) of the low nibble of the pdu type, 0 in
+    /// class 0 and 1
+    pub credit: u8,
+    #[cfg_attr(feature = "serde", serde(with = "hex"))]
     pub destination_ref: [u8; 2],
+    #[cfg_attr(feature = "serde", serde(with = "hex"))]
     pub source_ref: [u8; 2],
     pub class: u8,
     pub extended_formats: bool,
@@ -77,13 +226,34 @@ pub struct ConnectComm {
 }
 
 impl ConnectComm {
+    /// the max bytes of the source and destination tsap
+    pub const MAX_TSAP_LEN: usize = 16;
+
+    pub fn transport_class(&self) -> TransportClass {
+        TransportClass::from(self.class)
+    }
+
     pub fn length(&self) -> u8 {
-        6 + self.parameters.iter().fold(0, |x, item| x + item.length())
+        self.encoded_len() as u8
+    }
+
+    /// the value of the length indicator, which may exceed a u8
+    pub(crate) fn encoded_len(&self) -> usize {
+        6 + self
+            .parameters
+            .iter()
+            .map(Parameter::encoded_len)
+            .sum::<usize>()
     }
 
+    /// decode the bytes after the pdu type, the credit is left 0 for the
+    /// decoder to take it from the pdu type
     pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
         if src.len() < 5 {
-            return Err(Error::Other("data not enough".to_string()));
+            return Err(Error::InsufficientData {
+                needed: 5,
+                got: src.len(),
+            });
         }
 
         let destination_ref = [src.get_u8(), src.get_u8()];
@@ -93,19 +263,62 @@ impl ConnectComm {
         let extended_formats = merge << 6 >> 7 > 0;
         let no_explicit_flow_control = merge & 1 > 0;
 
-        let mut parameters = Vec::new();
-        while let Some(parameter) = Parameter::decode(src)? {
-            parameters.push(parameter);
-        }
+        let parameters = Parameter::decode_all(src)?;
 
-        Ok(Self {
+        let mut comm = Self {
+            credit: 0,
             destination_ref,
             source_ref,
             class,
             extended_formats,
             no_explicit_flow_control,
             parameters,
-        })
+        };
+        comm.deduplicate_parameters();
+        Ok(comm)
+    }
+
+    /// keep the last of the parameters of the same code, as most copt
+    /// implementations take the last one
+    pub fn deduplicate_parameters(&mut self) {
+        let codes: Vec<u8> = self.parameters.iter().map(Parameter::code).collect();
+        let mut index = 0;
+        self.parameters.retain(|_| {
+            index += 1;
+            !codes[index..].contains(&codes[index - 1])
+        });
+    }
+
+    /// check the class, that the tpdu size is given at most once and the
+    /// length of the tsaps
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |detail: String| Error::ProtocolError {
+            context: "connect comm",
+            detail,
+        };
+        if self.class > 0x0f {
+            return Err(invalid(format!("class {} exceeds 0x0f", self.class)));
+        }
+        let tpdu_sizes = self
+            .parameters
+            .iter()
+            .filter(|x| matches!(x, Parameter::TpduSize(_)))
+            .count();
+        if tpdu_sizes > 1 {
+            return Err(invalid(format!("{} tpdu size parameters", tpdu_sizes)));
+        }
+        for parameter in &self.parameters {
+            if let Parameter::SrcTsap(tsap) | Parameter::DstTsap(tsap) = parameter {
+                if tsap.len() > Self::MAX_TSAP_LEN {
+                    return Err(invalid(format!(
+                        "tsap of {} bytes exceeds {} bytes",
+                        tsap.len(),
+                        Self::MAX_TSAP_LEN
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     pub(crate) fn encode(&self, dst: &mut BytesMut) {
@@ -113,12 +326,75 @@ impl ConnectComm {
         dst.put_slice(self.source_ref.as_ref());
 
         let merge = self.class << 4
-            & if self.extended_formats { 2 } else { 0 }
-            & if self.no_explicit_flow_control { 1 } else { 0 };
+            | if self.extended_formats { 2 } else { 0 }
+            | if self.no_explicit_flow_control { 1 } else { 0 };
 
         dst.put_u8(merge);
 
-        self.parameters.iter().for_each(|x| x.encode(dst));
+        Parameter::encode_all(&self.parameters, dst);
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc905 13.5
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DisconnectComm {
+    #[cfg_attr(feature = "serde", serde(with = "hex"))]
+    pub destination_ref: [u8; 2],
+    #[cfg_attr(feature = "serde", serde(with = "hex"))]
+    pub source_ref: [u8; 2],
+    pub reason: u8,
+}
+
+impl DisconnectComm {
+    /// normal disconnect initiated by the session entity
+    pub const REASON_NORMAL: u8 = 0x80;
+    const LENGTH: u8 = 6;
+    const CONFIRM_LENGTH: u8 = 5;
+
+    /// the parameters of the variable part, e.g. the additional
+    /// information, are skipped
+    pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
+        if src.len() < 5 {
+            return Err(Error::InsufficientData {
+                needed: 5,
+                got: src.len(),
+            });
+        }
+        let comm = Self {
+            destination_ref: [src.get_u8(), src.get_u8()],
+            source_ref: [src.get_u8(), src.get_u8()],
+            reason: src.get_u8(),
+        };
+        src.clear();
+        Ok(comm)
+    }
+
+    /// the disconnect confirm has the references only
+    pub(crate) fn decode_confirm(src: &mut BytesMut) -> Result<Self> {
+        if src.len() < 4 {
+            return Err(Error::InsufficientData {
+                needed: 4,
+                got: src.len(),
+            });
+        }
+        let comm = Self {
+            destination_ref: [src.get_u8(), src.get_u8()],
+            source_ref: [src.get_u8(), src.get_u8()],
+            reason: 0,
+        };
+        src.clear();
+        Ok(comm)
+    }
+
+    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+        self.encode_confirm(dst);
+        dst.put_u8(self.reason);
+    }
+
+    pub(crate) fn encode_confirm(&self, dst: &mut BytesMut) {
+        dst.put_slice(self.destination_ref.as_ref());
+        dst.put_slice(self.source_ref.as_ref());
     }
 }
 
@@ -139,9 +415,10 @@ mod tests {
         assert_eq!(copt_frame.destination_ref, [0x00, 0x01]);
         assert_eq!(copt_frame.source_ref, [0x00, 0x02]);
         assert_eq!(copt_frame.class, 0);
-        assert_eq!(copt_frame.extended_formats, false);
-        assert_eq!(copt_frame.no_explicit_flow_control, false);
+        assert!(!copt_frame.extended_formats);
+        assert!(!copt_frame.no_explicit_flow_control);
         assert_eq!(copt_frame.parameters.len(), 2);
+        assert_eq!(copt_frame.clone(), copt_frame);
 
         let parameters = vec![
             Parameter::TpduSize(crate::TpduSize::L1024),
@@ -150,28 +427,167 @@ mod tests {
         assert_eq!(copt_frame.parameters, parameters);
     }
 
-    #[test]
-    fn test_unusual_copt_encode_decode() {
-        let mut data = BytesMut::new();
-        data.extend_from_slice(&[
+    /// the connect comm of a CPU 200 with the unknown 0x02 parameter
+    fn data_bytes() -> [u8; 16] {
+        [
             0x00, 0x01, 0x00, 0x02, 0x00, 0x02, 0x01, 0x01, 0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01,
             0x00, 0xc2,
-        ]);
+        ]
+    }
+
+    #[test]
+    fn test_unusual_copt_encode_decode() {
+        let mut data = BytesMut::from(data_bytes().as_ref());
 
         let copt_frame = ConnectComm::decode(&mut data).unwrap();
-        assert_eq!(copt_frame.length(), 13);
+        assert_eq!(copt_frame.length(), 16);
         assert_eq!(copt_frame.destination_ref, [0x00, 0x01]);
         assert_eq!(copt_frame.source_ref, [0x00, 0x02]);
         assert_eq!(copt_frame.class, 0);
-        assert_eq!(copt_frame.extended_formats, false);
-        assert_eq!(copt_frame.no_explicit_flow_control, false);
+        assert!(!copt_frame.extended_formats);
+        assert!(!copt_frame.no_explicit_flow_control);
         assert_eq!(copt_frame.parameters.len(), 3);
 
         let parameters = vec![
-            Parameter::Unknown,
+            Parameter::Unknown(vec![0x01]),
             Parameter::TpduSize(crate::TpduSize::L1024),
             Parameter::SrcTsap(vec![0x01, 0x00]),
         ];
         assert_eq!(copt_frame.parameters, parameters);
+
+        // the length counts the pdu type, which precedes the encoded bytes,
+        // the trailing 0xc2 is dropped
+        let mut buf = BytesMut::new();
+        copt_frame.encode(&mut buf);
+        assert_eq!(copt_frame.length() as usize, buf.len() + 1);
+        assert_eq!(buf.as_ref(), &data_bytes()[..15]);
+    }
+
+    #[test]
+    fn test_deduplicate_parameters() {
+        let mut data = BytesMut::from(
+            [
+                0x00, 0x01, 0x00, 0x02, 0x00, 0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01, 0x00, 0xc0, 0x01,
+                0x09,
+            ]
+            .as_ref(),
+        );
+        let comm = ConnectComm::decode(&mut data).unwrap();
+        assert_eq!(
+            comm.parameters,
+            vec![
+                Parameter::SrcTsap(vec![0x01, 0x00]),
+                Parameter::TpduSize(crate::TpduSize::L512),
+            ]
+        );
+        assert!(comm.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validate_connect_comm() {
+        let comm = || {
+            CoptFrame::<u8>::builder_of_connect()
+                .push_parameter(Parameter::new_tpdu_size(crate::TpduSize::L1024))
+                .push_parameter(Parameter::new_dst_tsap(vec![0; 16]))
+                .build_to_request()
+                .into_connect_request()
+                .unwrap()
+        };
+        assert!(comm().validate().is_ok());
+
+        let mut invalid = comm();
+        invalid.class = 0x10;
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = comm();
+        invalid
+            .parameters
+            .push(Parameter::new_tpdu_size(crate::TpduSize::L512));
+        assert!(invalid.validate().is_err());
+
+        let mut invalid = comm();
+        invalid
+            .parameters
+            .push(Parameter::new_src_tsap(vec![0; 17]));
+        let Err(Error::ProtocolError { detail, .. }) = invalid.validate() else {
+            unreachable!()
+        };
+        assert_eq!(detail, "tsap of 17 bytes exceeds 16 bytes");
+    }
+
+    #[test]
+    fn test_decode_class2_transport_class() {
+        let mut data = BytesMut::new();
+        data.extend_from_slice(&[
+            0x00, 0x01, 0x00, 0x02, 0x20, 0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01, 0x00,
+        ]);
+
+        let copt_frame = ConnectComm::decode(&mut data).unwrap();
+        assert_eq!(copt_frame.class, 2);
+        assert_eq!(copt_frame.transport_class(), TransportClass::Class2);
+
+        let pdu_type = PduType::<u8>::ConnectConfirm(copt_frame);
+        assert_eq!(pdu_type.transport_class(), Some(TransportClass::Class2));
+    }
+
+    #[test]
+    fn test_clone_copt_frame() {
+        let frame = CoptFrame::<u8>::builder_of_connect()
+            .source_ref([0, 1])
+            .push_parameter(Parameter::new_tpdu_size(crate::TpduSize::L1024))
+            .push_parameter(Parameter::new_src_tsap(vec![0x01, 0x00]))
+            .build_to_request();
+        assert_eq!(frame.clone(), frame);
+
+        let frame = CoptFrame::builder_of_dt_data(0x32u8).build(0, true);
+        let cloned = frame.clone();
+        assert_eq!(cloned, frame);
+        let PduType::DtData(dt_data) = cloned.pdu_type else {
+            unreachable!()
+        };
+        assert_eq!(dt_data.payload(), 0x32);
+    }
+
+    #[test]
+    fn test_frame_extractors() {
+        let frame = CoptFrame::<u8>::builder_of_connect()
+            .source_ref([0, 1])
+            .build_to_request();
+        assert!(frame.is_connect_request());
+        assert!(!frame.is_connect_confirm() && !frame.is_dt_data());
+        assert_eq!(frame.as_connect_request().unwrap().source_ref, [0, 1]);
+        assert!(frame.as_connect_confirm().is_none() && frame.as_dt_data().is_none());
+        assert_eq!(
+            frame.clone().into_connect_request().unwrap().source_ref,
+            [0, 1]
+        );
+        assert!(frame.into_connect_confirm().is_none());
+
+        let frame = CoptFrame::<u8>::builder_of_connect().build_to_confirm();
+        assert!(frame.is_connect_confirm());
+        assert!(frame.as_connect_confirm().is_some());
+        assert!(frame.into_connect_confirm().is_some());
+
+        let frame = CoptFrame::builder_of_dt_data(0x32u8).build(0, true);
+        assert!(frame.is_dt_data());
+        assert!(frame.as_dt_data().unwrap().last_data_unit());
+        assert!(frame.clone().into_connect_request().is_none());
+        assert_eq!(frame.into_dt_data().unwrap().payload(), 0x32);
+    }
+
+    #[test]
+    fn test_frame_map() {
+        let frame = CoptFrame::builder_of_dt_data(0x32u8).build(5, false);
+        let mapped = frame.map(|x| vec![x]);
+        let data = mapped.into_dt_data().unwrap();
+        assert_eq!(data.tpdu_number(), 5);
+        assert!(!data.last_data_unit());
+        assert_eq!(data.payload(), vec![0x32]);
+
+        let frame = CoptFrame::<u8>::disconnect_request([0, 1], [0, 2]);
+        assert_eq!(
+            frame.map(|x| x as u16),
+            CoptFrame::<u16>::disconnect_request([0, 1], [0, 2])
+        );
     }
 }
@@ -1,10 +1,11 @@
 use std::fmt::Debug;
 
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{BufMut, BytesMut};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::error::*;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum TpduSize {
@@ -31,8 +32,28 @@ impl TpduSize {
     }
 }
 
+/// The connection type of the first byte of the source tsap. The plc keeps
+/// a limited number of connections of each type and closes basic connections
+/// first when it runs out of resources.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ConnectionType {
+    /// programming device (Programmiergerät), allowed every service
+    /// including block upload/download and the cpu control functions
+    PG = 0x01,
+    /// operator panel, meant for hmi reading and writing of variables, a
+    /// protected cpu may refuse the programming services
+    #[default]
+    OP = 0x02,
+    /// generic data transfer, the lowest priority, only reading and
+    /// writing of variables
+    Basic = 0x03,
+}
+
 /// https://datatracker.ietf.org/doc/html/rfc905 13.3.4
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Parameter {
     /// 0xc0
     ///            0000 1101  8192 octets (not
@@ -47,12 +68,19 @@ pub enum Parameter {
     TpduSize(TpduSize),
     /// Source Reference
     /// 0xc1
-    SrcTsap(Vec<u8>),
+    SrcTsap(#[cfg_attr(feature = "serde", serde(with = "hex"))] Vec<u8>),
     /// Destination Reference
     /// 0xc2
-    DstTsap(Vec<u8>),
-    // unknown, 0x02
-    Unknown,
+    DstTsap(#[cfg_attr(feature = "serde", serde(with = "hex"))] Vec<u8>),
+    /// 0x02 of CPU 200, unknown, the data is kept to encode it back
+    Unknown(#[cfg_attr(feature = "serde", serde(with = "hex"))] Vec<u8>),
+    /// a parameter of any other code, e.g. the additional option 0xc6 of
+    /// some s7-1500 firmware, the data is kept to encode it back
+    Other {
+        code: u8,
+        #[cfg_attr(feature = "serde", serde(with = "hex"))]
+        data: Vec<u8>,
+    },
 }
 
 impl Parameter {
@@ -64,72 +92,126 @@ impl Parameter {
         Self::SrcTsap(data)
     }
 
+    /// destination tsap of a s7-300/400 cpu: the connection type 0x03
+    /// followed by `rack << 5 | slot`, rack is 0..=7 and slot is 0..=31.
+    /// s7-1200/1500 ignore rack and slot, use rack 0 and slot 0 or 1.
+    pub fn s7_dst_tsap(rack: u8, slot: u8) -> Result<Self> {
+        if rack > 0x07 || slot > 0x1f {
+            return Err(Error::ProtocolError {
+                context: "dst tsap",
+                detail: format!("rack {} or slot {} exceeds 7 or 31", rack, slot),
+            });
+        }
+        Ok(Self::DstTsap(vec![0x03, rack << 5 | slot]))
+    }
+
+    /// source tsap of the client: the connection type, 1: PG, 2: OP,
+    /// 3: basic, followed by 0x00
+    pub fn s7_src_tsap(connection_type: u8) -> Self {
+        Self::SrcTsap(vec![connection_type, 0x00])
+    }
+
+    /// source tsap of the client of the connection type
+    pub fn src_tsap_of(connection_type: ConnectionType) -> Self {
+        Self::s7_src_tsap(connection_type.into())
+    }
+
     pub fn new_tpdu_size(size: TpduSize) -> Self {
         Self::TpduSize(size)
     }
 
+    /// the parameter code, e.g. 0xc0 of the tpdu size
+    pub fn code(&self) -> u8 {
+        match self {
+            Parameter::TpduSize(_) => 0xc0,
+            Parameter::SrcTsap(_) => 0xc1,
+            Parameter::DstTsap(_) => 0xc2,
+            Parameter::Unknown(_) => 0x02,
+            Parameter::Other { code, .. } => *code,
+        }
+    }
+
     pub fn length(&self) -> u8 {
+        self.encoded_len() as u8
+    }
+
+    /// the length of the encoded parameter, which may exceed a u8
+    pub(crate) fn encoded_len(&self) -> usize {
         match self {
-            Parameter::TpduSize(_) => 3u8,
-            Parameter::SrcTsap(data) => 2 + data.len() as u8,
-            Parameter::DstTsap(data) => 2 + data.len() as u8,
-            Parameter::Unknown => 0,
+            Parameter::TpduSize(_) => 3,
+            Parameter::SrcTsap(data) => 2 + data.len(),
+            Parameter::DstTsap(data) => 2 + data.len(),
+            Parameter::Unknown(data) => 2 + data.len(),
+            Parameter::Other { data, .. } => 2 + data.len(),
         }
     }
 
-    pub(crate) fn decode(data: &mut BytesMut) -> Result<Option<Self>> {
+    pub fn decode(data: &mut BytesMut) -> Result<Option<Self>> {
         // NOTICE: CPU 200 碰到出现 0x02 参数码的机器, 0xc2 参数码在最末尾, 且没有参数数据
         if data.len() == 1 && data[0] == 0xc2 {
+            data.clear();
             return Ok(None);
         }
 
         // data is empty, parse done
-        if data.len() == 0 {
+        if data.is_empty() {
             return Ok(None);
         }
 
-        let (Some(parameter_code), Some(length)) = (data.get(0), data.get(1)) else {
-            return Err(Error::Other(
-                "decode parameter header data not enough".to_string(),
-            ));
+        // a single byte can't be a parameter, leave it to the length check of
+        // ConnectComm
+        let (Some(parameter_code), Some(length)) = (data.first(), data.get(1)) else {
+            return Ok(None);
         };
 
         let parameter_code = *parameter_code;
-        let length = (length + 2) as usize;
+        let length = *length as usize + 2;
         if data.len() < length {
-            return Err(Error::Other(format!(
-                "data.len={} need length={}, data not enough",
-                data.len(),
-                length
-            )));
+            return Err(Error::InsufficientData {
+                needed: length,
+                got: data.len(),
+            });
         }
 
-        let mut data = data.split_to(length).split_off(2);
+        let data = data.split_to(length).split_off(2);
 
         match parameter_code {
             0xc0 => {
-                let size = data.get_u8();
-                Ok(Some(Self::TpduSize(size.try_into()?)))
+                let Some(size) = data.first() else {
+                    return Err(Error::InsufficientData { needed: 1, got: 0 });
+                };
+                Ok(Some(Self::TpduSize((*size).try_into()?)))
             }
             0xc1 => Ok(Some(Self::SrcTsap(data.to_vec()))),
             0xc2 => Ok(Some(Self::DstTsap(data.to_vec()))),
-            // CPU 200. Unknown parameter type, skip it
-            0x02 => Ok(Some(Self::Unknown)),
-            _ => {
-                return Err(Error::Other(format!(
-                    "unknown parameter code: {}",
-                    parameter_code
-                )));
-            }
+            // CPU 200. Unknown parameter type
+            0x02 => Ok(Some(Self::Unknown(data.to_vec()))),
+            code => Ok(Some(Self::Other {
+                code,
+                data: data.to_vec(),
+            })),
         }
     }
 
-    pub(crate) fn encode(&self, dst: &mut BytesMut) {
+    /// decode the parameters until `src` is used up
+    pub fn decode_all(src: &mut BytesMut) -> Result<Vec<Self>> {
+        let mut parameters = Vec::new();
+        while let Some(parameter) = Self::decode(src)? {
+            parameters.push(parameter);
+        }
+        Ok(parameters)
+    }
+
+    pub fn encode_all(parameters: &[Self], dst: &mut BytesMut) {
+        parameters.iter().for_each(|x| x.encode(dst));
+    }
+
+    pub fn encode(&self, dst: &mut BytesMut) {
         match self {
             Parameter::TpduSize(data) => {
                 dst.put_u8(0xc0);
                 dst.put_u8(1u8);
-                dst.put_u8(data.clone().into())
+                dst.put_u8((*data).into())
             }
             Parameter::SrcTsap(data) => {
                 dst.put_u8(0xc1);
@@ -141,8 +223,15 @@ impl Parameter {
                 dst.put_u8(data.len() as u8);
                 dst.extend_from_slice(data.as_ref())
             }
-            Parameter::Unknown => {
-                // do nothing
+            Parameter::Unknown(data) => {
+                dst.put_u8(0x02);
+                dst.put_u8(data.len() as u8);
+                dst.extend_from_slice(data.as_ref())
+            }
+            Parameter::Other { code, data } => {
+                dst.put_u8(*code);
+                dst.put_u8(data.len() as u8);
+                dst.extend_from_slice(data.as_ref())
             }
         }
     }
@@ -159,20 +248,114 @@ mod tests {
         data.extend_from_slice(&[0x02, 0x01, 0x01]);
 
         let parameter = Parameter::decode(&mut data).unwrap().unwrap();
-        assert_eq!(parameter, Parameter::Unknown);
-        assert_eq!(parameter.length(), 0);
+        assert_eq!(parameter, Parameter::Unknown(vec![0x01]));
+        assert_eq!(parameter.length(), 3);
 
         let mut buf = BytesMut::new();
         parameter.encode(&mut buf);
-        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.as_ref(), [0x02, 0x01, 0x01]);
     }
 
     #[test]
     fn test_encode_unknown0x02_parameter() {
-        let parameter = Parameter::Unknown;
+        let parameter = Parameter::Unknown(Vec::new());
+        assert_eq!(parameter.length(), 2);
+        let mut buf = BytesMut::new();
+        parameter.encode(&mut buf);
+        assert_eq!(buf.as_ref(), [0x02, 0x00]);
+    }
+
+    #[test]
+    fn test_decode_encode_all() {
+        let bytes = [0xc0, 0x01, 0x0a, 0xc1, 0x02, 0x01, 0x00];
+        let mut data = BytesMut::from(bytes.as_ref());
+        let parameters = Parameter::decode_all(&mut data).unwrap();
+        assert_eq!(
+            parameters,
+            vec![
+                Parameter::TpduSize(TpduSize::L1024),
+                Parameter::SrcTsap(vec![0x01, 0x00]),
+            ]
+        );
+        assert!(data.is_empty());
+
+        let mut buf = BytesMut::new();
+        Parameter::encode_all(&parameters, &mut buf);
+        assert_eq!(buf.as_ref(), bytes.as_ref());
+
+        // the trailing 0xc2 of CPU 200
+        let mut data = BytesMut::from([0x02, 0x00, 0xc2].as_ref());
+        assert_eq!(
+            Parameter::decode_all(&mut data).unwrap(),
+            vec![Parameter::Unknown(Vec::new())]
+        );
+        assert!(Parameter::decode_all(&mut BytesMut::from([0xc1, 0x03].as_ref())).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_other_parameter() {
+        let bytes = [0xc6, 0x02, 0x01, 0x02];
+        let mut data = BytesMut::from(bytes.as_ref());
+
+        let parameter = Parameter::decode(&mut data).unwrap().unwrap();
+        assert_eq!(
+            parameter,
+            Parameter::Other {
+                code: 0xc6,
+                data: vec![0x01, 0x02]
+            }
+        );
+        assert_eq!(parameter.length(), 4);
+        assert!(data.is_empty());
+
         let mut buf = BytesMut::new();
         parameter.encode(&mut buf);
-        assert_eq!(buf.len(), 0);
+        assert_eq!(buf.as_ref(), bytes.as_ref());
+
+        // a parameter overrunning the buffer is still an error
+        let mut data = BytesMut::from([0xc6, 0x03, 0x01].as_ref());
+        assert!(matches!(
+            Parameter::decode(&mut data),
+            Err(Error::InsufficientData { needed: 5, got: 3 })
+        ));
+    }
+
+    #[test]
+    fn test_s7_tsap() {
+        assert_eq!(
+            Parameter::s7_dst_tsap(0, 2).unwrap(),
+            Parameter::DstTsap(vec![0x03, 0x02])
+        );
+        assert_eq!(
+            Parameter::s7_dst_tsap(0, 1).unwrap(),
+            Parameter::DstTsap(vec![0x03, 0x01])
+        );
+        assert_eq!(
+            Parameter::s7_dst_tsap(1, 3).unwrap(),
+            Parameter::DstTsap(vec![0x03, 0x23])
+        );
+        assert_eq!(
+            Parameter::s7_src_tsap(1),
+            Parameter::SrcTsap(vec![0x01, 0x00])
+        );
+
+        let mut buf = BytesMut::new();
+        Parameter::s7_dst_tsap(0, 2).unwrap().encode(&mut buf);
+        assert_eq!(buf.as_ref(), [0xc2, 0x02, 0x03, 0x02]);
+
+        // rack 8 would wrap to rack 0 of another cpu
+        assert_eq!(
+            Parameter::s7_dst_tsap(7, 31).unwrap(),
+            Parameter::DstTsap(vec![0x03, 0xff])
+        );
+        assert!(matches!(
+            Parameter::s7_dst_tsap(8, 2),
+            Err(Error::ProtocolError { .. })
+        ));
+        assert!(matches!(
+            Parameter::s7_dst_tsap(0, 32),
+            Err(Error::ProtocolError { .. })
+        ));
     }
 
     #[test]
@@ -182,6 +365,7 @@ mod tests {
 
         let parameter = Parameter::decode(&mut data).unwrap();
         assert_eq!(parameter, None);
+        assert!(data.is_empty());
     }
 
     #[test]
@@ -191,6 +375,7 @@ mod tests {
 
         let parameter = Parameter::decode(&mut data).unwrap().unwrap();
         assert_eq!(parameter, Parameter::TpduSize(TpduSize::L1024));
+        assert_eq!(parameter.clone(), parameter);
         assert_eq!(parameter.length(), 3);
 
         let mut buf = BytesMut::new();
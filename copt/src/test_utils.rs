@@ -0,0 +1,35 @@
+use std::io;
+
+use bytes::BytesMut;
+use tokio_util::codec::Decoder;
+
+use crate::error::{Error, ToCoptError};
+
+/// A payload decoder which never produces a frame, for tests and fuzzing
+/// of the copt layer alone.
+#[derive(Default)]
+pub struct NopDecoder;
+
+#[derive(Debug)]
+pub struct NopError(io::Error);
+
+impl From<io::Error> for NopError {
+    fn from(value: io::Error) -> Self {
+        Self(value)
+    }
+}
+
+impl ToCoptError for NopError {
+    fn to_err(self) -> Error {
+        Error::IoErr(self.0)
+    }
+}
+
+impl Decoder for NopDecoder {
+    type Item = ();
+    type Error = NopError;
+
+    fn decode(&mut self, _src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(None)
+    }
+}
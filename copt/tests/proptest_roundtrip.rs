@@ -0,0 +1,232 @@
+use bytes::BytesMut;
+use copt::{
+    error::Error, test_utils::NopError, ConnectComm, CoptDecoder, CoptEncoder, CoptFrame,
+    DisconnectComm, Parameter, PduType, TpduSize,
+};
+use proptest::prelude::*;
+use std::fmt::Debug;
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktEncoder, TpktFrame};
+
+/// payload codec of `()`, it takes no bytes
+#[derive(Default)]
+struct UnitCodec;
+
+impl Encoder<()> for UnitCodec {
+    type Error = NopError;
+
+    fn encode(&mut self, _item: (), _dst: &mut BytesMut) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Decoder for UnitCodec {
+    type Item = ();
+    type Error = NopError;
+
+    fn decode(&mut self, _src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(()))
+    }
+}
+
+/// payload codec of raw bytes, it takes the rest of the frame
+#[derive(Default)]
+struct BytesCodec;
+
+impl Encoder<Vec<u8>> for BytesCodec {
+    type Error = NopError;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Vec<u8>;
+    type Error = NopError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Ok(Some(src.split().to_vec()))
+    }
+}
+
+fn tpdu_size() -> impl Strategy<Value = TpduSize> {
+    prop_oneof![
+        Just(TpduSize::L8192),
+        Just(TpduSize::L4096),
+        Just(TpduSize::L2048),
+        Just(TpduSize::L1024),
+        Just(TpduSize::L512),
+        Just(TpduSize::L256),
+        Just(TpduSize::L128),
+    ]
+}
+
+fn parameter() -> impl Strategy<Value = Parameter> {
+    prop_oneof![
+        tpdu_size().prop_map(Parameter::new_tpdu_size),
+        prop::collection::vec(any::<u8>(), 0..8).prop_map(Parameter::new_src_tsap),
+        prop::collection::vec(any::<u8>(), 0..8).prop_map(Parameter::new_dst_tsap),
+        (
+            any::<u8>().prop_filter("modeled code", |x| ![0xc0, 0xc1, 0xc2, 0x02].contains(x)),
+            prop::collection::vec(any::<u8>(), 0..8)
+        )
+            .prop_map(|(code, data)| Parameter::Other { code, data }),
+    ]
+}
+
+prop_compose! {
+    fn connect_comm()(
+        credit in 0u8..16,
+        destination_ref in any::<[u8; 2]>(),
+        source_ref in any::<[u8; 2]>(),
+        class in 0u8..16,
+        extended_formats in any::<bool>(),
+        no_explicit_flow_control in any::<bool>(),
+        parameters in prop::collection::vec(parameter(), 0..4)
+    ) -> ConnectComm {
+        // the decoder keeps the last parameter of a code
+        let mut comm = ConnectComm {
+            credit,
+            destination_ref,
+            source_ref,
+            class,
+            extended_formats,
+            no_explicit_flow_control,
+            parameters,
+        };
+        comm.deduplicate_parameters();
+        comm
+    }
+}
+
+/// the frames which survive a round trip: the decoder keeps the last
+/// connect parameter of a code and disconnect confirm carries no reason, so
+/// the generated frames have neither duplicated codes nor a reason
+fn copt_frame<F: Debug + Eq + PartialEq + Clone + 'static>(
+    payload: impl Strategy<Value = F> + 'static,
+) -> impl Strategy<Value = CoptFrame<F>> {
+    prop_oneof![
+        connect_comm().prop_map(|comm| CoptFrame {
+            pdu_type: PduType::ConnectRequest(comm)
+        }),
+        connect_comm().prop_map(|comm| CoptFrame {
+            pdu_type: PduType::ConnectConfirm(comm)
+        }),
+        (payload, 0u8..128, any::<bool>()).prop_map(|(payload, tpdu_number, last_data_unit)| {
+            CoptFrame::builder_of_dt_data(payload).build(tpdu_number, last_data_unit)
+        }),
+        (any::<[u8; 2]>(), any::<[u8; 2]>(), any::<u8>()).prop_map(
+            |(destination_ref, source_ref, reason)| CoptFrame {
+                pdu_type: PduType::DisconnectRequest(DisconnectComm {
+                    destination_ref,
+                    source_ref,
+                    reason,
+                })
+            }
+        ),
+        (any::<[u8; 2]>(), any::<[u8; 2]>()).prop_map(|(destination_ref, source_ref)| {
+            CoptFrame::disconnect_confirm(destination_ref, source_ref)
+        }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        max_shrink_iters: 256,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn encode_decode_roundtrip(frame in copt_frame(Just(()))) {
+        let mut dst = BytesMut::new();
+        CoptEncoder(UnitCodec).encode(frame.clone(), &mut dst).unwrap();
+        prop_assert_eq!(dst.len(), frame.length() as usize + 1);
+
+        let decoded = CoptDecoder::new(UnitCodec).decode(&mut dst).unwrap();
+        prop_assert_eq!(decoded, Some(frame));
+        prop_assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn tpkt_encode_decode_roundtrip(
+        frame in copt_frame(prop::collection::vec(any::<u8>(), 0..64))
+    ) {
+        let mut dst = BytesMut::new();
+        TpktEncoder(CoptEncoder(BytesCodec))
+            .encode(TpktFrame::new(frame.clone()), &mut dst)
+            .unwrap();
+
+        let decoded = TpktDecoder::new(CoptDecoder::new(BytesCodec)).decode(&mut dst).unwrap();
+        prop_assert_eq!(decoded, Some(TpktFrame::new(frame)));
+        prop_assert!(dst.is_empty());
+    }
+
+    #[test]
+    fn parameter_encode_decode_roundtrip(parameter in parameter()) {
+        let mut dst = BytesMut::new();
+        parameter.encode(&mut dst);
+        prop_assert_eq!(dst.len(), parameter.length() as usize);
+
+        let decoded = Parameter::decode(&mut dst).unwrap();
+        prop_assert_eq!(decoded, Some(parameter));
+        prop_assert!(dst.is_empty());
+    }
+}
+
+#[test]
+fn connect_credit_roundtrip() {
+    // connect request and confirm with the credit 3 and 15
+    for bytes in [
+        [0x06, 0xe3, 0x00, 0x00, 0x00, 0x01, 0x00],
+        [0x06, 0xdf, 0x00, 0x01, 0x00, 0x02, 0x00],
+    ] {
+        let mut src = BytesMut::from(bytes.as_ref());
+        let frame = CoptDecoder::new(UnitCodec)
+            .decode(&mut src)
+            .unwrap()
+            .unwrap();
+        let (PduType::ConnectRequest(comm) | PduType::ConnectConfirm(comm)) = &frame.pdu_type
+        else {
+            unreachable!()
+        };
+        assert_eq!(comm.credit, bytes[1] & 0x0f);
+
+        let mut dst = BytesMut::new();
+        CoptEncoder(UnitCodec).encode(frame, &mut dst).unwrap();
+        assert_eq!(dst.as_ref(), bytes.as_ref());
+    }
+}
+
+#[test]
+fn connect_exceeding_length_indicator() {
+    // 6 header bytes and 25 parameters of 10 bytes exceed the u8 length
+    let frame = (0..25)
+        .fold(CoptFrame::<()>::builder_of_connect(), |builder, _| {
+            builder.push_parameter(Parameter::SrcTsap(vec![0; 8]))
+        })
+        .build_to_request();
+    let mut dst = BytesMut::new();
+    let err = CoptEncoder(UnitCodec).encode(frame, &mut dst).unwrap_err();
+    assert!(matches!(err, Error::FrameTooLong { length: 256 }));
+    assert!(dst.is_empty());
+}
+
+#[test]
+fn connect_invalid_not_encoded() {
+    for builder in [
+        CoptFrame::<()>::builder_of_connect().class_and_others(0x10, false, false),
+        CoptFrame::builder_of_connect()
+            .push_parameter(Parameter::new_tpdu_size(TpduSize::L1024))
+            .push_parameter(Parameter::new_tpdu_size(TpduSize::L512)),
+        CoptFrame::builder_of_connect().push_parameter(Parameter::new_dst_tsap(vec![0; 17])),
+    ] {
+        let mut dst = BytesMut::new();
+        let err = CoptEncoder(UnitCodec)
+            .encode(builder.build_to_request(), &mut dst)
+            .unwrap_err();
+        assert!(matches!(err, Error::ProtocolError { .. }));
+        assert!(dst.is_empty());
+    }
+}
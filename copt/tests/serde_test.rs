@@ -0,0 +1,29 @@
+#![cfg(feature = "serde")]
+
+use copt::{ConnectComm, Parameter, TpduSize};
+
+#[test]
+fn connect_comm_roundtrip() {
+    let comm = ConnectComm {
+        credit: 0,
+        destination_ref: [0, 0],
+        source_ref: [0, 1],
+        class: 0,
+        extended_formats: false,
+        no_explicit_flow_control: false,
+        parameters: vec![
+            Parameter::TpduSize(TpduSize::L1024),
+            Parameter::new_src_tsap(vec![0x01, 0x00]),
+            Parameter::new_dst_tsap(vec![0x01, 0x02]),
+            Parameter::Other {
+                code: 0xc6,
+                data: vec![0xab],
+            },
+        ],
+    };
+    let json = serde_json::to_string(&comm).unwrap();
+    assert!(json.contains(r#""source_ref":"0001""#));
+    assert!(json.contains(r#"{"SrcTsap":"0100"}"#));
+    assert!(json.contains(r#""data":"ab""#));
+    assert_eq!(serde_json::from_str::<ConnectComm>(&json).unwrap(), comm);
+}
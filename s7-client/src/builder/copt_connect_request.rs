@@ -65,6 +65,7 @@ impl CoptConnectRequestBuilder {
         } = self;
         let frame = tpkt::TpktFrame::new(CoptFrame {
             pdu_type: PduType::ConnectRequest(ConnectComm {
+                credit: 0,
                 destination_ref,
                 source_ref,
                 class,
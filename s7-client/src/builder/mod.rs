@@ -3,8 +3,8 @@ mod s7_write;
 use crate::builder::copt_connect_request::CoptConnectRequestBuilder;
 use crate::builder::s7_setup::S7SetupBuilder;
 
-use self::s7_read::S7ReadBuilder;
-use self::s7_write::S7WriteBuilder;
+pub use self::s7_read::S7ReadBuilder;
+pub use self::s7_write::S7WriteBuilder;
 
 mod copt_connect_request;
 mod s7_setup;
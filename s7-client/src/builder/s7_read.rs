@@ -1,14 +1,22 @@
-use crate::{codec::S7Encoder, error::*};
+use crate::{
+    codec::S7Encoder, db_number, error::*, job_size, max_read_item_len, read_item_size,
+    split_by_pdu, ReadValueType, ISO_HEAD_LEN, MAX_ITEMS_PER_JOB,
+};
 use bytes::BytesMut;
 use copt::CoptFrame;
-use s7_comm::ItemRequest;
+use s7_comm::{DataItemVal, ItemRequest, TransportSize};
 use tokio_util::codec::Encoder;
 use tpkt::TpktFrame;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct S7ReadBuilder {
     pdu_ref: u16,
     items: Vec<ItemRequest>,
+    /// how to decode the data item of every item
+    value_types: Vec<ReadValueType>,
+    pdu_size_limit: Option<u16>,
+    /// the max gap of bytes between the merged items of `optimize`
+    max_gap: Option<u16>,
 }
 
 impl S7ReadBuilder {
@@ -17,15 +25,145 @@ impl S7ReadBuilder {
         self
     }
 
-    pub fn add_item(mut self, item: ItemRequest) -> Self {
+    pub fn add_item(self, item: ItemRequest) -> Self {
+        self.add_typed_item(item, ReadValueType::Bytes)
+    }
+
+    /// read a BOOL of a db
+    pub fn add_db_bool(self, db_number: u16, byte_addr: u16, bit_addr: u8) -> Self {
+        self.add_typed_item(
+            ItemRequest::init_db_bit(db_number, byte_addr, bit_addr),
+            ReadValueType::Bool,
+        )
+    }
+
+    /// read a INT of a db
+    pub fn add_db_int(self, db_number: u16, byte_addr: u16) -> Self {
+        self.add_typed_item(
+            ItemRequest::init_db_byte(db_number, byte_addr, 2),
+            ReadValueType::Int,
+        )
+    }
+
+    /// read a REAL of a db
+    pub fn add_db_real(self, db_number: u16, byte_addr: u16) -> Self {
+        self.add_typed_item(
+            ItemRequest::init_db_byte(db_number, byte_addr, 4),
+            ReadValueType::Real,
+        )
+    }
+
+    fn add_typed_item(mut self, item: ItemRequest, value_type: ReadValueType) -> Self {
         self.items.push(item);
+        self.value_types.push(value_type);
+        self
+    }
+
+    pub(crate) fn value_types(&self) -> Vec<ReadValueType> {
+        self.value_types.clone()
+    }
+
+    /// Merge the byte and bit items of the same area and db whose ranges
+    /// touch, overlap or are apart by at most `max_gap` bytes into one wider
+    /// item, `S7Client::read_values` slices the data of every item out of the
+    /// merged item. A merged item fits in the response of the pdu size limit.
+    pub fn optimize(mut self, max_gap: u16) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    /// the items which are sent and where the data of every item of the
+    /// builder is within them
+    pub(crate) fn merged_items(&self) -> (Vec<ItemRequest>, Vec<ItemSlice>) {
+        match self.max_gap {
+            Some(max_gap) => {
+                let max_len = self
+                    .pdu_size_limit
+                    .map_or(u16::MAX as usize, max_read_item_len);
+                merge_items(&self.items, max_gap, max_len)
+            }
+            None => (
+                self.items.clone(),
+                (0..self.items.len()).map(ItemSlice::Whole).collect(),
+            ),
+        }
+    }
+
+    /// the builder of the items of `indices`, which are sent unmerged
+    pub(crate) fn select(&self, indices: &[usize]) -> Self {
+        Self {
+            pdu_ref: self.pdu_ref,
+            items: indices.iter().map(|x| self.items[*x].clone()).collect(),
+            value_types: indices.iter().map(|x| self.value_types[*x]).collect(),
+            pdu_size_limit: self.pdu_size_limit,
+            max_gap: None,
+        }
+    }
+
+    /// `build` fails with `Error::FrameTooLarge` if the s7 pdu of the
+    /// request or of the expected response exceeds `max_pdu`
+    pub fn with_pdu_size_limit(mut self, max_pdu: u16) -> Self {
+        self.pdu_size_limit = Some(max_pdu);
         self
     }
 
+    /// the bytes of the encoded frame, with the tpkt and copt header
+    pub fn estimated_pdu_size(&self) -> usize {
+        ISO_HEAD_LEN + s7_pdu_size(&self.merged_items().0).0
+    }
+
+    /// Split the items in order into the minimum builders whose request and
+    /// expected response fit in `max_pdu`. An item which doesn't fit in
+    /// `max_pdu` alone gets a builder of its own. The builders share the pdu
+    /// ref.
+    pub fn split_by_pdu_size(self, max_pdu: u16) -> Vec<S7ReadBuilder> {
+        let Self {
+            pdu_ref,
+            items,
+            value_types,
+            pdu_size_limit,
+            max_gap,
+        } = self;
+        let sizes: Vec<_> = items.iter().map(read_item_size).collect();
+        let (mut items, mut value_types) = (items.into_iter(), value_types.into_iter());
+        split_by_pdu(&sizes, max_pdu)
+            .into_iter()
+            .map(|batch| Self {
+                pdu_ref,
+                items: items.by_ref().take(batch.len()).collect(),
+                value_types: value_types.by_ref().take(batch.len()).collect(),
+                pdu_size_limit,
+                max_gap,
+            })
+            .collect()
+    }
+
+    /// split by `max_pdu` and build every frame with the limit of `max_pdu`
+    pub fn build_split(self, max_pdu: u16) -> Result<Vec<BytesMut>> {
+        self.split_by_pdu_size(max_pdu)
+            .into_iter()
+            .map(|x| x.with_pdu_size_limit(max_pdu).build())
+            .collect()
+    }
+
     pub fn build(self) -> Result<BytesMut> {
+        let (items, _) = self.merged_items();
+        if items.len() > MAX_ITEMS_PER_JOB {
+            return Err(Error::TooManyItems {
+                limit: MAX_ITEMS_PER_JOB,
+                actual: items.len(),
+            });
+        }
+        if let Some(limit) = self.pdu_size_limit {
+            let (request, response) = s7_pdu_size(&items);
+            let actual = request.max(response);
+            if actual > limit as usize {
+                return Err(Error::FrameTooLarge { limit, actual });
+            }
+        }
         let mut read_builder = s7_comm::Frame::job_read_var(self.pdu_ref);
 
-        for item in self.items {
+        for item in items {
             read_builder = read_builder.add_item(item);
         }
         let frame =
@@ -36,3 +174,326 @@ impl S7ReadBuilder {
         Ok(dst)
     }
 }
+
+/// the bytes of the s7 pdu of the request and of the expected response
+fn s7_pdu_size(items: &[ItemRequest]) -> (usize, usize) {
+    job_size(items.iter().map(read_item_size))
+}
+
+/// where the data of an item of the builder is within the items sent
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum ItemSlice {
+    /// the item is sent as it is
+    Whole(usize),
+    /// the bytes from `offset` of the merged item
+    Bytes {
+        index: usize,
+        offset: usize,
+        len: usize,
+    },
+    /// the bit of the byte at `offset` of the merged item
+    Bit {
+        index: usize,
+        offset: usize,
+        bit: u8,
+    },
+}
+
+impl ItemSlice {
+    /// the data item of the item out of the data items of the response,
+    /// `None` if the merged item failed, its items are to be read unmerged
+    /// for the return code of every item
+    pub(crate) fn extract(&self, items: &[DataItemVal]) -> Result<Option<DataItemVal>> {
+        let (index, offset, len) = match *self {
+            Self::Whole(index) => (index, 0, None),
+            Self::Bytes { index, offset, len } => (index, offset, Some(len)),
+            Self::Bit { index, offset, .. } => (index, offset, Some(1)),
+        };
+        let Some(item) = items.get(index) else {
+            return Err(Error::Err(format!("should recv read var item {}", index)));
+        };
+        let Some(len) = len else {
+            return Ok(Some(item.clone()));
+        };
+        if !item.return_code.is_success() {
+            return Ok(None);
+        }
+        let Some(data) = item.data.get(offset..offset + len) else {
+            return Err(Error::Err(format!(
+                "merged read var item {} of {} bytes lacks the bytes {}..{}",
+                index,
+                item.data.len(),
+                offset,
+                offset + len
+            )));
+        };
+        Ok(Some(match *self {
+            Self::Bit { bit, .. } => DataItemVal::init_with_bit(
+                item.return_code.clone(),
+                data[0] >> (bit & 0x07) & 1 == 1,
+            ),
+            _ => DataItemVal::init_with_bytes(item.return_code.clone(), data),
+        }))
+    }
+}
+
+/// the byte range of a byte or bit item, other items aren't merged
+fn byte_range(item: &ItemRequest) -> Option<(usize, usize)> {
+    let start = item.byte_addr() as usize;
+    match item.transport_size_type() {
+        TransportSize::NoBit => Some((start, start + item.length() as usize)),
+        TransportSize::Bit => Some((start, start + 1)),
+        _ => None,
+    }
+}
+
+/// Merge the byte and bit items of the same area and db which are apart by
+/// at most `max_gap` bytes into items of at most `max_len` bytes. The merged
+/// items are in the order of their first item, an item merged with no other
+/// is kept as it is.
+fn merge_items(
+    items: &[ItemRequest],
+    max_gap: u16,
+    max_len: usize,
+) -> (Vec<ItemRequest>, Vec<ItemSlice>) {
+    let key = |index: usize| {
+        (
+            u8::from(items[index].area().clone()),
+            items[index].db_number(),
+        )
+    };
+    let mut ranges: Vec<_> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| byte_range(item).map(|(start, end)| (index, start, end)))
+        .collect();
+    ranges.sort_by_key(|(index, start, _)| (key(*index), *start));
+
+    // the items of every merged item and its byte range
+    let mut groups: Vec<(Vec<usize>, usize, usize)> = Vec::new();
+    for (index, start, end) in ranges {
+        match groups.last_mut() {
+            Some((group, group_start, group_end))
+                if key(group[0]) == key(index)
+                    && start <= *group_end + max_gap as usize
+                    && end.max(*group_end) - *group_start <= max_len =>
+            {
+                group.push(index);
+                *group_end = end.max(*group_end);
+            }
+            _ => groups.push((vec![index], start, end)),
+        }
+    }
+    groups.extend(
+        (0..items.len())
+            .filter(|x| byte_range(&items[*x]).is_none())
+            .map(|x| (vec![x], 0, 0)),
+    );
+    groups.sort_by_key(|(group, ..)| group.iter().min().copied());
+
+    let mut requests = Vec::with_capacity(groups.len());
+    let mut slices = vec![ItemSlice::Whole(0); items.len()];
+    for (merged, (group, start, end)) in groups.into_iter().enumerate() {
+        if let [index] = group[..] {
+            requests.push(items[index].clone());
+            slices[index] = ItemSlice::Whole(merged);
+            continue;
+        }
+        let first = &items[group[0]];
+        requests.push(ItemRequest::init_byte(
+            db_number(first.area(), first.db_number()),
+            first.area().clone(),
+            start as u16,
+            (end - start) as u16,
+        ));
+        for index in group {
+            let item = &items[index];
+            let offset = item.byte_addr() as usize - start;
+            slices[index] = match item.transport_size_type() {
+                TransportSize::Bit => ItemSlice::Bit {
+                    index: merged,
+                    offset,
+                    bit: item.bit_addr(),
+                },
+                _ => ItemSlice::Bytes {
+                    index: merged,
+                    offset,
+                    len: item.length() as usize,
+                },
+            };
+        }
+    }
+    (requests, slices)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ItemSlice, S7ReadBuilder};
+    use crate::Error;
+    use s7_comm::{DataItemVal, ItemRequest, ReturnCode};
+
+    #[test]
+    fn check_split_by_pdu_size() {
+        let builder = (0..5u16).fold(S7ReadBuilder::default(), |builder, x| {
+            builder.add_item(ItemRequest::init_db_byte(1, x * 100, 100))
+        });
+        assert_eq!(builder.estimated_pdu_size(), 7 + 12 + 5 * 12);
+
+        // the response of two items: 14 + 2 * (4 + 100)
+        let builders = builder.split_by_pdu_size(240);
+        assert_eq!(
+            builders.iter().map(|x| x.items.len()).collect::<Vec<_>>(),
+            [2, 2, 1]
+        );
+        assert_eq!(builders[2].items[0].byte_addr(), 400);
+
+        let builder = (0..25u16).fold(S7ReadBuilder::default(), |builder, x| {
+            builder.add_item(ItemRequest::init_db_bit(1, x, 0))
+        });
+        let frames = builder.build_split(960).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].len(), 7 + 12 + 20 * 12);
+
+        let builder = (0..21u16).fold(S7ReadBuilder::default(), |builder, x| {
+            builder.add_db_int(1, x * 2)
+        });
+        assert!(matches!(
+            builder.build(),
+            Err(Error::TooManyItems {
+                limit: 20,
+                actual: 21
+            })
+        ));
+
+        assert!(matches!(
+            S7ReadBuilder::default()
+                .add_item(ItemRequest::init_db_byte(1, 0, 300))
+                .build_split(240),
+            Err(Error::FrameTooLarge {
+                limit: 240,
+                actual: 318
+            })
+        ));
+    }
+
+    #[test]
+    fn check_optimize() {
+        // touching, overlapping and a bit within the merged range, DB2 and
+        // the counter are kept
+        let builder = S7ReadBuilder::default()
+            .add_db_int(1, 2)
+            .add_item(ItemRequest::init_db_byte(1, 0, 2))
+            .add_db_real(1, 3)
+            .add_db_bool(1, 6, 1)
+            .add_db_int(2, 0)
+            .add_item(ItemRequest::init_counter(3, 1))
+            .optimize(0);
+        let (items, slices) = builder.merged_items();
+        assert_eq!(
+            items,
+            [
+                ItemRequest::init_db_byte(1, 0, 7),
+                ItemRequest::init_db_byte(2, 0, 2),
+                ItemRequest::init_counter(3, 1),
+            ]
+        );
+        assert_eq!(
+            slices,
+            [
+                ItemSlice::Bytes {
+                    index: 0,
+                    offset: 2,
+                    len: 2
+                },
+                ItemSlice::Bytes {
+                    index: 0,
+                    offset: 0,
+                    len: 2
+                },
+                ItemSlice::Bytes {
+                    index: 0,
+                    offset: 3,
+                    len: 4
+                },
+                ItemSlice::Bit {
+                    index: 0,
+                    offset: 6,
+                    bit: 1
+                },
+                ItemSlice::Whole(1),
+                ItemSlice::Whole(2),
+            ]
+        );
+        assert_eq!(builder.estimated_pdu_size(), 7 + 12 + 3 * 12);
+
+        // the gap of 2 bytes between 0..2 and 4..6
+        let builder = S7ReadBuilder::default().add_db_int(1, 0).add_db_int(1, 4);
+        let (items, _) = builder.clone().optimize(1).merged_items();
+        assert_eq!(items.len(), 2);
+        let (items, slices) = builder.clone().optimize(2).merged_items();
+        assert_eq!(items, [ItemRequest::init_db_byte(1, 0, 6)]);
+        assert_eq!(
+            slices[1],
+            ItemSlice::Bytes {
+                index: 0,
+                offset: 4,
+                len: 2
+            }
+        );
+        assert_eq!(
+            builder.merged_items().1,
+            [ItemSlice::Whole(0), ItemSlice::Whole(1)]
+        );
+
+        // a merged item fits in the response of the pdu size limit
+        let builder = S7ReadBuilder::default()
+            .add_item(ItemRequest::init_db_byte(1, 0, 100))
+            .add_item(ItemRequest::init_db_byte(1, 150, 100))
+            .optimize(100);
+        assert_eq!(builder.merged_items().0.len(), 1);
+        assert_eq!(builder.with_pdu_size_limit(240).merged_items().0.len(), 2);
+    }
+
+    #[test]
+    fn check_item_slice_extract() {
+        let merged = [
+            DataItemVal::init_with_bytes(ReturnCode::Success, &[0x01, 0x02, 0x04]),
+            DataItemVal::init_with_bytes(ReturnCode::InvalidAddress, &[]),
+        ];
+        let slice = ItemSlice::Bytes {
+            index: 0,
+            offset: 1,
+            len: 2,
+        };
+        assert_eq!(
+            slice.extract(&merged).unwrap(),
+            Some(DataItemVal::init_with_bytes(
+                ReturnCode::Success,
+                &[0x02, 0x04]
+            ))
+        );
+        let slice = ItemSlice::Bit {
+            index: 0,
+            offset: 2,
+            bit: 2,
+        };
+        assert!(slice.extract(&merged).unwrap().unwrap().as_bool().unwrap());
+        assert_eq!(
+            ItemSlice::Whole(1).extract(&merged).unwrap(),
+            Some(merged[1].clone())
+        );
+        let slice = ItemSlice::Bytes {
+            index: 1,
+            offset: 0,
+            len: 2,
+        };
+        assert_eq!(slice.extract(&merged).unwrap(), None);
+        let slice = ItemSlice::Bytes {
+            index: 0,
+            offset: 2,
+            len: 2,
+        };
+        assert!(slice.extract(&merged).is_err());
+        assert!(ItemSlice::Whole(2).extract(&merged).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{codec::S7Encoder, error::*};
+use crate::{codec::S7Encoder, error::*, job_size, split_by_pdu, write_item_size, ISO_HEAD_LEN};
 use bytes::BytesMut;
 use copt::CoptFrame;
 use s7_comm::{Area, DataItemVal, ItemRequest, ReturnCode};
@@ -9,18 +9,22 @@ use tpkt::TpktFrame;
 pub struct S7WriteBuilder {
     pdu_ref: u16,
     items: Vec<(ItemRequest, DataItemVal)>,
+    pdu_size_limit: Option<u16>,
 }
 impl S7WriteBuilder {
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn pdu_ref(mut self, pdu_ref: u16) -> Self {
         self.pdu_ref = pdu_ref;
         self
     }
 
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     fn add_item(mut self, item: (ItemRequest, DataItemVal)) -> Self {
         self.items.push(item);
         self
     }
 
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn write_bytes(
         self,
         db_number: Option<u16>,
@@ -33,6 +37,7 @@ impl S7WriteBuilder {
         self.add_item((req, data_val))
     }
 
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
     pub fn write_bit(
         self,
         db_number: Option<u16>,
@@ -46,7 +51,143 @@ impl S7WriteBuilder {
         self.add_item((req, data_val))
     }
 
+    /// write a WORD of a db, big-endian
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_word(self, db_number: u16, byte_addr: u16, value: u16) -> Self {
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, 2);
+        let data_val = DataItemVal::init_with_word(ReturnCode::Reserved, value);
+        self.add_item((req, data_val))
+    }
+
+    /// write a DWORD of a db, big-endian
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_dword(self, db_number: u16, byte_addr: u16, value: u32) -> Self {
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, 4);
+        let data_val = DataItemVal::init_with_dword(ReturnCode::Reserved, value);
+        self.add_item((req, data_val))
+    }
+
+    /// write a INT of a db, big-endian
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_int(self, db_number: u16, byte_addr: u16, value: i16) -> Self {
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, 2);
+        let data_val = DataItemVal::init_with_int(ReturnCode::Reserved, value);
+        self.add_item((req, data_val))
+    }
+
+    /// write a DINT of a db, big-endian
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_dint(self, db_number: u16, byte_addr: u16, value: i32) -> Self {
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, 4);
+        let data_val = DataItemVal::init_with_dint(ReturnCode::Reserved, value);
+        self.add_item((req, data_val))
+    }
+
+    /// write a REAL of a db, big-endian
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_real(self, db_number: u16, byte_addr: u16, value: f32) -> Self {
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, 4);
+        let data_val = DataItemVal::init_with_real(ReturnCode::Reserved, value);
+        self.add_item((req, data_val))
+    }
+
+    /// write a STRING of `max_len` chars of a db, the header and the chars of
+    /// `value`, see `DataItemVal::init_with_s7_string`
+    pub fn write_db_string(
+        self,
+        db_number: u16,
+        byte_addr: u16,
+        max_len: u8,
+        value: &str,
+    ) -> Result<Self> {
+        let data_val = DataItemVal::init_with_s7_string(ReturnCode::Reserved, value, max_len)?;
+        let req = ItemRequest::init_db_byte(db_number, byte_addr, data_val.data.len() as u16);
+        Ok(self.add_item((req, data_val)))
+    }
+
+    /// see `s7_comm::FrameJobWriteVarBuilder::write_db_masked`
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn write_db_masked(
+        self,
+        db_number: u16,
+        byte_addr: u16,
+        and_mask: u8,
+        or_mask: u8,
+    ) -> Self {
+        (0..8u8).fold(self, |builder, bit_addr| {
+            let bit = 1 << bit_addr;
+            if or_mask & bit > 0 || and_mask & bit == 0 {
+                builder.write_bit(
+                    Some(db_number),
+                    Area::DataBlocks,
+                    byte_addr,
+                    bit_addr,
+                    or_mask & bit > 0,
+                )
+            } else {
+                builder
+            }
+        })
+    }
+
+    /// `build` fails with `Error::FrameTooLarge` if the s7 pdu exceeds
+    /// `max_pdu`
+    #[must_use = "builder methods return a new builder; the old builder is consumed"]
+    pub fn with_pdu_size_limit(mut self, max_pdu: u16) -> Self {
+        self.pdu_size_limit = Some(max_pdu);
+        self
+    }
+
+    /// the bytes of the encoded frame, with the tpkt and copt header
+    pub fn estimated_pdu_size(&self) -> usize {
+        ISO_HEAD_LEN + self.s7_pdu_size()
+    }
+
+    fn s7_pdu_size(&self) -> usize {
+        let (request, _) = job_size(self.items.iter().map(|x| write_item_size(x.1.data.len())));
+        // no fill byte after the last data item
+        request - self.items.last().map_or(0, |x| x.1.data.len() % 2)
+    }
+
+    /// Split the items in order into the minimum builders whose request and
+    /// response fit in `max_pdu`. An item which doesn't fit in `max_pdu`
+    /// alone gets a builder of its own. The builders share the pdu ref.
+    pub fn split_by_pdu_size(self, max_pdu: u16) -> Vec<S7WriteBuilder> {
+        let Self {
+            pdu_ref,
+            items,
+            pdu_size_limit,
+        } = self;
+        let sizes: Vec<_> = items
+            .iter()
+            .map(|x| write_item_size(x.1.data.len()))
+            .collect();
+        let mut items = items.into_iter();
+        split_by_pdu(&sizes, max_pdu)
+            .into_iter()
+            .map(|batch| Self {
+                pdu_ref,
+                items: items.by_ref().take(batch.len()).collect(),
+                pdu_size_limit,
+            })
+            .collect()
+    }
+
+    /// split by `max_pdu` and build every frame with the limit of `max_pdu`
+    pub fn build_split(self, max_pdu: u16) -> Result<Vec<BytesMut>> {
+        self.split_by_pdu_size(max_pdu)
+            .into_iter()
+            .map(|x| x.with_pdu_size_limit(max_pdu).build())
+            .collect()
+    }
+
     pub fn build(self) -> Result<BytesMut> {
+        if let Some(limit) = self.pdu_size_limit {
+            let actual = self.s7_pdu_size();
+            if actual > limit as usize {
+                return Err(Error::FrameTooLarge { limit, actual });
+            }
+        }
         let mut write_builder = s7_comm::Frame::job_write_var(self.pdu_ref);
 
         for item in self.items {
@@ -60,3 +201,124 @@ impl S7WriteBuilder {
         Ok(dst)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::S7WriteBuilder;
+    use crate::Error;
+    use s7_comm::Area;
+
+    /// tpkt 4 bytes, copt 3 bytes, s7 header 10 bytes, function and count
+    const ITEM_OFFSET: usize = 19;
+
+    /// the item request and the data item of a single item write
+    fn encode(builder: S7WriteBuilder) -> (Vec<u8>, Vec<u8>) {
+        let frame = builder.pdu_ref(1).build().unwrap();
+        (
+            frame[ITEM_OFFSET..ITEM_OFFSET + 12].to_vec(),
+            frame[ITEM_OFFSET + 12..].to_vec(),
+        )
+    }
+
+    fn db1_item(byte_addr: u8, len: u8) -> Vec<u8> {
+        vec![
+            0x12,
+            0x0a,
+            0x10,
+            0x02,
+            0x00,
+            len,
+            0x00,
+            0x01,
+            0x84,
+            0x00,
+            0x00,
+            byte_addr << 3,
+        ]
+    }
+
+    #[test]
+    fn check_write_db_word() {
+        let (item, data) = encode(S7WriteBuilder::default().write_db_word(1, 4, 0x1234));
+        assert_eq!(item, db1_item(4, 2));
+        assert_eq!(data, [0x00, 0x04, 0x00, 0x10, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn check_write_db_dword() {
+        let (item, data) = encode(S7WriteBuilder::default().write_db_dword(1, 8, 0x12345678));
+        assert_eq!(item, db1_item(8, 4));
+        assert_eq!(data, [0x00, 0x04, 0x00, 0x20, 0x12, 0x34, 0x56, 0x78]);
+    }
+
+    #[test]
+    fn check_write_db_int() {
+        let (item, data) = encode(S7WriteBuilder::default().write_db_int(1, 4, -2));
+        assert_eq!(item, db1_item(4, 2));
+        assert_eq!(data, [0x00, 0x05, 0x00, 0x10, 0xff, 0xfe]);
+    }
+
+    #[test]
+    fn check_write_db_dint() {
+        let (item, data) = encode(S7WriteBuilder::default().write_db_dint(1, 8, -100000));
+        assert_eq!(item, db1_item(8, 4));
+        assert_eq!(data, [0x00, 0x05, 0x00, 0x20, 0xff, 0xfe, 0x79, 0x60]);
+    }
+
+    #[test]
+    fn check_write_db_real() {
+        let (item, data) = encode(S7WriteBuilder::default().write_db_real(1, 12, 1.5));
+        assert_eq!(item, db1_item(12, 4));
+        assert_eq!(data, [0x00, 0x07, 0x00, 0x04, 0x3f, 0xc0, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn check_write_db_string() {
+        let builder = S7WriteBuilder::default().write_db_string(1, 4, 10, "s7");
+        let (item, data) = encode(builder.unwrap());
+        assert_eq!(item, db1_item(4, 4));
+        assert_eq!(data, [0x00, 0x04, 0x00, 0x20, 10, 2, b's', b'7']);
+
+        assert!(matches!(
+            S7WriteBuilder::default().write_db_string(1, 4, 2, "s7-comm"),
+            Err(Error::S7CommErr(s7_comm::Error::OutOfRange { .. }))
+        ));
+    }
+
+    #[test]
+    fn check_split_by_pdu_size() {
+        let builder = (0..4u16).fold(S7WriteBuilder::default(), |builder, x| {
+            builder.write_bytes(Some(1), Area::DataBlocks, x * 100, &[0; 99])
+        });
+        // 12 + 4 * (12 + 4 + 99) + 3 fill bytes
+        assert_eq!(builder.estimated_pdu_size(), 7 + 475);
+
+        // the fill byte is counted for every item when splitting
+        let builders = builder.split_by_pdu_size(250);
+        assert_eq!(
+            builders.iter().map(|x| x.items.len()).collect::<Vec<_>>(),
+            [2, 2]
+        );
+        assert_eq!(builders[0].items[1].0.byte_addr(), 100);
+        assert_eq!(builders[1].items[0].0.byte_addr(), 200);
+        assert_eq!(builders[0].estimated_pdu_size(), 7 + 243);
+
+        let frames = S7WriteBuilder::default()
+            .write_bytes(Some(1), Area::DataBlocks, 0, &[0; 99])
+            .write_bytes(Some(1), Area::DataBlocks, 100, &[0; 99])
+            .build_split(244)
+            .unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].len(), 7 + 243);
+
+        assert!(matches!(
+            S7WriteBuilder::default()
+                .write_bytes(Some(1), Area::DataBlocks, 0, &[0; 250])
+                .build_split(240),
+            Err(Error::FrameTooLarge {
+                limit: 240,
+                actual: 278
+            })
+        ));
+    }
+}
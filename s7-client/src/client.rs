@@ -1,68 +1,303 @@
 use std::{
+    future::Future,
     net::{IpAddr, SocketAddr},
     time::Duration,
 };
 
-use crate::{build_copt_connect_request, build_s7_read, build_s7_setup, build_s7_write, error::*};
+use crate::{
+    build_copt_connect_request, build_s7_read, build_s7_setup, build_s7_write, chunk_db_read,
+    codec::S7Encoder, config::AutoReconnect, db_value_range, error::*, partition_read,
+    partition_write, ConnectionStats, ControlOutcome, PduRefCounter, ReadItem, ReadResult,
+    S7ClientConfig, S7ReadBuilder, StatsSnapshot, WriteItem,
+};
 use bytes::BytesMut;
 use copt::{CoptDecoder, CoptFrame, Parameter, PduType, TpduSize};
-use log::debug;
-use s7_comm::{AckData, DataItemVal, DataItemWriteResponse, Frame, S7CommDecoder};
+use log::{debug, warn};
+use s7_comm::{
+    check_set_clock, decode_block_info, decode_clock, decode_list_blocks,
+    decode_list_blocks_of_type_data, AckData, BlockInfo, BlockListEntry, BlockType, CpuFamily,
+    CpuInfo, CpuState, DataItemVal, DataItemWriteResponse, Frame, IoFaultSummary, ItemRequest,
+    PlcDateTime, S7CommDecoder, SzlId, SzlResponse, UserDataFunctionGroup,
+    SUBFUNCTION_LIST_BLOCKS_OF_TYPE, SUBFUNCTION_READ_SZL, SZL_ID_CPU_STATE,
+};
+use socket2::{SockRef, TcpKeepalive};
 use tokio::{
-    io::{AsyncReadExt, AsyncWriteExt},
-    net::TcpStream,
-    time::timeout,
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, ToSocketAddrs},
+    time::{sleep, sleep_until, timeout, Instant},
 };
-use tokio_util::codec::Decoder;
+use tokio_util::codec::{Decoder, Encoder};
 use tpkt::{TpktDecoder, TpktFrame};
 
+mod block_transfer;
 mod param;
 mod request_param;
 
 pub use param::*;
 pub use request_param::*;
 
+/// the source reference of the connect request
+const COPT_SOURCE_REF: [u8; 2] = [0, 1];
+
+/// called with the raw bytes and the error when a received frame fails to
+/// decode
+pub type DecodeErrorHandler = Box<dyn Fn(&[u8], &Error) + Send + Sync>;
+
+/// called with the address of the plc after the client reconnected by
+/// itself, by auto reconnect or keepalive
+pub type ReconnectHandler = Box<dyn Fn(SocketAddr) + Send + Sync>;
+
 pub struct S7Client {
-    options: Options,
+    config: S7ClientConfig,
+    /// the address of the plc, which `reconnect` connects to
+    addr: SocketAddr,
     connect: TcpStream,
+    tpdu_size: TpduSize,
+    /// the pdu length negotiated by setup communication
+    pdu_len: u16,
+    /// the max amq calling and called negotiated by setup communication
+    max_amq: (u16, u16),
+    pdu_ref: PduRefCounter,
+    decode_error_handler: Option<DecodeErrorHandler>,
+    reconnect_handler: Option<ReconnectHandler>,
+    /// the idle time after which `keepalive` polls the plc
+    keepalive: Option<Duration>,
+    /// the time of the last frame sent or received
+    last_traffic: Instant,
+    /// the received bytes following the last frame, e.g. a job the plc sent
+    /// right after its ack
+    read_buf: BytesMut,
+    stats: ConnectionStats,
+    /// the source reference of the connect confirm
+    remote_ref: [u8; 2],
+    disconnected: bool,
 }
 
 impl S7Client {
     pub async fn connect(options: Options) -> Result<Self> {
-        let connect =
-            tokio::net::TcpStream::connect(SocketAddr::new(options.address, options.port))
-                .await
-                .map_err(|e| Error::Other(format!("failed to tcp connect: {}", e)))?;
+        Self::connect_with_config(
+            SocketAddr::new(options.address, options.port),
+            S7ClientConfig::from(&options),
+        )
+        .await
+    }
 
-        let mut client = Self { options, connect };
+    pub async fn connect_with_config(
+        addr: impl ToSocketAddrs,
+        config: S7ClientConfig,
+    ) -> Result<Self> {
+        let connect = tcp_connect(addr, config.connection_timeout).await?;
+        let addr = connect.peer_addr()?;
+
+        let mut client = Self {
+            tpdu_size: TpduSize::L1024,
+            pdu_len: config.max_pdu_size,
+            max_amq: (config.max_amq_calling, config.max_amq_calling),
+            config,
+            addr,
+            connect,
+            pdu_ref: PduRefCounter::default(),
+            decode_error_handler: None,
+            reconnect_handler: None,
+            keepalive: None,
+            last_traffic: Instant::now(),
+            read_buf: BytesMut::new(),
+            stats: ConnectionStats::default(),
+            remote_ref: [0, 0],
+            disconnected: false,
+        };
         client.copt_connect().await?;
         client.s7_setup().await?;
+        client.stats.connected();
         Ok(client)
     }
 
+    /// connect to the plc by rack and slot, e.g. rack 0 and slot 1 for a
+    /// s7-300, with the default config
+    pub async fn connect_rack_slot(addr: SocketAddr, rack: u8, slot: u8) -> Result<Self> {
+        Self::connect_with_config(addr, S7ClientConfig::default().rack(rack).slot(slot)).await
+    }
+
+    /// the config which the client connected with
+    pub fn config(&self) -> &S7ClientConfig {
+        &self.config
+    }
+
+    /// the pdu length negotiated by setup communication
+    pub fn pdu_length(&self) -> u16 {
+        self.pdu_len
+    }
+
+    /// the max amq calling negotiated by setup communication, i.e. the
+    /// number of jobs the client may have unacknowledged. The client sends a
+    /// job only after the ack of the previous one, so it never exceeds the
+    /// negotiated value
+    pub fn max_amq_calling(&self) -> u16 {
+        self.max_amq.0
+    }
+
+    /// the max amq called negotiated by setup communication, i.e. the number
+    /// of jobs the plc may have unacknowledged
+    pub fn max_amq_called(&self) -> u16 {
+        self.max_amq.1
+    }
+
+    /// the traffic of the connection since the client connected, e.g. to
+    /// log `stats().to_string()` periodically
+    pub fn stats(&self) -> StatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Enable tcp keepalive, the first probe is sent after the connection
+    /// has been idle for `time`. A plc whose tcp stack died silently is
+    /// detected by the failed probes, the pending and following requests
+    /// fail with `Error::ConnectionLost`.
+    pub fn set_tcp_keepalive(&self, time: Duration) -> Result<()> {
+        let keepalive = TcpKeepalive::new().with_time(time);
+        SockRef::from(&self.connect).set_tcp_keepalive(&keepalive)?;
+        Ok(())
+    }
+
+    /// capture the raw bytes of frames which fail to decode, e.g. to report
+    /// exotic plc frames
+    pub fn set_decode_error_handler(
+        &mut self,
+        handler: impl Fn(&[u8], &Error) + Send + Sync + 'static,
+    ) {
+        self.decode_error_handler = Some(Box::new(handler));
+    }
+
+    /// notified after the client reconnected by itself, e.g. to subscribe
+    /// again or to log the outage
+    pub fn set_reconnect_handler(&mut self, handler: impl Fn(SocketAddr) + Send + Sync + 'static) {
+        self.reconnect_handler = Some(Box::new(handler));
+    }
+
+    /// Poll the plc by `keepalive` once the connection has been idle for
+    /// `interval`, a plc closes an idle session after a timeout of its own.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(interval);
+        self
+    }
+
+    /// Wait until the connection has been idle for the interval of
+    /// `with_keepalive`, then poll the plc by reading the cpu state. If the
+    /// plc dropped the session, the client reconnects by the back-off of
+    /// auto reconnect, or once without it, and notifies the reconnect
+    /// handler. Call it whenever the client has nothing else to do, e.g. in
+    /// the loop of a monitoring task.
+    pub async fn keepalive(&mut self) -> Result<()> {
+        let Some(interval) = self.keepalive else {
+            return Err(Error::Err("keepalive is not enabled".to_string()));
+        };
+        sleep_until(self.last_traffic + interval).await;
+        match self.get_plc_state().await {
+            Err(e) if e.is_connection_broken() => {
+                let reconnect = self.config.auto_reconnect.unwrap_or(AutoReconnect {
+                    max_retries: 1,
+                    initial_delay: Duration::ZERO,
+                    max_delay: Duration::ZERO,
+                });
+                self.reconnect_with_back_off(reconnect, e).await
+            }
+            rs => rs.map(|_| ()),
+        }
+    }
+
+    /// Connect to the plc again by the copt handshake and setup
+    /// communication, e.g. after `Error::ConnectionLost`. The bytes received
+    /// on the old connection are dropped.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        self.connect = tcp_connect(self.addr, self.config.connection_timeout).await?;
+        self.read_buf.clear();
+        self.tpdu_size = TpduSize::L1024;
+        self.pdu_len = self.config.max_pdu_size;
+        self.max_amq = (self.config.max_amq_calling, self.config.max_amq_calling);
+        self.copt_connect().await?;
+        self.s7_setup().await?;
+        self.stats.connected();
+        Ok(())
+    }
+
+    /// reconnect by the back-off of the config, the error of the last
+    /// attempt is returned if all of them fail
+    async fn reconnect_with_back_off(
+        &mut self,
+        reconnect: AutoReconnect,
+        mut error: Error,
+    ) -> Result<()> {
+        for attempt in 0..reconnect.max_retries {
+            let delay = reconnect.delay(attempt);
+            warn!(
+                "reconnect to {} in {:?}, attempt {}/{}: {}",
+                self.addr,
+                delay,
+                attempt + 1,
+                reconnect.max_retries,
+                error
+            );
+            sleep(delay).await;
+            match self.reconnect().await {
+                Ok(()) => {
+                    if let Some(handler) = &self.reconnect_handler {
+                        handler(self.addr);
+                    }
+                    return Ok(());
+                }
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    /// Close the session by the disconnect request of copt and wait for the
+    /// disconnect confirm, then shut down the tcp connection. A plc which
+    /// closes the connection instead of confirming is accepted as well.
+    pub async fn disconnect(mut self) -> Result<()> {
+        self.disconnected = true;
+        let frame = TpktFrame::new(CoptFrame::disconnect_request(
+            self.remote_ref,
+            COPT_SOURCE_REF,
+        ));
+        let mut dst = BytesMut::new();
+        S7Encoder::default().encode(frame, &mut dst)?;
+        self.write_frame(&dst, "disconnect").await?;
+        match self.read_frame("disconnect").await {
+            Ok(frame) => {
+                let frame = frame.payload();
+                if !matches!(frame.pdu_type, PduType::DisconnectConfirm(_)) {
+                    return Err(Error::Err(format!(
+                        "should recv disconnect confirm, but not {:?}",
+                        frame
+                    )));
+                }
+            }
+            Err(Error::ConnectionLost) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        self.connect.flush().await.map_err(Error::from_io)?;
+        self.connect.shutdown().await.map_err(Error::from_io)?;
+        Ok(())
+    }
+
     async fn copt_connect(&mut self) -> Result<()> {
-        let frame = build_framed_copt_connect_request(&self.options).map_err(|e| {
+        let frame = build_framed_copt_connect_request(&self.config).map_err(|e| {
             Error::Other(format!(
                 "failed to build_framed_copt_connect_request: {:?}",
                 e
             ))
         })?;
 
-        self.write_frame(frame)
-            .await
-            .map_err(|e| Error::Other(format!("failed to copt write frame: {:?}", e)))?;
-
-        let frame = self
-            .read_frame()
-            .await
-            .map_err(|e| Error::Other(format!("failed to copt read frame: {:?}", e)))?
-            .payload();
+        // keep the errors of the exchange, e.g. Timeout and ConnectionLost
+        self.write_frame(&frame, "copt_connect").await?;
+        let frame = self.read_frame("copt_connect").await?.payload();
 
         if let PduType::ConnectConfirm(comm) = &frame.pdu_type {
             debug!("{:?}", comm);
+            self.remote_ref = comm.source_ref;
             for item in &comm.parameters {
                 if let Parameter::TpduSize(size) = item {
-                    self.options.tpdu_size = size.clone();
+                    self.tpdu_size = *size;
                 }
             }
         } else {
@@ -77,25 +312,21 @@ impl S7Client {
     }
 
     async fn s7_setup(&mut self) -> Result<()> {
-        let frame = build_framed_s7_setup(&self.options)
+        let frame = build_framed_s7_setup(&self.config, self.pdu_ref.next())
             .map_err(|e| Error::Other(format!("failed to build_framed_s7_setup: {:?}", e)))?;
 
-        self.write_frame(frame)
-            .await
-            .map_err(|e| Error::Other(format!("failed to write s7 setup frame: {:?}", e)))?;
-
-        let frame = self
-            .read_frame()
-            .await
-            .map_err(|e| Error::Other(format!("failed to read s7 setup frame: {:?}", e)))?
-            .payload();
+        self.write_frame(&frame, "s7_setup").await?;
+        let frame = self.read_frame("s7_setup").await?.payload();
 
         if let PduType::DtData(comm) = frame.pdu_type {
-            if let Frame::AckData { ack_data, .. } = comm.payload() {
-                if let AckData::SetupCommunication(data) = ack_data {
-                    debug!("{:?}", data);
-                    self.options.pdu_len = data.pdu_length();
-                }
+            let payload = comm.payload();
+            if let Some(pdu_length) = payload.negotiated_pdu_length() {
+                debug!("negotiated pdu length: {}", pdu_length);
+                self.pdu_len = pdu_length;
+            }
+            if let Some(max_amq) = payload.negotiated_max_amq() {
+                debug!("negotiated max amq: {:?}", max_amq);
+                self.max_amq = max_amq;
             }
         } else {
             return Err(Error::ConnectErr(format!(
@@ -108,6 +339,8 @@ impl S7Client {
         Ok(())
     }
 
+    /// fails with `Error::FrameTooLarge` before sending if the frame
+    /// exceeds the negotiated pdu length
     pub async fn write_bytes(
         &mut self,
         db_number: Option<u16>,
@@ -116,11 +349,12 @@ impl S7Client {
         data: &[u8],
     ) -> Result<DataItemWriteResponse> {
         let frame = build_s7_write()
-            .pdu_ref(self.options.tpdu_size.pdu_ref())
+            .pdu_ref(self.pdu_ref.next())
             .write_bytes(db_number, area, byte_addr, data)
+            .with_pdu_size_limit(self.pdu_len)
             .build()?;
 
-        let items = self.write(frame).await?;
+        let items = self.write(frame, "write_bytes").await?;
         if items.len() == 1 {
             Ok(items[0].clone())
         } else {
@@ -139,11 +373,15 @@ impl S7Client {
         bit_addr: u8,
         data: bool,
     ) -> Result<DataItemWriteResponse> {
+        if bit_addr > 7 {
+            return Err(Error::InvalidBitAddr(bit_addr as u16));
+        }
         let frame = build_s7_write()
-            .pdu_ref(self.options.tpdu_size.pdu_ref())
+            .pdu_ref(self.pdu_ref.next())
             .write_bit(db_number, area, byte_addr, bit_addr, data)
+            .with_pdu_size_limit(self.pdu_len)
             .build()?;
-        let items = self.write(frame).await?;
+        let items = self.write(frame, "write_bit").await?;
         if items.len() == 1 {
             Ok(items[0].clone())
         } else {
@@ -154,71 +392,853 @@ impl S7Client {
         }
     }
 
-    async fn write(&mut self, frame: BytesMut) -> Result<Vec<DataItemWriteResponse>> {
-        self.write_frame(frame).await?;
-        let frame = self.read_frame().await?.payload();
+    /// read a bit of the area, `db_number` is the db of `Area::DataBlocks`
+    pub async fn read_bit(
+        &mut self,
+        db_number: Option<u16>,
+        area: s7_comm::Area,
+        byte_addr: u16,
+        bit_addr: u8,
+    ) -> Result<bool> {
+        if bit_addr > 7 {
+            return Err(Error::InvalidBitAddr(bit_addr as u16));
+        }
+        let item = ItemRequest::init_bit(db_number, area, byte_addr, bit_addr);
+        let item = self.read_item(item, "read_bit").await?;
+        match item.data.first() {
+            Some(x) => Ok(*x > 0),
+            None => Err(Error::Err("read bit without data".to_string())),
+        }
+    }
+
+    /// read a bit of a db, e.g. DB1.DBX2.3
+    pub async fn read_db_bit(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        bit_addr: u8,
+    ) -> Result<bool> {
+        self.read_bit(
+            Some(db_number),
+            s7_comm::Area::DataBlocks,
+            byte_addr,
+            bit_addr,
+        )
+        .await
+    }
+
+    /// read a bit of the merkers, e.g. M2.3
+    pub async fn read_merker_bit(&mut self, byte_addr: u16, bit_addr: u8) -> Result<bool> {
+        self.read_bit(None, s7_comm::Area::Merker, byte_addr, bit_addr)
+            .await
+    }
+
+    /// read a bit of the process inputs, e.g. I2.3
+    pub async fn read_input_bit(&mut self, byte_addr: u16, bit_addr: u8) -> Result<bool> {
+        self.read_bit(None, s7_comm::Area::ProcessInput, byte_addr, bit_addr)
+            .await
+    }
+
+    /// read the value of the counter `number`, 0..=999
+    pub async fn read_counter(&mut self, number: u16) -> Result<u16> {
+        let item = ItemRequest::init_counter(number, 1);
+        Ok(self.read_item(item, "read_counter").await?.as_counter()?)
+    }
+
+    /// read the time value of the timer `number`
+    pub async fn read_timer(&mut self, number: u16) -> Result<Duration> {
+        let item = ItemRequest::init_timer(number, 1);
+        Ok(self.read_item(item, "read_timer").await?.as_s5time()?)
+    }
+
+    /// read `len` bytes of a db from `byte_addr`
+    pub async fn read_db_bytes(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        len: u16,
+    ) -> Result<Vec<u8>> {
+        let item = self
+            .read(&Area::DataBausteine(
+                db_number,
+                DataSizeType::Byte {
+                    addr: byte_addr,
+                    len,
+                },
+            ))
+            .await?;
+        if !item.return_code.is_success() {
+            return Err(s7_comm::Error::ItemError {
+                index: 0,
+                return_code: item.return_code,
+            }
+            .into());
+        }
+        Ok(item.data)
+    }
+
+    /// Read `count` units of the transport of a db from `byte_addr` and
+    /// return the raw bytes, e.g. 2 dwords in 8 bytes.
+    pub async fn read_db(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        transport: ReadTransport,
+        count: u16,
+    ) -> Result<Vec<u8>> {
+        let item = transport.db_item(db_number, byte_addr, count);
+        Ok(self.read_item(item, "read_db").await?.data)
+    }
+
+    /// Read `len` bytes of a db from `byte_addr`, which may exceed the
+    /// negotiated pdu length. The read is split into chunks of one job each,
+    /// a failed chunk fails the whole read with `ItemError` whose index is
+    /// the index of the chunk.
+    pub async fn read_db_bytes_chunked(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        len: u16,
+    ) -> Result<Vec<u8>> {
+        let items = chunk_db_read(db_number, byte_addr, len, self.pdu_len)?;
+        Ok(self.read_multi(items).await?.concat())
+    }
+
+    /// write the bytes to a db from `byte_addr`
+    pub async fn write_db_bytes(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        data: &[u8],
+    ) -> Result<()> {
+        let resp = self
+            .write_bytes(Some(db_number), s7_comm::Area::DataBlocks, byte_addr, data)
+            .await?;
+        if !resp.return_code.is_success() {
+            return Err(s7_comm::Error::ItemError {
+                index: 0,
+                return_code: resp.return_code,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Write the bytes to a db from `byte_addr`, then read them back and
+    /// fail with `Error::VerificationMismatch` at the first byte which
+    /// differs. The read back is a job of its own, the plc program may change
+    /// the bytes between the write and the read back, which is reported as a
+    /// mismatch as well.
+    pub async fn write_db_bytes_verified(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        data: &[u8],
+    ) -> Result<()> {
+        self.write_db_bytes(db_number, byte_addr, data).await?;
+        let read_back = self
+            .read_db_bytes_chunked(db_number, byte_addr, data.len() as u16)
+            .await?;
+        match data.iter().zip(&read_back).position(|(x, y)| x != y) {
+            Some(index) => Err(Error::VerificationMismatch {
+                offset: byte_addr + index as u16,
+            }),
+            None if read_back.len() != data.len() => Err(Error::VerificationMismatch {
+                offset: byte_addr + read_back.len() as u16,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Write a bit of a db, then read the bit back, see
+    /// `write_db_bytes_verified`. Only the bit is compared, the other bits
+    /// of the byte may be changed by the plc program meanwhile.
+    pub async fn write_db_bit_verified(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        bit_addr: u8,
+        value: bool,
+    ) -> Result<()> {
+        let resp = self
+            .write_bit(
+                Some(db_number),
+                s7_comm::Area::DataBlocks,
+                byte_addr,
+                bit_addr,
+                value,
+            )
+            .await?;
+        if !resp.return_code.is_success() {
+            return Err(s7_comm::Error::ItemError {
+                index: 0,
+                return_code: resp.return_code,
+            }
+            .into());
+        }
+        if self.read_db_bit(db_number, byte_addr, bit_addr).await? != value {
+            return Err(Error::VerificationMismatch { offset: byte_addr });
+        }
+        Ok(())
+    }
+
+    /// read `count` values of `N` bytes of a db from the value `offset`
+    async fn read_db_values<const N: usize>(
+        &mut self,
+        db_number: u16,
+        offset: u16,
+        count: u16,
+    ) -> Result<Vec<[u8; N]>> {
+        let (byte_addr, len) = db_value_range(db_number, offset, count, N as u16)?;
+        let data = self.read_db_bytes(db_number, byte_addr, len).await?;
+        if data.len() != len as usize {
+            return Err(Error::Err(format!(
+                "should recv {} bytes, but recv {}",
+                len,
+                data.len()
+            )));
+        }
+        Ok(data
+            .chunks_exact(N)
+            .map(|x| x.try_into().unwrap())
+            .collect())
+    }
+
+    /// write the values of `N` bytes to a db from the value `offset`
+    async fn write_db_values<const N: usize>(
+        &mut self,
+        db_number: u16,
+        offset: u16,
+        values: Vec<[u8; N]>,
+    ) -> Result<()> {
+        // a count beyond u16 exceeds the address space as well
+        let count = values.len().try_into().unwrap_or(u16::MAX);
+        let (byte_addr, _) = db_value_range(db_number, offset, count, N as u16)?;
+        self.write_db_bytes(db_number, byte_addr, &values.concat())
+            .await
+    }
+
+    /// read `count` WORDs of a db from the word `word_offset`, big-endian
+    pub async fn read_db_words(
+        &mut self,
+        db_number: u16,
+        word_offset: u16,
+        count: u16,
+    ) -> Result<Vec<u16>> {
+        let values = self.read_db_values(db_number, word_offset, count).await?;
+        Ok(values.into_iter().map(u16::from_be_bytes).collect())
+    }
+
+    /// write the WORDs to a db from the word `word_offset`, big-endian
+    pub async fn write_db_words(
+        &mut self,
+        db_number: u16,
+        word_offset: u16,
+        values: &[u16],
+    ) -> Result<()> {
+        let values = values.iter().map(|x| x.to_be_bytes()).collect();
+        self.write_db_values(db_number, word_offset, values).await
+    }
+
+    /// read `count` DWORDs of a db from the dword `dword_offset`, big-endian
+    pub async fn read_db_dwords(
+        &mut self,
+        db_number: u16,
+        dword_offset: u16,
+        count: u16,
+    ) -> Result<Vec<u32>> {
+        let values = self.read_db_values(db_number, dword_offset, count).await?;
+        Ok(values.into_iter().map(u32::from_be_bytes).collect())
+    }
+
+    /// write the DWORDs to a db from the dword `dword_offset`, big-endian
+    pub async fn write_db_dwords(
+        &mut self,
+        db_number: u16,
+        dword_offset: u16,
+        values: &[u32],
+    ) -> Result<()> {
+        let values = values.iter().map(|x| x.to_be_bytes()).collect();
+        self.write_db_values(db_number, dword_offset, values).await
+    }
+
+    /// read `count` INTs of a db from the int `int_offset`, big-endian
+    pub async fn read_db_ints(
+        &mut self,
+        db_number: u16,
+        int_offset: u16,
+        count: u16,
+    ) -> Result<Vec<i16>> {
+        let values = self.read_db_values(db_number, int_offset, count).await?;
+        Ok(values.into_iter().map(i16::from_be_bytes).collect())
+    }
+
+    /// write the INTs to a db from the int `int_offset`, big-endian
+    pub async fn write_db_ints(
+        &mut self,
+        db_number: u16,
+        int_offset: u16,
+        values: &[i16],
+    ) -> Result<()> {
+        let values = values.iter().map(|x| x.to_be_bytes()).collect();
+        self.write_db_values(db_number, int_offset, values).await
+    }
+
+    /// read `count` REALs of a db from the real `real_offset`, big-endian
+    pub async fn read_db_reals(
+        &mut self,
+        db_number: u16,
+        real_offset: u16,
+        count: u16,
+    ) -> Result<Vec<f32>> {
+        let values = self.read_db_values(db_number, real_offset, count).await?;
+        Ok(values.into_iter().map(f32::from_be_bytes).collect())
+    }
+
+    /// write the REALs to a db from the real `real_offset`, big-endian
+    pub async fn write_db_reals(
+        &mut self,
+        db_number: u16,
+        real_offset: u16,
+        values: &[f32],
+    ) -> Result<()> {
+        let values = values.iter().map(|x| x.to_be_bytes()).collect();
+        self.write_db_values(db_number, real_offset, values).await
+    }
+
+    /// Change a db byte to `(byte & and_mask) | or_mask` by writing the
+    /// changed bits in one job, without reading the byte. Returns the
+    /// response of every written bit, from bit 0 to bit 7.
+    ///
+    /// NOTICE: plcs which reject bit writes, e.g. optimized dbs, reject it as
+    /// well.
+    pub async fn write_db_masked(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        and_mask: u8,
+        or_mask: u8,
+    ) -> Result<Vec<DataItemWriteResponse>> {
+        if and_mask == 0xff && or_mask == 0 {
+            return Ok(Vec::new());
+        }
+        let frame = build_s7_write()
+            .pdu_ref(self.pdu_ref.next())
+            .write_db_masked(db_number, byte_addr, and_mask, or_mask)
+            .with_pdu_size_limit(self.pdu_len)
+            .build()?;
+        self.write(frame, "write_db_masked").await
+    }
+
+    /// Write a bit of a db by reading the containing byte, changing the bit
+    /// and writing the byte back. It's the fallback for plcs which reject
+    /// bit writes, e.g. optimized dbs.
+    ///
+    /// NOTICE: it is not atomic, a change of the other bits of the byte
+    /// between the read and the write will be overwritten.
+    pub async fn write_db_bit_rmw(
+        &mut self,
+        db_number: u16,
+        byte_addr: u16,
+        bit_addr: u8,
+        data: bool,
+    ) -> Result<DataItemWriteResponse> {
+        if bit_addr > 7 {
+            return Err(Error::InvalidBitAddr(bit_addr as u16));
+        }
+        let item = self
+            .read(&Area::DataBausteine(
+                db_number,
+                DataSizeType::Byte {
+                    addr: byte_addr,
+                    len: 1,
+                },
+            ))
+            .await?;
+        let Some(byte) = item.data.first().filter(|_| item.return_code.is_ok()) else {
+            return Err(Error::Err(format!(
+                "failed to read DB{}.DBB{}: {:?}",
+                db_number, byte_addr, item.return_code
+            )));
+        };
+        let byte = if data {
+            *byte | 1 << bit_addr
+        } else {
+            *byte & !(1 << bit_addr)
+        };
+        self.write_bytes(
+            Some(db_number),
+            s7_comm::Area::DataBlocks,
+            byte_addr,
+            &[byte],
+        )
+        .await
+    }
+
+    async fn write(
+        &mut self,
+        frame: BytesMut,
+        operation: &'static str,
+    ) -> Result<Vec<DataItemWriteResponse>> {
+        let frame = self.exchange(frame, operation).await?.payload();
         if let PduType::DtData(comm) = frame.pdu_type {
-            if let Frame::AckData { ack_data, .. } = comm.payload() {
-                if let AckData::WriteVar(data) = ack_data {
-                    return Ok(data.data_item());
-                }
+            if let Frame::AckData {
+                ack_data: AckData::WriteVar(data),
+                ..
+            } = comm.payload()
+            {
+                return Ok(data.data_item());
             }
         }
-        return Err(Error::Err(format!("should recv read var")));
+        Err(Error::Err("should recv read var".to_string()))
     }
 
     pub async fn read(&mut self, area: &Area) -> Result<DataItemVal> {
-        let frame = build_framed_s7_read(&self.options, &[*area])?;
-        self.write_frame(frame).await?;
-
-        let frame = self.read_frame().await?.payload();
+        let frame = build_framed_s7_read(self.pdu_ref.next(), &[*area])?;
+        let frame = self.exchange(frame, "read").await?.payload();
         if let PduType::DtData(comm) = frame.pdu_type {
-            if let Frame::AckData { ack_data, .. } = comm.payload() {
-                if let AckData::ReadVar(data) = ack_data {
-                    let data_item = data.data_item();
-                    if data_item.len() != 1 {
-                        return Err(Error::Err(format!(
-                            "should recv one item, \
+            if let Frame::AckData {
+                ack_data: AckData::ReadVar(data),
+                ..
+            } = comm.payload()
+            {
+                let data_item = data.data_item();
+                if data_item.len() != 1 {
+                    return Err(Error::Err(format!(
+                        "should recv one item, \
                              but recv {}",
-                            data_item.len()
-                        )));
-                    }
-
-                    return Ok(data_item[0].clone());
+                        data_item.len()
+                    )));
                 }
+
+                return Ok(data_item[0].clone());
             }
         }
 
-        return Err(Error::Err(format!("should recv read var")));
+        Err(Error::Err("should recv read var".to_string()))
     }
 
     pub async fn read_vec(&mut self, areas: &[Area]) -> Result<Vec<DataItemVal>> {
-        let frame = build_framed_s7_read(&self.options, areas)?;
-        self.write_frame(frame).await?;
-        let frame = self.read_frame().await?.payload();
-        if let PduType::DtData(comm) = frame.pdu_type {
-            if let Frame::AckData { ack_data, .. } = comm.payload() {
-                if let AckData::ReadVar(data) = ack_data {
-                    return Ok(data.data_item());
+        let frame = build_framed_s7_read(self.pdu_ref.next(), areas)?;
+        self.read_var(frame, areas.len(), "read_vec").await
+    }
+
+    /// Read the bytes of every item, sending as many items per job as the
+    /// negotiated pdu length allows. The results are in the order of the
+    /// items.
+    pub async fn read_multi(&mut self, items: Vec<ReadItem>) -> Result<Vec<Vec<u8>>> {
+        let mut results = Vec::with_capacity(items.len());
+        for batch in partition_read(&items, self.pdu_len)? {
+            let mut builder = build_s7_read().pdu_ref(self.pdu_ref.next());
+            for item in &items[batch.clone()] {
+                builder = builder.add_item(item.to_request());
+            }
+            let data_items = self
+                .read_var(builder.build()?, batch.len(), "read_multi")
+                .await?;
+            for (index, item) in batch.zip(data_items) {
+                if !item.return_code.is_success() {
+                    return Err(s7_comm::Error::ItemError {
+                        index,
+                        return_code: item.return_code,
+                    }
+                    .into());
                 }
+                results.push(item.data);
             }
         }
-        return Err(Error::Err(format!("should recv read var")));
+        Ok(results)
     }
 
-    async fn write_frame(&mut self, framed: BytesMut) -> Result<()> {
-        timeout(self.options.write_timeout, self.connect.write_all(&framed))
-            .await
-            .map_err(|_| Error::WriteTimeout)??;
+    /// Write every item, sending as many items per job as the negotiated
+    /// pdu length allows.
+    ///
+    /// NOTICE: the jobs before a failed item have been written.
+    pub async fn write_multi(&mut self, items: Vec<WriteItem>) -> Result<()> {
+        for batch in partition_write(&items, self.pdu_len)? {
+            let mut builder = build_s7_write().pdu_ref(self.pdu_ref.next());
+            for item in &items[batch.clone()] {
+                builder = builder.write_bytes(
+                    item.db_number(),
+                    item.area.clone(),
+                    item.byte_offset,
+                    &item.data,
+                );
+            }
+            let frame = builder.with_pdu_size_limit(self.pdu_len).build()?;
+            let responses = self.write(frame, "write_multi").await?;
+            if responses.len() != batch.len() {
+                return Err(Error::Err(format!(
+                    "should recv {} items, but recv {}",
+                    batch.len(),
+                    responses.len()
+                )));
+            }
+            for (index, resp) in batch.zip(responses) {
+                if !resp.return_code.is_success() {
+                    return Err(s7_comm::Error::ItemError {
+                        index,
+                        return_code: resp.return_code,
+                    }
+                    .into());
+                }
+            }
+        }
         Ok(())
     }
 
-    async fn read_frame(&mut self) -> Result<TpktFrame<CoptFrame<Frame>>> {
-        Ok(
-            timeout(self.options.read_timeout, read_framed(&mut self.connect))
-                .await
-                .map_err(|_| Error::WriteTimeout)??,
+    /// Read the items of the builder in one job, e.g. a batch of
+    /// `add_db_real`, `add_db_int` and `add_db_bool`. The result of every
+    /// item is in the order of the items. The items of a merged item of
+    /// `S7ReadBuilder::optimize` which failed are read once more unmerged, so
+    /// that every item gets its own return code.
+    pub async fn read_values(&mut self, builder: S7ReadBuilder) -> Result<Vec<ReadResult>> {
+        let value_types = builder.value_types();
+        let builder = builder
+            .pdu_ref(self.pdu_ref.next())
+            .with_pdu_size_limit(self.pdu_len);
+        let (requests, slices) = builder.merged_items();
+        let frame = builder.clone().build()?;
+        let data_items = self.read_var(frame, requests.len(), "read_values").await?;
+        let mut items = slices
+            .iter()
+            .map(|x| x.extract(&data_items))
+            .collect::<Result<Vec<_>>>()?;
+
+        // the items unmerged may take more jobs than the merged items
+        let failed: Vec<_> = (0..items.len()).filter(|x| items[*x].is_none()).collect();
+        let mut retried = Vec::with_capacity(failed.len());
+        for part in builder.select(&failed).split_by_pdu_size(self.pdu_len) {
+            let expected = part.value_types().len();
+            let frame = part.pdu_ref(self.pdu_ref.next()).build()?;
+            retried.extend(self.read_var(frame, expected, "read_values").await?);
+        }
+        for (index, item) in failed.into_iter().zip(retried) {
+            items[index] = Some(item);
+        }
+        value_types
+            .iter()
+            .zip(items.into_iter().flatten())
+            .map(|(value_type, item)| value_type.decode(item))
+            .collect()
+    }
+
+    /// Read the system status list, e.g. `SzlId::ModuleIdent`. A response
+    /// of several data units is requested and joined unit by unit.
+    pub async fn read_szl(&mut self, szl_id: u16, szl_index: u16) -> Result<SzlResponse> {
+        let frame = Frame::read_szl(self.pdu_ref.next(), szl_id, szl_index);
+        let data = self
+            .user_data_request(
+                frame,
+                UserDataFunctionGroup::CpuFunctions,
+                SUBFUNCTION_READ_SZL,
+                "read_szl",
+            )
+            .await?;
+        Ok(SzlResponse::decode(&data)?)
+    }
+
+    /// all blocks of the plc, sorted by block type then block number
+    pub async fn list_blocks(&mut self) -> Result<Vec<BlockListEntry>> {
+        let frame = self
+            .request(Frame::list_blocks(self.pdu_ref.next()), "list_blocks")
+            .await?;
+        let mut blocks = Vec::new();
+        for (block_type, count) in decode_list_blocks(&frame)? {
+            if count == 0 {
+                continue;
+            }
+            let frame = Frame::list_blocks_of_type(self.pdu_ref.next(), block_type);
+            let data = self
+                .user_data_request(
+                    frame,
+                    UserDataFunctionGroup::BlockFunctions,
+                    SUBFUNCTION_LIST_BLOCKS_OF_TYPE,
+                    "list_blocks",
+                )
+                .await?;
+            blocks.extend(decode_list_blocks_of_type_data(&data)?.into_iter().map(
+                |(block_num, _)| BlockListEntry {
+                    block_type,
+                    block_num,
+                },
+            ));
+        }
+        blocks.sort();
+        Ok(blocks)
+    }
+
+    /// the metadata of the block, e.g. its mc7 code length and load memory
+    /// size
+    pub async fn get_block_info(
+        &mut self,
+        block_type: BlockType,
+        block_num: u16,
+    ) -> Result<BlockInfo> {
+        let frame = Frame::get_block_info(self.pdu_ref.next(), block_type, block_num);
+        let frame = self.request(frame, "get_block_info").await?;
+        Ok(decode_block_info(&frame)?)
+    }
+
+    /// the operating state of the cpu
+    pub async fn get_plc_state(&mut self) -> Result<CpuState> {
+        let szl = self.read_szl(SZL_ID_CPU_STATE, 0x0000).await?;
+        Ok(CpuState::from_szl(&szl)?)
+    }
+
+    /// the family of the cpu by the order number of the module
+    /// identification, e.g. to pick the addressing of the plc
+    pub async fn cpu_family(&mut self) -> Result<CpuFamily> {
+        let szl = self.read_szl(SzlId::ModuleIdent.into(), 0x0000).await?;
+        Ok(CpuFamily::from_szl(&szl)?)
+    }
+
+    /// the order number, versions, serial number and module name of the
+    /// cpu by the module identification and the component identification
+    pub async fn cpu_info(&mut self) -> Result<CpuInfo> {
+        let module_ident = self.read_szl(SzlId::ModuleIdent.into(), 0x0000).await?;
+        let component_ident = self.read_szl(SzlId::ComponentIdent.into(), 0x0000).await?;
+        Ok(CpuInfo::from_szl(&module_ident, &component_ident)?)
+    }
+
+    /// the identification of the cpu, an alias of `cpu_info`
+    pub async fn get_cpu_info(&mut self) -> Result<CpuInfo> {
+        self.cpu_info().await
+    }
+
+    /// whether any module reports a fault and the slots of the faulted
+    /// modules by the module status, a quick health check of the io
+    pub async fn io_fault_summary(&mut self) -> Result<IoFaultSummary> {
+        let szl = self.read_szl(SzlId::ModuleStatus.into(), 0x0000).await?;
+        Ok(IoFaultSummary::from_szl(&szl)?)
+    }
+
+    /// Stop the cpu, see the danger of `s7_comm::Frame::plc_stop`. A
+    /// protected cpu rejects it with `NakResponse`.
+    pub async fn stop_plc(&mut self) -> Result<()> {
+        let frame = self
+            .request(Frame::plc_stop(self.pdu_ref.next()), "stop_plc")
+            .await?;
+        check_plc_control_ack(frame)
+    }
+
+    /// Start the cpu with a warm restart, see the danger of
+    /// `s7_comm::Frame::plc_stop`. A protected cpu rejects it with
+    /// `NakResponse`.
+    pub async fn start_plc(&mut self) -> Result<()> {
+        let frame = self
+            .request(Frame::plc_start(self.pdu_ref.next()), "start_plc")
+            .await?;
+        check_plc_control_ack(frame)
+    }
+
+    /// Stop the cpu like `stop_plc`, reading the cpu state before and after
+    /// the job, e.g. to tell a cpu which stopped from one which was in stop
+    /// already. It costs two more round trips than `stop_plc`.
+    pub async fn stop_plc_checked(&mut self) -> Result<ControlOutcome> {
+        let previous = self.get_plc_state().await?;
+        self.stop_plc().await?;
+        let current = self.get_plc_state().await?;
+        Ok(ControlOutcome { previous, current })
+    }
+
+    /// Start the cpu like `start_plc`, reading the cpu state before and
+    /// after the job. It costs two more round trips than `start_plc`.
+    pub async fn start_plc_checked(&mut self) -> Result<ControlOutcome> {
+        let previous = self.get_plc_state().await?;
+        self.start_plc().await?;
+        let current = self.get_plc_state().await?;
+        Ok(ControlOutcome { previous, current })
+    }
+
+    /// read the clock of the cpu
+    pub async fn read_clock(&mut self) -> Result<PlcDateTime> {
+        let frame = self
+            .request(Frame::read_clock(self.pdu_ref.next()), "read_clock")
+            .await?;
+        Ok(decode_clock(&frame)?.into())
+    }
+
+    /// Set the clock of the cpu, e.g. to `PlcDateTime::now()`. A protected
+    /// cpu rejects it with `NakResponse`.
+    pub async fn write_clock(&mut self, dt: PlcDateTime) -> Result<()> {
+        let frame = Frame::set_clock(self.pdu_ref.next(), &dt.try_into()?)?;
+        let frame = self.request(frame, "write_clock").await?;
+        Ok(check_set_clock(&frame)?)
+    }
+
+    /// Send the user data request and join the data of the response of the
+    /// function group and subfunction, a response of several data units is
+    /// requested unit by unit.
+    async fn user_data_request(
+        &mut self,
+        frame: Frame,
+        function_group: UserDataFunctionGroup,
+        subfunction: u8,
+        operation: &'static str,
+    ) -> Result<Vec<u8>> {
+        let mut frame = self.request(frame, operation).await?;
+        let mut data = Vec::new();
+        let mut sequence_number = None;
+        loop {
+            data.extend_from_slice(frame.user_data_response(function_group, subfunction)?);
+            let Frame::UserData { parameter, .. } = &frame else {
+                unreachable!()
+            };
+            let first = *sequence_number.get_or_insert(parameter.sequence_number);
+            if parameter.sequence_number != first {
+                return Err(Error::Err(format!(
+                    "should recv data unit of sequence number {}, but {}",
+                    first, parameter.sequence_number
+                )));
+            }
+            if parameter.last_data_unit {
+                break;
+            }
+            let next = Frame::user_data_continuation(self.pdu_ref.next(), parameter);
+            frame = self.request(next, operation).await?;
+        }
+        Ok(data)
+    }
+
+    /// send the s7 frame and receive the s7 frame of the response
+    async fn request(&mut self, frame: Frame, operation: &'static str) -> Result<Frame> {
+        self.send(frame, operation).await?;
+        self.receive(operation).await
+    }
+
+    async fn send(&mut self, frame: Frame, operation: &'static str) -> Result<()> {
+        let frame = TpktFrame::new(CoptFrame::builder_of_dt_data(frame).build(0, true));
+        let mut dst = BytesMut::new();
+        S7Encoder::default().encode(frame, &mut dst)?;
+        self.write_frame(&dst, operation).await
+    }
+
+    /// receive the s7 frame of a response, or of a job of the plc
+    async fn receive(&mut self, operation: &'static str) -> Result<Frame> {
+        match self.read_frame(operation).await?.payload().pdu_type {
+            PduType::DtData(comm) => Ok(comm.payload()),
+            pdu_type => Err(Error::Err(format!(
+                "should recv dt data, but not {:?}",
+                pdu_type
+            ))),
+        }
+    }
+
+    /// read the item in a job of its own, a failed item fails with
+    /// `ItemError`
+    async fn read_item(
+        &mut self,
+        item: ItemRequest,
+        operation: &'static str,
+    ) -> Result<DataItemVal> {
+        let frame = build_s7_read()
+            .pdu_ref(self.pdu_ref.next())
+            .add_item(item)
+            .build()?;
+        let item = self.read_var(frame, 1, operation).await?.remove(0);
+        if !item.return_code.is_success() {
+            return Err(s7_comm::Error::ItemError {
+                index: 0,
+                return_code: item.return_code,
+            }
+            .into());
+        }
+        Ok(item)
+    }
+
+    /// send the read var job of `expected` items, the missing items of the
+    /// response are handled by `S7ClientConfig::missing_items`
+    async fn read_var(
+        &mut self,
+        frame: BytesMut,
+        expected: usize,
+        operation: &'static str,
+    ) -> Result<Vec<DataItemVal>> {
+        let frame = self.exchange(frame, operation).await?.payload();
+        if let PduType::DtData(comm) = frame.pdu_type {
+            if let Frame::AckData {
+                ack_data: AckData::ReadVar(data),
+                ..
+            } = comm.payload()
+            {
+                return Ok(data.data_item_of(expected, self.config.missing_items)?);
+            }
+        }
+        Err(Error::Err("should recv read var".to_string()))
+    }
+
+    /// Send the framed job and receive its response. With auto reconnect
+    /// the job is sent once more after reconnecting if the connection broke,
+    /// the frame is borrowed for both sends rather than copied.
+    async fn exchange(
+        &mut self,
+        framed: BytesMut,
+        operation: &'static str,
+    ) -> Result<TpktFrame<CoptFrame<Frame>>> {
+        let rs = self.exchange_once(&framed, operation).await;
+        match (rs, self.config.auto_reconnect) {
+            (Err(e), Some(reconnect)) if e.is_connection_broken() => {
+                self.reconnect_with_back_off(reconnect, e).await?;
+                self.exchange_once(&framed, operation).await
+            }
+            (rs, _) => rs,
+        }
+    }
+
+    async fn exchange_once(
+        &mut self,
+        framed: &[u8],
+        operation: &'static str,
+    ) -> Result<TpktFrame<CoptFrame<Frame>>> {
+        self.write_frame(framed, operation).await?;
+        self.read_frame(operation).await
+    }
+
+    async fn write_frame(&mut self, framed: &[u8], operation: &'static str) -> Result<()> {
+        let rs = within_request_timeout(self.config.request_timeout, operation, async {
+            self.connect.write_all(framed).await.map_err(Error::from_io)
+        })
+        .await;
+        match &rs {
+            Ok(()) => {
+                self.stats.sent(framed.len());
+                self.last_traffic = Instant::now();
+            }
+            Err(_) => self.stats.error(),
+        }
+        rs
+    }
+
+    async fn read_frame(&mut self, operation: &'static str) -> Result<TpktFrame<CoptFrame<Frame>>> {
+        let rs = within_request_timeout(
+            self.config.request_timeout,
+            operation,
+            read_framed(
+                &mut self.connect,
+                &mut self.read_buf,
+                S7CommDecoder::default().lenient_padding(self.config.lenient_padding),
+                self.decode_error_handler.as_ref(),
+                &self.stats,
+            ),
         )
+        .await;
+        match &rs {
+            Ok(_) => {
+                self.stats.pdu_received();
+                self.last_traffic = Instant::now();
+            }
+            Err(_) => self.stats.error(),
+        }
+        rs
+    }
+}
+
+impl Drop for S7Client {
+    fn drop(&mut self) {
+        if !self.disconnected {
+            debug!("client of {} dropped without disconnect", self.addr);
+        }
     }
 }
 
@@ -226,13 +1246,13 @@ impl S7Client {
 pub struct Options {
     pub read_timeout: Duration,
     pub write_timeout: Duration,
-    address: IpAddr,
-    port: u16,
+    pub(crate) address: IpAddr,
+    pub(crate) port: u16,
     pub conn_mode: ConnectMode,
     pub tpdu_size: TpduSize,
     //PDULength variable to store pdu length
     // after connect
-    pdu_len: u16,
+    pub(crate) pdu_len: u16,
 }
 
 impl Options {
@@ -249,52 +1269,146 @@ impl Options {
     }
 }
 
-async fn read_framed(req: &mut TcpStream) -> Result<TpktFrame<CoptFrame<Frame>>> {
+async fn tcp_connect(addr: impl ToSocketAddrs, connection_timeout: Duration) -> Result<TcpStream> {
+    timeout(connection_timeout, TcpStream::connect(addr))
+        .await
+        .map_err(|_| Error::ConnectErr("tcp connect timeout".to_string()))?
+        .map_err(|e| Error::Other(format!("failed to tcp connect: {}", e)))
+}
+
+/// await the send or receive of `operation`, a request timeout of `None`
+/// waits without a limit
+async fn within_request_timeout<T>(
+    request_timeout: Option<Duration>,
+    operation: &'static str,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    timeout(request_timeout.unwrap_or(Duration::MAX), future)
+        .await
+        .map_err(|_| Error::Timeout { operation })?
+}
+
+/// decode a frame of `bytes`, reading more bytes from the stream until one
+/// is complete, the bytes following the frame are kept in `bytes`
+async fn read_framed(
+    req: &mut (impl AsyncRead + Unpin),
+    bytes: &mut BytesMut,
+    s7_decoder: S7CommDecoder,
+    decode_error_handler: Option<&DecodeErrorHandler>,
+    stats: &ConnectionStats,
+) -> Result<TpktFrame<CoptFrame<Frame>>> {
     let mut buf = [0u8; 1000];
-    let mut bytes = BytesMut::new();
-    let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(s7_decoder));
 
     loop {
-        let size = req
-            .read(&mut buf)
-            .await
-            .map_err(|e| Error::Other(format!("failed to read Tpkt frame: {:?}", e)))?;
+        let raw = decode_error_handler.map(|_| bytes.clone());
+        match decoder.decode(bytes) {
+            Ok(Some(frame)) => return Ok(frame),
+            Ok(None) => {}
+            Err(e) => {
+                bytes.clear();
+                let e = match e {
+                    tpkt::Error::IoErr(e) => Error::from_io(e),
+                    e => Error::Other(format!("failed to decode frame: {:?}", e)),
+                };
+                if let (Some(handler), Some(raw)) = (decode_error_handler, raw) {
+                    handler(raw.as_ref(), &e);
+                }
+                return Err(e);
+            }
+        }
+
+        let size = req.read(&mut buf).await.map_err(Error::from_io)?;
+        if size == 0 {
+            return Err(Error::ConnectionLost);
+        }
+        stats.received(size);
 
         bytes.extend_from_slice(buf[0..size].as_ref());
+    }
+}
 
-        if let Some(frame) = decoder
-            .decode(&mut bytes)
-            .map_err(|e| Error::Other(format!("failed to decode frame: {:?}", e)))?
-        {
-            return Ok(frame);
-        }
+fn check_plc_control_ack(frame: Frame) -> Result<()> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::PlcControl(_) | AckData::PlcStop(_),
+        } => Ok(header.check()?),
+        frame => Err(Error::Err(format!(
+            "should recv ack of plc control, but not {:?}",
+            frame
+        ))),
     }
 }
 
-fn build_framed_s7_read(options: &Options, areas: &[Area]) -> Result<BytesMut> {
-    let mut builder = build_s7_read().pdu_ref(options.tpdu_size.pdu_ref());
+fn build_framed_s7_read(pdu_ref: u16, areas: &[Area]) -> Result<BytesMut> {
+    let mut builder = build_s7_read().pdu_ref(pdu_ref);
     for area in areas {
         builder = builder.add_item((*area).into());
     }
-    Ok(builder.build()?)
+    builder.build()
 }
 
-fn build_framed_copt_connect_request(options: &Options) -> Result<BytesMut> {
-    Ok(build_copt_connect_request()
-        .source_ref([0, 1])
+fn build_framed_copt_connect_request(config: &S7ClientConfig) -> Result<BytesMut> {
+    build_copt_connect_request()
+        .source_ref(COPT_SOURCE_REF)
         .destination_ref([0, 0])
         .class_and_others(0, false, false)
         .pdu_size(TpduSize::L1024)
-        .src_tsap(options.conn_mode.local_tsap())
-        .dst_tsap(options.conn_mode.remote_tsap())
-        .build_to_request()?)
+        .push_parameter(Parameter::new_src_tsap(config.local_tsap()))
+        .push_parameter(Parameter::new_dst_tsap(config.remote_tsap()))
+        .build_to_request()
+}
+
+fn build_framed_s7_setup(config: &S7ClientConfig, pdu_ref: u16) -> Result<BytesMut> {
+    build_s7_setup()
+        .max_amq_called(config.max_amq_calling)
+        .max_amq_calling(config.max_amq_calling)
+        .pdu_length(config.max_pdu_size)
+        .pdu_ref(pdu_ref)
+        .build()
 }
 
-fn build_framed_s7_setup(options: &Options) -> Result<BytesMut> {
-    Ok(build_s7_setup()
-        .max_amq_called(1)
-        .max_amq_calling(1)
-        .pdu_length(options.pdu_len)
-        .pdu_ref(options.tpdu_size.pdu_ref())
-        .build()?)
+#[cfg(test)]
+mod test {
+    use super::{read_framed, within_request_timeout};
+    use crate::{ConnectionStats, Error};
+    use bytes::BytesMut;
+    use s7_comm::S7CommDecoder;
+    use std::time::Duration;
+    use tokio_test::io::Builder;
+
+    #[tokio::test]
+    async fn check_request_timeout() {
+        // a plc which never answers the request
+        let mut connect = Builder::new().wait(Duration::from_secs(5)).build();
+        let mut bytes = BytesMut::new();
+        let stats = ConnectionStats::default();
+        let rs = within_request_timeout(
+            Some(Duration::from_millis(50)),
+            "read_db",
+            read_framed(
+                &mut connect,
+                &mut bytes,
+                S7CommDecoder::default(),
+                None,
+                &stats,
+            ),
+        )
+        .await;
+        assert!(matches!(
+            rs,
+            Err(Error::Timeout {
+                operation: "read_db"
+            })
+        ));
+
+        // without a request timeout the answer is awaited
+        let rs = within_request_timeout(None, "read_db", async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(())
+        })
+        .await;
+        assert!(rs.is_ok());
+    }
 }
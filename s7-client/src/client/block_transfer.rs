@@ -0,0 +1,140 @@
+use super::S7Client;
+use crate::error::*;
+use s7_comm::{
+    block_mc7_length, check_end_upload, check_request_download, decode_start_upload, decode_upload,
+    BlockType, Frame, Job,
+};
+
+/// ack data header, status and the length and unknown of the data of a
+/// download block ack
+const DOWNLOAD_BLOCK_ACK_HEAD_LEN: usize = 12 + 2 + 4;
+
+/// the steps of block upload
+enum UploadState {
+    Start,
+    Upload { upload_id: u32 },
+    End { upload_id: u32 },
+}
+
+/// the steps of block download, after request download the plc requests the
+/// data of the block by download block until it sends download ended
+enum DownloadState {
+    Request,
+    Transfer { offset: usize },
+}
+
+impl S7Client {
+    /// Upload the block from the plc, e.g. to back it up. The block starts
+    /// with its header and can be restored by `download_block`.
+    pub async fn upload_block(&mut self, block_type: BlockType, block_num: u16) -> Result<Vec<u8>> {
+        let rejected = |source| Error::BlockRejected {
+            block_type,
+            block_num,
+            source,
+        };
+        let mut data = Vec::new();
+        let mut state = UploadState::Start;
+        loop {
+            state = match state {
+                UploadState::Start => {
+                    let frame = Frame::start_upload(self.pdu_ref.next(), block_type, block_num);
+                    let frame = self.request(frame, "upload_block").await?;
+                    let (upload_id, block_length) =
+                        decode_start_upload(&frame).map_err(rejected)?;
+                    data.reserve(block_length);
+                    UploadState::Upload { upload_id }
+                }
+                UploadState::Upload { upload_id } => {
+                    let frame = Frame::upload(self.pdu_ref.next(), upload_id);
+                    let frame = self.request(frame, "upload_block").await?;
+                    let (block, more_data) = decode_upload(&frame).map_err(rejected)?;
+                    data.extend_from_slice(block);
+                    if more_data {
+                        UploadState::Upload { upload_id }
+                    } else {
+                        UploadState::End { upload_id }
+                    }
+                }
+                UploadState::End { upload_id } => {
+                    let frame = Frame::end_upload(self.pdu_ref.next(), upload_id);
+                    let frame = self.request(frame, "upload_block").await?;
+                    check_end_upload(&frame).map_err(rejected)?;
+                    return Ok(data);
+                }
+            }
+        }
+    }
+
+    /// Download the block to the plc, e.g. a block of `upload_block`. The
+    /// mc7 code length is taken from the header of the block, the data is
+    /// sent in chunks fitting in the pdu length.
+    ///
+    /// DANGER: downloading a block to a running cpu replaces the code or the
+    /// data the program is running with.
+    pub async fn download_block(
+        &mut self,
+        block_type: BlockType,
+        block_num: u16,
+        data: &[u8],
+    ) -> Result<()> {
+        let rejected = |source| Error::BlockRejected {
+            block_type,
+            block_num,
+            source,
+        };
+        let mc7_length = block_mc7_length(data).map_err(rejected)?;
+        let chunk_len = (self.pdu_len as usize).saturating_sub(DOWNLOAD_BLOCK_ACK_HEAD_LEN);
+        if chunk_len == 0 {
+            return Err(Error::Err(format!(
+                "pdu length {} is too small",
+                self.pdu_len
+            )));
+        }
+        let mut state = DownloadState::Request;
+        loop {
+            state = match state {
+                DownloadState::Request => {
+                    let frame = Frame::request_download(
+                        self.pdu_ref.next(),
+                        block_type,
+                        block_num,
+                        data.len() as u32,
+                        mc7_length as u32,
+                    );
+                    let frame = self.request(frame, "download_block").await?;
+                    check_request_download(&frame).map_err(rejected)?;
+                    DownloadState::Transfer { offset: 0 }
+                }
+                DownloadState::Transfer { offset } => match self.receive("download_block").await? {
+                    Frame::Job {
+                        header,
+                        job: Job::DownloadBlock(_),
+                    } => {
+                        let end = data.len().min(offset + chunk_len);
+                        let frame = Frame::download_block_ack(
+                            header.pdu_ref,
+                            end < data.len(),
+                            data[offset..end].to_vec(),
+                        );
+                        self.send(frame, "download_block").await?;
+                        DownloadState::Transfer { offset: end }
+                    }
+                    Frame::Job {
+                        header,
+                        job: Job::DownloadEnded(_),
+                    } => {
+                        self.send(Frame::download_ended_ack(header.pdu_ref), "download_block")
+                            .await?;
+                        return Ok(());
+                    }
+                    frame => {
+                        return Err(Error::Err(format!(
+                            "should recv download block or download ended, but not {:?}",
+                            frame
+                        )))
+                    }
+                },
+            }
+        }
+    }
+}
@@ -1,31 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-/// Client Connection Type
-/// 16 possible connections limited by the
-/// hardware The types are defined from the
-/// highest to lowest priority
-/// The basic connections are the first which
-/// would be closed if there aren't enough
-/// resources
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
-pub enum ConnectionType {
-    /// Connect to the PLC programming console
-    /// (Programmiergeräte). German for
-    /// programming device.
-    PG = 1,
-    /// Connect to the PLC Siemens HMI panel
-    OP = 2,
-    /// Basic connection for generic data
-    /// transfer connection
-    /// 14 Basic connections
-    Basic = 3,
-}
-
-impl Default for ConnectionType {
-    fn default() -> Self {
-        Self::OP
-    }
-}
+/// the connection type of copt, which `ConnectMode` puts in the tsap
+pub use copt::ConnectionType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConnectMode {
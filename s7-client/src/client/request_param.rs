@@ -27,9 +27,9 @@ pub enum Area {
     /* TODO: Counter */
 }
 
-impl Into<ItemRequest> for Area {
-    fn into(self) -> ItemRequest {
-        match &self {
+impl From<Area> for ItemRequest {
+    fn from(val: Area) -> Self {
+        match &val {
             Area::ProcessInput(ds) => ItemRequest::new(
                 ds.to_transport_size(),
                 s7_comm::DbNumber::NotIn,
@@ -166,6 +166,7 @@ impl DataSizeType {
     }
 
     /// 读取的单位长度
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> u16 {
         use DataSizeType::*;
         match self {
@@ -189,3 +190,75 @@ impl DataSizeType {
         }
     }
 }
+
+/// transport size of the item of `S7Client::read_db`, the count of the read
+/// is in its units, e.g. a count of 2 words reads 4 bytes
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub enum ReadTransport {
+    /// one bit of the byte, the count is 1
+    Bit(BitAddr),
+    Byte,
+    Word,
+    DWord,
+}
+
+impl ReadTransport {
+    pub fn to_transport_size(&self) -> TransportSize {
+        match self {
+            ReadTransport::Bit(_) => TransportSize::Bit,
+            ReadTransport::Byte => TransportSize::NoBit,
+            ReadTransport::Word => TransportSize::Word,
+            ReadTransport::DWord => TransportSize::DWord,
+        }
+    }
+
+    /// the item of reading `count` units of the db from `byte_addr`
+    pub fn db_item(&self, db_number: u16, byte_addr: u16, count: u16) -> ItemRequest {
+        let bit_addr = match self {
+            ReadTransport::Bit(bit_addr) => *bit_addr as u8,
+            _ => 0,
+        };
+        ItemRequest::new(
+            self.to_transport_size(),
+            s7_comm::DbNumber::DbNumber(db_number),
+            S7Area::DataBlocks,
+            byte_addr,
+            bit_addr,
+            count,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BitAddr, ReadTransport};
+    use s7_comm::TransportSize;
+
+    #[test]
+    fn check_read_transport_item() {
+        for (transport, transport_size, byte) in [
+            (
+                ReadTransport::Bit(BitAddr::Addr3),
+                TransportSize::Bit,
+                0x01u8,
+            ),
+            (ReadTransport::Byte, TransportSize::NoBit, 0x02),
+            (ReadTransport::Word, TransportSize::Word, 0x04),
+            (ReadTransport::DWord, TransportSize::DWord, 0x06),
+        ] {
+            let item = transport.db_item(1, 10, 3);
+            assert_eq!(item.transport_size_type(), transport_size);
+            assert_eq!(u8::from(item.transport_size_type()), byte);
+            assert_eq!(item.length(), 3);
+            assert_eq!(item.db_number(), 1);
+            assert_eq!(item.byte_addr(), 10);
+        }
+        assert_eq!(
+            ReadTransport::Bit(BitAddr::Addr3)
+                .db_item(1, 10, 1)
+                .bit_addr(),
+            3
+        );
+        assert_eq!(ReadTransport::Word.db_item(1, 10, 1).bit_addr(), 0);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{ConnectMode, ConnectionType, Options};
+use s7_comm::MissingItems;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parameters of the connection to the plc, built by chaining the setters on
+/// `S7ClientConfig::default()`:
+///
+/// ```
+/// use s7_client::S7ClientConfig;
+/// use std::time::Duration;
+///
+/// let config = S7ClientConfig::default()
+///     .rack(0)
+///     .slot(1)
+///     .request_timeout(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct S7ClientConfig {
+    pub(crate) rack: u8,
+    pub(crate) slot: u8,
+    pub(crate) connection_timeout: Duration,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) max_pdu_size: u16,
+    pub(crate) max_amq_calling: u16,
+    pub(crate) src_tsap: Option<Vec<u8>>,
+    pub(crate) dst_tsap: Option<Vec<u8>>,
+    pub(crate) missing_items: MissingItems,
+    pub(crate) lenient_padding: bool,
+    pub(crate) auto_reconnect: Option<AutoReconnect>,
+}
+
+impl Default for S7ClientConfig {
+    fn default() -> Self {
+        Self {
+            rack: 0,
+            slot: 2,
+            connection_timeout: Duration::from_secs(5),
+            request_timeout: Some(Duration::from_secs(30)),
+            max_pdu_size: 480,
+            max_amq_calling: 1,
+            src_tsap: None,
+            dst_tsap: None,
+            missing_items: MissingItems::Error,
+            lenient_padding: false,
+            auto_reconnect: None,
+        }
+    }
+}
+
+impl S7ClientConfig {
+    pub fn rack(mut self, rack: u8) -> Self {
+        self.rack = rack;
+        self
+    }
+
+    pub fn slot(mut self, slot: u8) -> Self {
+        self.slot = slot;
+        self
+    }
+
+    /// timeout of the tcp connect
+    pub fn connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.connection_timeout = connection_timeout;
+        self
+    }
+
+    /// timeout of sending a request and of receiving its response, 30s by
+    /// default
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    /// wait for the plc without a timeout
+    pub fn without_request_timeout(mut self) -> Self {
+        self.request_timeout = None;
+        self
+    }
+
+    /// the pdu length proposed in setup communication, the plc may
+    /// negotiate a smaller one
+    pub fn max_pdu_size(mut self, max_pdu_size: u16) -> Self {
+        self.max_pdu_size = max_pdu_size;
+        self
+    }
+
+    pub fn max_amq_calling(mut self, max_amq_calling: u16) -> Self {
+        self.max_amq_calling = max_amq_calling;
+        self
+    }
+
+    /// the local tsap, `[0x01, 0x00]` if not set
+    pub fn src_tsap(mut self, src_tsap: Vec<u8>) -> Self {
+        self.src_tsap = Some(src_tsap);
+        self
+    }
+
+    /// the remote tsap, which overrides the rack and slot
+    pub fn dst_tsap(mut self, dst_tsap: Vec<u8>) -> Self {
+        self.dst_tsap = Some(dst_tsap);
+        self
+    }
+
+    /// how a read var response with fewer items than the request is handled,
+    /// `MissingItems::Error` by default
+    pub fn missing_items(mut self, missing_items: MissingItems) -> Self {
+        self.missing_items = missing_items;
+        self
+    }
+
+    /// tolerate a firmware which omits the fill byte of odd length items in
+    /// read var responses, see `S7CommDecoder::lenient_padding`
+    pub fn lenient_padding(mut self, lenient_padding: bool) -> Self {
+        self.lenient_padding = lenient_padding;
+        self
+    }
+
+    /// Reconnect when a read or write fails on a broken connection, then
+    /// retry it once. The attempts wait `initial_delay`, doubled per attempt
+    /// up to `max_delay` with a jitter of ±20%.
+    pub fn with_auto_reconnect(
+        mut self,
+        max_retries: usize,
+        initial_delay: Duration,
+        max_delay: Duration,
+    ) -> Self {
+        self.auto_reconnect = Some(AutoReconnect {
+            max_retries,
+            initial_delay,
+            max_delay,
+        });
+        self
+    }
+
+    pub(crate) fn local_tsap(&self) -> Vec<u8> {
+        match &self.src_tsap {
+            Some(tsap) => tsap.clone(),
+            None => vec![0x01, 0x00],
+        }
+    }
+
+    pub(crate) fn remote_tsap(&self) -> Vec<u8> {
+        match &self.dst_tsap {
+            Some(tsap) => tsap.clone(),
+            None => ConnectMode::init_rack_slot(
+                ConnectionType::default(),
+                self.rack as u16,
+                self.slot as u16,
+            )
+            .remote_tsap()
+            .to_vec(),
+        }
+    }
+}
+
+/// the back-off of `S7ClientConfig::with_auto_reconnect`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) struct AutoReconnect {
+    pub(crate) max_retries: usize,
+    pub(crate) initial_delay: Duration,
+    pub(crate) max_delay: Duration,
+}
+
+impl AutoReconnect {
+    /// the delay before the attempt, which starts with 0
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let base = self
+            .initial_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_delay);
+        base.mul_f64(1.0 + jitter()).min(self.max_delay)
+    }
+}
+
+/// a factor within ±0.2, the attempts of several clients after the plc
+/// restarted are spread
+fn jitter() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.subsec_nanos());
+    (nanos % 4001) as f64 / 10000.0 - 0.2
+}
+
+impl From<&Options> for S7ClientConfig {
+    fn from(options: &Options) -> Self {
+        let config = match options.conn_mode {
+            ConnectMode::RackSlot { rack, slot, .. } => {
+                Self::default().rack(rack as u8).slot(slot as u8)
+            }
+            ConnectMode::Tsap { .. } => Self::default(),
+        };
+        config
+            .request_timeout(options.read_timeout.max(options.write_timeout))
+            .max_pdu_size(options.pdu_len)
+            .src_tsap(options.conn_mode.local_tsap().to_vec())
+            .dst_tsap(options.conn_mode.remote_tsap().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::S7ClientConfig;
+    use crate::{ConnectMode, ConnectionType, Options};
+    use std::{net::IpAddr, time::Duration};
+
+    #[test]
+    fn check_default_tsap() {
+        let config = S7ClientConfig::default();
+        assert_eq!(config.local_tsap(), vec![0x01, 0x00]);
+        assert_eq!(config.remote_tsap(), vec![0x02, 0x02]);
+
+        let config = config.rack(1).slot(3);
+        assert_eq!(config.remote_tsap(), vec![0x02, 0x23]);
+
+        let config = config.src_tsap(vec![0x10, 0x00]).dst_tsap(vec![0x03, 0x01]);
+        assert_eq!(config.local_tsap(), vec![0x10, 0x00]);
+        assert_eq!(config.remote_tsap(), vec![0x03, 0x01]);
+    }
+
+    #[test]
+    fn check_auto_reconnect_delay() {
+        let config = S7ClientConfig::default().with_auto_reconnect(
+            5,
+            Duration::from_millis(100),
+            Duration::from_millis(500),
+        );
+        let reconnect = config.auto_reconnect.unwrap();
+        assert_eq!(reconnect.max_retries, 5);
+        for (attempt, base) in [(0, 100), (1, 200), (2, 400)] {
+            let delay = reconnect.delay(attempt);
+            assert!(delay >= Duration::from_millis(base * 8 / 10));
+            assert!(delay <= Duration::from_millis(base * 12 / 10));
+        }
+        for attempt in [3, 10, 100] {
+            let delay = reconnect.delay(attempt);
+            assert!(delay >= Duration::from_millis(400));
+            assert!(delay <= Duration::from_millis(500));
+        }
+        assert!(S7ClientConfig::default().auto_reconnect.is_none());
+    }
+
+    #[test]
+    fn check_from_options() {
+        let mut options = Options::new(
+            IpAddr::from([127, 0, 0, 1]),
+            102,
+            ConnectMode::init_rack_slot(ConnectionType::Basic, 0, 1),
+        );
+        options.write_timeout = Duration::from_secs(2);
+        let config = S7ClientConfig::from(&options);
+        assert_eq!(config.slot, 1);
+        assert_eq!(config.request_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(config.max_pdu_size, 480);
+        assert_eq!(config.remote_tsap(), vec![0x03, 0x01]);
+        assert!(config.without_request_timeout().request_timeout.is_none());
+    }
+}
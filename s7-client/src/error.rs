@@ -1,3 +1,4 @@
+use s7_comm::BlockType;
 use std::io;
 use thiserror::Error;
 
@@ -9,27 +10,81 @@ pub enum Error {
     #[error(transparent)]
     TpktErr(#[from] tpkt::Error),
 
+    #[error(transparent)]
+    S7CommErr(#[from] s7_comm::Error),
+
     #[error("Error: {0}")]
     Err(String),
 
+    #[deprecated(note = "the client reports `Error::Timeout` instead")]
     #[error("WriteTimeout")]
     WriteTimeout,
 
+    #[deprecated(note = "the client reports `Error::Timeout` instead")]
     #[error("ReadTimeout")]
     ReadTimeout,
 
+    /// the plc did not take the request or did not answer it within the
+    /// request timeout, `operation` is the method of the client
+    #[error("{operation} timed out")]
+    Timeout { operation: &'static str },
+
     #[error("connect failed: {0}")]
     ConnectErr(String),
 
+    /// the plc closed the connection or it stopped answering tcp keepalive
+    #[error("connection lost")]
+    ConnectionLost,
+
     #[error("InvalidBitAddr: {0}")]
     InvalidBitAddr(u16),
 
+    /// the s7 pdu of the frame exceeds the pdu length
+    #[error("frame of {actual} bytes exceeds the pdu length {limit}")]
+    FrameTooLarge { limit: u16, actual: usize },
+
+    /// the items exceed the items of a job
+    #[error("{actual} items exceed the limit {limit} of a job")]
+    TooManyItems { limit: usize, actual: usize },
+
+    /// the byte at the address `offset` of the db read back differs from
+    /// the byte written
+    #[error("read back of the write differs at byte {offset}")]
+    VerificationMismatch { offset: u16 },
+
+    /// the plc rejected the upload or download of the block
+    #[error("{block_type:?} {block_num} rejected: {source}")]
+    BlockRejected {
+        block_type: BlockType,
+        block_num: u16,
+        source: s7_comm::Error,
+    },
+
     #[error("{0}")]
     Other(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// map the io errors of a broken connection to `Error::ConnectionLost`
+    pub(crate) fn from_io(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::TimedOut => Error::ConnectionLost,
+            _ => Error::IoErr(e),
+        }
+    }
+
+    /// the connection is broken, a reconnect may recover it
+    pub(crate) fn is_connection_broken(&self) -> bool {
+        matches!(self, Error::ConnectionLost | Error::IoErr(_))
+    }
+}
+
 // impl<T: TryFromPrimitive>
 // From<TryFromPrimitiveError<T>> for Error {
 //     fn from(value: TryFromPrimitiveError<T>) ->
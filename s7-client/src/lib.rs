@@ -3,11 +3,21 @@
 mod builder;
 mod client;
 mod codec;
+mod config;
 mod error;
+mod pdu_ref;
+mod stats;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+mod types;
 
 pub use builder::*;
 pub use client::*;
+pub use config::*;
 pub use copt;
 pub use error::*;
+pub use pdu_ref::*;
 pub use s7_comm;
+pub use stats::*;
 pub use tpkt;
+pub use types::*;
@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+
+/// Thread-safe generator of pdu reference numbers. It wraps from 65535 back
+/// to 1, 0 is skipped because some plcs reject it.
+#[derive(Debug)]
+pub struct PduRefCounter(AtomicU16);
+
+impl PduRefCounter {
+    pub fn new(start: u16) -> Self {
+        Self(AtomicU16::new(start.max(1)))
+    }
+
+    pub fn next(&self) -> u16 {
+        self.0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| {
+                Some(if x == u16::MAX { 1 } else { x + 1 })
+            })
+            .unwrap_or_else(|x| x)
+    }
+}
+
+impl Default for PduRefCounter {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PduRefCounter;
+
+    #[test]
+    fn check_next() {
+        let counter = PduRefCounter::default();
+        assert_eq!(counter.next(), 1);
+        assert_eq!(counter.next(), 2);
+
+        let counter = PduRefCounter::new(u16::MAX - 1);
+        assert_eq!(counter.next(), u16::MAX - 1);
+        assert_eq!(counter.next(), u16::MAX);
+        assert_eq!(counter.next(), 1);
+
+        assert_eq!(PduRefCounter::new(0).next(), 1);
+    }
+}
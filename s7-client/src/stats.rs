@@ -0,0 +1,129 @@
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counters of the traffic of a connection, updated by `S7Client` on every
+/// send and receive.
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    pub bytes_sent: AtomicU64,
+    pub bytes_received: AtomicU64,
+    pub pdus_sent: AtomicU64,
+    pub pdus_received: AtomicU64,
+    /// the failed sends and receives, e.g. timeouts and broken connections
+    pub errors: AtomicU64,
+    /// the time of the last successful connect or reconnect
+    pub connected_at: Mutex<Option<Instant>>,
+}
+
+impl ConnectionStats {
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            pdus_sent: self.pdus_sent.load(Ordering::Relaxed),
+            pdus_received: self.pdus_received.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            uptime: self.connected_at.lock().unwrap().map(|x| x.elapsed()),
+        }
+    }
+
+    pub(crate) fn connected(&self) {
+        *self.connected_at.lock().unwrap() = Some(Instant::now());
+    }
+
+    pub(crate) fn sent(&self, bytes: usize) {
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.pdus_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn pdu_received(&self) {
+        self.pdus_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// a copy of `ConnectionStats` at one moment
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct StatsSnapshot {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub pdus_sent: u64,
+    pub pdus_received: u64,
+    pub errors: u64,
+    /// the time since the last successful connect or reconnect
+    pub uptime: Option<Duration>,
+}
+
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sent {} bytes in {} pdus, received {} bytes in {} pdus, {} errors",
+            self.bytes_sent, self.pdus_sent, self.bytes_received, self.pdus_received, self.errors
+        )?;
+        match self.uptime {
+            Some(uptime) => write!(f, ", up {}s", uptime.as_secs()),
+            None => write!(f, ", not connected"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConnectionStats, StatsSnapshot};
+    use std::time::Duration;
+
+    #[test]
+    fn check_snapshot() {
+        let stats = ConnectionStats::default();
+        assert_eq!(stats.snapshot().uptime, None);
+        stats.connected();
+        stats.sent(25);
+        stats.received(10);
+        stats.received(17);
+        stats.pdu_received();
+        stats.error();
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.bytes_sent, 25);
+        assert_eq!(snapshot.pdus_sent, 1);
+        assert_eq!(snapshot.bytes_received, 27);
+        assert_eq!(snapshot.pdus_received, 1);
+        assert_eq!(snapshot.errors, 1);
+        assert!(snapshot.uptime.is_some());
+    }
+
+    #[test]
+    fn check_display() {
+        let snapshot = StatsSnapshot {
+            bytes_sent: 25,
+            bytes_received: 27,
+            pdus_sent: 1,
+            pdus_received: 1,
+            errors: 0,
+            uptime: Some(Duration::from_millis(61_500)),
+        };
+        assert_eq!(
+            snapshot.to_string(),
+            "sent 25 bytes in 1 pdus, received 27 bytes in 1 pdus, 0 errors, up 61s"
+        );
+        let snapshot = StatsSnapshot {
+            uptime: None,
+            ..snapshot
+        };
+        assert!(snapshot.to_string().ends_with(", not connected"));
+    }
+}
@@ -0,0 +1,771 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use bytes::BytesMut;
+use copt::{CoptDecoder, CoptFrame, DisconnectComm, PduType};
+use log::debug;
+use s7_comm::{
+    AckData, Area, BlockListEntry, BlockType, CpuState, DataItemVal, DataItemWriteResponse,
+    DataTransportSize, Frame, Header, HearderAckData, ItemRequest, Job, PlcControlAck, PlcDateTime,
+    ReadVarAckData, ReturnCode, S7CommDecoder, SetupCommunication, TransportSize, UserDataData,
+    UserDataFunctionGroup, UserDataParameter, WriteVarAckData, SUBFUNCTION_GET_BLOCK_INFO,
+    SUBFUNCTION_LIST_BLOCKS, SUBFUNCTION_LIST_BLOCKS_OF_TYPE, SUBFUNCTION_READ_CLOCK,
+    SUBFUNCTION_READ_SZL, SUBFUNCTION_SET_CLOCK, SZL_ID_CPU_STATE, USER_DATA_METHOD_RESPONSE,
+    USER_DATA_TYPE_RESPONSE,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+use tokio_util::codec::{Decoder, Encoder};
+use tpkt::{TpktDecoder, TpktFrame};
+
+use crate::{codec::S7Encoder, error::*};
+
+#[derive(Default)]
+struct State {
+    /// the bytes of every area, by the area and the db number, which is 0
+    /// outside of the data blocks
+    memory: Mutex<HashMap<(u8, u16), Vec<u8>>>,
+    reject_bit_write: AtomicBool,
+    discard_next_write: AtomicBool,
+    next_raw_response: Mutex<Option<Vec<u8>>>,
+    close_on_next_frame: AtomicBool,
+    ignore_next_frame: AtomicBool,
+    stopped: AtomicBool,
+    protected: AtomicBool,
+    clock: Mutex<Option<PlcDateTime>>,
+    szls: Mutex<HashMap<u16, Vec<u8>>>,
+    szl_fragment_size: AtomicUsize,
+    pending_szl: Mutex<VecDeque<Vec<u8>>>,
+    blocks: Mutex<Vec<BlockListEntry>>,
+    block_data: Mutex<BTreeMap<BlockListEntry, Vec<u8>>>,
+    pending_upload: Mutex<VecDeque<Vec<u8>>>,
+    download: Mutex<Option<(BlockListEntry, Vec<u8>)>>,
+    disconnect_request: Mutex<Option<DisconnectComm>>,
+    max_amq: Mutex<Option<u16>>,
+    pipelined_frames: AtomicUsize,
+}
+
+/// the upload id of start upload ack
+const UPLOAD_ID: u32 = 7;
+/// the data of the block in an upload ack
+const UPLOAD_CHUNK_LEN: usize = 200;
+
+/// A plc simulator for tests: answers the copt handshake, setup
+/// communication and read/write var jobs against in-memory data blocks.
+#[derive(Default)]
+pub struct MockS7Server {
+    state: Arc<State>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockS7Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_db(&self, db: u16, data: Vec<u8>) {
+        self.state
+            .memory
+            .lock()
+            .unwrap()
+            .insert(memory_key(Area::DataBlocks, db), data);
+    }
+
+    pub fn db(&self, db: u16) -> Option<Vec<u8>> {
+        self.state
+            .memory
+            .lock()
+            .unwrap()
+            .get(&memory_key(Area::DataBlocks, db))
+            .cloned()
+    }
+
+    /// set the bytes of an area outside of the data blocks, e.g. the merkers
+    pub fn set_area(&self, area: Area, data: Vec<u8>) {
+        self.state
+            .memory
+            .lock()
+            .unwrap()
+            .insert(memory_key(area, 0), data);
+    }
+
+    pub fn area(&self, area: Area) -> Option<Vec<u8>> {
+        self.state
+            .memory
+            .lock()
+            .unwrap()
+            .get(&memory_key(area, 0))
+            .cloned()
+    }
+
+    /// behave like an optimized db, answer bit writes with
+    /// `ReturnCode::NotSupported`
+    pub fn set_reject_bit_write(&self, reject: bool) {
+        self.state.reject_bit_write.store(reject, Ordering::Relaxed);
+    }
+
+    /// acknowledge the items of the next write var without storing them, e.g.
+    /// to simulate the plc program overwriting the value right after the
+    /// write
+    pub fn set_discard_next_write(&self) {
+        self.state.discard_next_write.store(true, Ordering::Relaxed);
+    }
+
+    /// advertise the max amq calling and called in the ack of setup
+    /// communication instead of echoing the ones of the job
+    pub fn set_max_amq(&self, max_amq: u16) {
+        *self.state.max_amq.lock().unwrap() = Some(max_amq);
+    }
+
+    /// the number of frames which arrived before the previous frame was
+    /// answered
+    pub fn pipelined_frames(&self) -> usize {
+        self.state.pipelined_frames.load(Ordering::Relaxed)
+    }
+
+    /// answer the next frame with the raw bytes, e.g. a malformed frame
+    pub fn set_next_raw_response(&self, data: Vec<u8>) {
+        *self.state.next_raw_response.lock().unwrap() = Some(data);
+    }
+
+    /// close the connection instead of answering the next frame, e.g. to
+    /// simulate a plc which went away
+    pub fn set_close_on_next_frame(&self) {
+        self.state
+            .close_on_next_frame
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// leave the next frame unanswered, e.g. to simulate a plc which stopped
+    /// responding
+    pub fn set_ignore_next_frame(&self) {
+        self.state.ignore_next_frame.store(true, Ordering::Relaxed);
+    }
+
+    /// the cpu is in run unless stopped by plc stop
+    pub fn is_stopped(&self) -> bool {
+        self.state.stopped.load(Ordering::Relaxed)
+    }
+
+    /// reject plc control and plc stop with the error of protection level
+    pub fn set_protected(&self, protected: bool) {
+        self.state.protected.store(protected, Ordering::Relaxed);
+    }
+
+    /// the clock set by set clock, read clock answers the system time if
+    /// not set
+    pub fn clock(&self) -> Option<PlcDateTime> {
+        *self.state.clock.lock().unwrap()
+    }
+
+    /// answer read szl of the szl id with the szl data, which starts with
+    /// the szl header
+    pub fn set_szl(&self, szl_id: u16, data: Vec<u8>) {
+        self.state.szls.lock().unwrap().insert(szl_id, data);
+    }
+
+    /// split the szl data into data units of the size, 0: not split
+    pub fn set_szl_fragment_size(&self, size: usize) {
+        self.state.szl_fragment_size.store(size, Ordering::Relaxed);
+    }
+
+    /// add a block to list blocks and to get block info, its mc7 code is 20
+    /// bytes and its load memory is 158 bytes
+    pub fn add_block(&self, block_type: BlockType, block_num: u16) {
+        self.state.blocks.lock().unwrap().push(BlockListEntry {
+            block_type,
+            block_num,
+        });
+    }
+
+    /// add a block with the data of upload and download, which starts with
+    /// the block header
+    pub fn set_block(&self, block_type: BlockType, block_num: u16, data: Vec<u8>) {
+        let block = BlockListEntry {
+            block_type,
+            block_num,
+        };
+        insert_block(&self.state, block, data);
+    }
+
+    /// the data of the block set by `set_block` or by download
+    pub fn block(&self, block_type: BlockType, block_num: u16) -> Option<Vec<u8>> {
+        let block = BlockListEntry {
+            block_type,
+            block_num,
+        };
+        self.state.block_data.lock().unwrap().get(&block).cloned()
+    }
+
+    /// the disconnect request received last, the connection is closed
+    /// after it
+    pub fn disconnect_request(&self) -> Option<DisconnectComm> {
+        self.state.disconnect_request.lock().unwrap().clone()
+    }
+
+    /// listen on a random local port
+    pub async fn start(&mut self) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("failed to bind mock server");
+        let addr = listener
+            .local_addr()
+            .expect("failed to get mock server address");
+        let state = self.state.clone();
+        self.handle = Some(tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve(stream, state).await {
+                        debug!("mock server connection closed: {:?}", e);
+                    }
+                });
+            }
+        }));
+        addr
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+impl Drop for MockS7Server {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<State>) -> Result<()> {
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
+    let mut encoder = S7Encoder::default();
+    let mut buf = [0u8; 1000];
+    let mut src = BytesMut::new();
+    loop {
+        while let Some(frame) = decoder.decode(&mut src)? {
+            if !src.is_empty() {
+                state.pipelined_frames.fetch_add(1, Ordering::Relaxed);
+            }
+            if state.close_on_next_frame.swap(false, Ordering::Relaxed) {
+                stream.shutdown().await?;
+                return Ok(());
+            }
+            if state.ignore_next_frame.swap(false, Ordering::Relaxed) {
+                continue;
+            }
+            let raw_response = state.next_raw_response.lock().unwrap().take();
+            if let Some(raw_response) = raw_response {
+                stream.write_all(&raw_response).await?;
+                continue;
+            }
+            let frame = frame.payload();
+            if let PduType::DisconnectRequest(comm) = &frame.pdu_type {
+                *state.disconnect_request.lock().unwrap() = Some(comm.clone());
+                let confirm = CoptFrame::disconnect_confirm(comm.source_ref, comm.destination_ref);
+                let mut dst = BytesMut::new();
+                encoder.encode(TpktFrame::new(confirm), &mut dst)?;
+                stream.write_all(&dst).await?;
+                return Ok(());
+            }
+            for response in respond(frame, &state) {
+                let mut dst = BytesMut::new();
+                encoder.encode(TpktFrame::new(response), &mut dst)?;
+                stream.write_all(&dst).await?;
+            }
+        }
+        let size = stream.read(&mut buf).await?;
+        if size == 0 {
+            return Ok(());
+        }
+        src.extend_from_slice(&buf[0..size]);
+    }
+}
+
+/// the response to the frame, request download is followed by the first
+/// download block job of the plc
+fn respond(frame: CoptFrame<Frame>, state: &State) -> Vec<CoptFrame<Frame>> {
+    match frame.pdu_type {
+        PduType::ConnectRequest(comm) => {
+            let mut builder = CoptFrame::builder_of_connect()
+                .source_ref([0, 1])
+                .destination_ref(comm.source_ref)
+                .class_and_others(
+                    comm.class,
+                    comm.extended_formats,
+                    comm.no_explicit_flow_control,
+                );
+            for parameter in comm.parameters {
+                builder = builder.push_parameter(parameter);
+            }
+            vec![builder.build_to_confirm()]
+        }
+        PduType::DtData(data) => {
+            let frames = match data.payload() {
+                Frame::Job { header, job } => {
+                    let request_download = matches!(job, Job::RequestDownload(_));
+                    let mut frames = vec![respond_job(header.pdu_ref, job, state)];
+                    if request_download {
+                        frames.extend(next_download_job(header.pdu_ref, state));
+                    }
+                    frames
+                }
+                Frame::UserData {
+                    header,
+                    parameter,
+                    data,
+                } => vec![respond_user_data(header.pdu_ref, parameter, data, state)],
+                Frame::AckData { header, ack_data } => {
+                    respond_download(header.pdu_ref(), ack_data, state)
+                        .into_iter()
+                        .collect()
+                }
+            };
+            frames
+                .into_iter()
+                .map(|x| CoptFrame::builder_of_dt_data(x).build(0, true))
+                .collect()
+        }
+        PduType::ConnectConfirm(_)
+        | PduType::DisconnectRequest(_)
+        | PduType::DisconnectConfirm(_) => Vec::new(),
+    }
+}
+
+fn respond_job(pdu_ref: u16, job: Job, state: &State) -> Frame {
+    let mut memory = state.memory.lock().unwrap();
+    let reject_bit_write = state.reject_bit_write.load(Ordering::Relaxed);
+    match job {
+        Job::SetupCommunication(setup) => {
+            let setup = match *state.max_amq.lock().unwrap() {
+                Some(max_amq) => SetupCommunication::init(max_amq, max_amq, setup.pdu_length()),
+                None => setup,
+            };
+            Frame::AckData {
+                header: HearderAckData::init(pdu_ref, 8, 0, 0, 0),
+                ack_data: AckData::SetupCommunication(setup),
+            }
+        }
+        Job::ReadVar(job) => {
+            let ack = job
+                .parameters_item()
+                .iter()
+                .fold(ReadVarAckData::default(), |ack, item| {
+                    ack.add_response(read_item(&memory, item))
+                });
+            Frame::AckData {
+                header: HearderAckData::init(pdu_ref, 2, ack.bytes_len_data(), 0, 0),
+                ack_data: AckData::ReadVar(ack),
+            }
+        }
+        Job::WriteVar(job) => {
+            let discard = state.discard_next_write.swap(false, Ordering::Relaxed);
+            let ack = job.parameters_item().iter().zip(job.data_item()).fold(
+                WriteVarAckData::default(),
+                |ack, (item, data)| {
+                    ack.add_response(if discard {
+                        DataItemWriteResponse::init(ReturnCode::Success)
+                    } else {
+                        write_item(&mut memory, item, data, reject_bit_write)
+                    })
+                },
+            );
+            Frame::AckData {
+                header: HearderAckData::init(pdu_ref, 2, job.parameters_item().len() as u16, 0, 0),
+                ack_data: AckData::WriteVar(ack),
+            }
+        }
+        Job::PlcControl(_) => Frame::AckData {
+            header: plc_control_header(pdu_ref, false, state),
+            ack_data: AckData::PlcControl(PlcControlAck {
+                parameter: Vec::new(),
+            }),
+        },
+        Job::PlcStop(_) => Frame::AckData {
+            header: plc_control_header(pdu_ref, true, state),
+            ack_data: AckData::PlcStop(PlcControlAck {
+                parameter: Vec::new(),
+            }),
+        },
+        Job::StartUpload(job) => {
+            let block_data = state.block_data.lock().unwrap();
+            let Some(data) = job.block().and_then(|x| block_data.get(&x)) else {
+                // block not found
+                return reject(Frame::start_upload_ack(pdu_ref, 0, 0), 0xd209);
+            };
+            let mut pending = state.pending_upload.lock().unwrap();
+            *pending = data.chunks(UPLOAD_CHUNK_LEN).map(|x| x.to_vec()).collect();
+            Frame::start_upload_ack(pdu_ref, UPLOAD_ID, data.len())
+        }
+        Job::Upload(_) => {
+            let mut pending = state.pending_upload.lock().unwrap();
+            match pending.pop_front() {
+                Some(data) => Frame::upload_ack(pdu_ref, !pending.is_empty(), data),
+                // invalid service sequence
+                None => reject(Frame::upload_ack(pdu_ref, false, Vec::new()), 0x8401),
+            }
+        }
+        Job::EndUpload(_) => {
+            state.pending_upload.lock().unwrap().clear();
+            Frame::end_upload_ack(pdu_ref)
+        }
+        Job::RequestDownload(job) => {
+            let mut download = state.download.lock().unwrap();
+            *download = None;
+            if state.protected.load(Ordering::Relaxed) {
+                // function not permitted in current protection level
+                return reject(Frame::request_download_ack(pdu_ref), 0xd0a1);
+            }
+            let Some(block) = job.block() else {
+                // invalid block number
+                return reject(Frame::request_download_ack(pdu_ref), 0xd210);
+            };
+            *download = Some((block, Vec::new()));
+            Frame::request_download_ack(pdu_ref)
+        }
+        // the jobs of the plc, invalid service sequence
+        Job::DownloadBlock(_) => reject(
+            Frame::download_block_ack(pdu_ref, false, Vec::new()),
+            0x8401,
+        ),
+        Job::DownloadEnded(_) => reject(Frame::download_ended_ack(pdu_ref), 0x8401),
+    }
+}
+
+/// the ack with the error class and error code of the error
+fn reject(frame: Frame, error: u16) -> Frame {
+    let Frame::AckData { header, ack_data } = frame else {
+        return frame;
+    };
+    let [error_class, error_code] = error.to_be_bytes();
+    Frame::AckData {
+        header: HearderAckData::init(
+            header.pdu_ref(),
+            header.parameter_len(),
+            header.data_len(),
+            error_class,
+            error_code,
+        ),
+        ack_data,
+    }
+}
+
+fn insert_block(state: &State, block: BlockListEntry, data: Vec<u8>) {
+    let mut blocks = state.blocks.lock().unwrap();
+    if !blocks.contains(&block) {
+        blocks.push(block);
+    }
+    state.block_data.lock().unwrap().insert(block, data);
+}
+
+/// the next job of the plc requesting the data of the downloaded block
+fn next_download_job(pdu_ref: u16, state: &State) -> Option<Frame> {
+    let download = state.download.lock().unwrap();
+    let (block, _) = download.as_ref()?;
+    Some(Frame::download_block(
+        pdu_ref,
+        block.block_type,
+        block.block_num,
+    ))
+}
+
+/// keep the data of the download block ack of the client, and store the
+/// block after download ended
+fn respond_download(pdu_ref: u16, ack_data: AckData, state: &State) -> Option<Frame> {
+    let mut download = state.download.lock().unwrap();
+    match ack_data {
+        AckData::DownloadBlock(ack) => {
+            let (block, data) = download.as_mut()?;
+            data.extend_from_slice(&ack.data);
+            Some(if ack.more_data() {
+                Frame::download_block(pdu_ref, block.block_type, block.block_num)
+            } else {
+                Frame::download_ended(pdu_ref, block.block_type, block.block_num)
+            })
+        }
+        AckData::DownloadEnded(_) => {
+            let (block, data) = download.take()?;
+            insert_block(state, block, data);
+            None
+        }
+        _ => None,
+    }
+}
+
+fn plc_control_header(pdu_ref: u16, stop: bool, state: &State) -> HearderAckData {
+    if state.protected.load(Ordering::Relaxed) {
+        // function not permitted in current protection level
+        return HearderAckData::init(pdu_ref, 1, 0, 0xd0, 0xa1);
+    }
+    state.stopped.store(stop, Ordering::Relaxed);
+    HearderAckData::init(pdu_ref, 1, 0, 0, 0)
+}
+
+/// answer read szl, read clock, set clock and the block functions, reject
+/// the others
+fn respond_user_data(
+    pdu_ref: u16,
+    parameter: UserDataParameter,
+    data: UserDataData,
+    state: &State,
+) -> Frame {
+    let function_group: u8 = parameter.function_group().into();
+    let continuation = parameter.method == USER_DATA_METHOD_RESPONSE;
+    let mut parameter = UserDataParameter {
+        method: USER_DATA_METHOD_RESPONSE,
+        type_and_function: USER_DATA_TYPE_RESPONSE | function_group,
+        sequence_number: 1,
+        ..parameter
+    };
+    let data = if parameter.function_group() == UserDataFunctionGroup::CpuFunctions
+        && parameter.subfunction == SUBFUNCTION_READ_SZL
+    {
+        let mut pending = state.pending_szl.lock().unwrap();
+        if !continuation {
+            pending.clear();
+            let szl = data
+                .data
+                .get(..2)
+                .and_then(|x| read_szl(u16::from_be_bytes([x[0], x[1]]), state));
+            if let Some(szl) = szl {
+                match state.szl_fragment_size.load(Ordering::Relaxed) {
+                    0 => pending.push_back(szl),
+                    size => pending.extend(szl.chunks(size).map(|x| x.to_vec())),
+                }
+            }
+        }
+        match pending.pop_front() {
+            Some(szl) => {
+                parameter.last_data_unit = pending.is_empty();
+                UserDataData::init_with_octet_string(szl)
+            }
+            None => {
+                // illegal status list id
+                parameter.error_code = 0xd041;
+                UserDataData::init_empty()
+            }
+        }
+    } else if parameter.function_group() == UserDataFunctionGroup::TimeFunctions
+        && parameter.subfunction == SUBFUNCTION_READ_CLOCK
+    {
+        let clock = state.clock.lock().unwrap().unwrap_or_else(PlcDateTime::now);
+        // the century in bcd
+        let century = if clock.year < 90 { 0x20 } else { 0x19 };
+        let mut data = vec![0x00, century];
+        // the clock is the system time or was decoded from set clock
+        data.extend_from_slice(&clock.encode().expect("the clock is valid"));
+        UserDataData::init_with_octet_string(data)
+    } else if parameter.function_group() == UserDataFunctionGroup::TimeFunctions
+        && parameter.subfunction == SUBFUNCTION_SET_CLOCK
+    {
+        match data.data.get(2..).map(PlcDateTime::decode) {
+            Some(Ok(clock)) => *state.clock.lock().unwrap() = Some(clock),
+            // invalid data
+            _ => parameter.error_code = 0xd40c,
+        }
+        UserDataData::init_empty()
+    } else if parameter.function_group() == UserDataFunctionGroup::BlockFunctions {
+        match respond_block_function(parameter.subfunction, &data.data, state) {
+            Ok(data) => UserDataData::init_with_octet_string(data),
+            Err(error_code) => {
+                parameter.error_code = error_code;
+                UserDataData::init_empty()
+            }
+        }
+    } else {
+        // service not supported
+        parameter.error_code = 0xd405;
+        UserDataData::init_empty()
+    };
+    Frame::UserData {
+        header: Header::init(pdu_ref, parameter.bytes_len(), data.bytes_len()),
+        parameter,
+        data,
+    }
+}
+
+/// the data of the block function, or the error code
+fn respond_block_function(
+    subfunction: u8,
+    data: &[u8],
+    state: &State,
+) -> std::result::Result<Vec<u8>, u16> {
+    let blocks = state.blocks.lock().unwrap();
+    let blocks: &[BlockListEntry] = &blocks;
+    let block_type = data.get(1).map(|x| BlockType::from(*x));
+    let of_type = move |block_type: Option<BlockType>| {
+        blocks
+            .iter()
+            .filter(move |x| Some(x.block_type) == block_type)
+    };
+    match subfunction {
+        SUBFUNCTION_LIST_BLOCKS => Ok(BLOCK_TYPES
+            .iter()
+            .flat_map(|x| {
+                let count = of_type(Some(*x)).count() as u16;
+                let [count0, count1] = count.to_be_bytes();
+                [0x30, (*x).into(), count0, count1]
+            })
+            .collect()),
+        SUBFUNCTION_LIST_BLOCKS_OF_TYPE => {
+            let data: Vec<u8> = of_type(block_type)
+                .flat_map(|x| {
+                    let [num0, num1] = x.block_num.to_be_bytes();
+                    [num0, num1, 0x22, 0x05]
+                })
+                .collect();
+            if data.is_empty() {
+                // no block available
+                return Err(0xd20e);
+            }
+            Ok(data)
+        }
+        SUBFUNCTION_GET_BLOCK_INFO => {
+            let block_num = data
+                .get(2..7)
+                .and_then(|x| std::str::from_utf8(x).ok())
+                .and_then(|x| x.parse::<u16>().ok());
+            let Some(block) = of_type(block_type).find(|x| Some(x.block_num) == block_num) else {
+                // block not found
+                return Err(0xd209);
+            };
+            let mut info = vec![0u8; 78];
+            info[..2].copy_from_slice(&[0x01, 0x00]);
+            info[11] = sub_block_type(block.block_type);
+            info[12..14].copy_from_slice(&block.block_num.to_be_bytes());
+            info[14..18].copy_from_slice(&158u32.to_be_bytes());
+            info[40..42].copy_from_slice(&20u16.to_be_bytes());
+            Ok(info)
+        }
+        // service not supported
+        _ => Err(0xd405),
+    }
+}
+
+const BLOCK_TYPES: [BlockType; 7] = [
+    BlockType::Ob,
+    BlockType::Fb,
+    BlockType::Fc,
+    BlockType::Db,
+    BlockType::Sdb,
+    BlockType::Sfc,
+    BlockType::Sfb,
+];
+
+/// the block type of block info
+fn sub_block_type(block_type: BlockType) -> u8 {
+    match block_type {
+        BlockType::Ob => 0x08,
+        BlockType::Db => 0x0a,
+        BlockType::Sdb => 0x0b,
+        BlockType::Fc => 0x0c,
+        BlockType::Sfc => 0x0d,
+        BlockType::Fb => 0x0e,
+        BlockType::Sfb => 0x0f,
+        BlockType::NotSupport(x) => x,
+    }
+}
+
+/// the szl data set by `set_szl`, the cpu state is of the simulated cpu
+fn read_szl(szl_id: u16, state: &State) -> Option<Vec<u8>> {
+    if szl_id != SZL_ID_CPU_STATE {
+        return state.szls.lock().unwrap().get(&szl_id).cloned();
+    }
+    let cpu_state = if state.stopped.load(Ordering::Relaxed) {
+        CpuState::Stop
+    } else {
+        CpuState::Run
+    };
+    let mut record = vec![0u8; 20];
+    record[..4].copy_from_slice(&[0x51, 0x44, 0xff, cpu_state.into()]);
+    let mut szl = vec![0x04, 0x24, 0x00, 0x00, 0x00, 0x14, 0x00, 0x01];
+    szl.extend_from_slice(&record);
+    Some(szl)
+}
+
+fn memory_key(area: Area, db_number: u16) -> (u8, u16) {
+    (area.into(), db_number)
+}
+
+fn read_item(memory: &HashMap<(u8, u16), Vec<u8>>, item: &ItemRequest) -> DataItemVal {
+    let Some(db) = memory.get(&memory_key(item.area().clone(), item.db_number())) else {
+        return DataItemVal::init_with_bytes(ReturnCode::Err, &[]);
+    };
+    let start = item.byte_addr() as usize;
+    match item.transport_size_type() {
+        TransportSize::Bit => match db.get(start) {
+            Some(byte) => {
+                DataItemVal::init_with_bit(ReturnCode::Success, (byte >> item.bit_addr()) & 1 > 0)
+            }
+            None => DataItemVal::init_with_bytes(ReturnCode::InvalidAddress, &[]),
+        },
+        // addressed by number, 2 bytes each, answered as an octet string
+        TransportSize::Counter | TransportSize::Timer => {
+            let start = start * 2;
+            match db.get(start..start + item.length() as usize * 2) {
+                Some(data) => DataItemVal {
+                    return_code: ReturnCode::Success,
+                    transport_size_type: DataTransportSize::OctetString,
+                    length: data.len() as u16,
+                    data: data.to_vec(),
+                },
+                None => DataItemVal::init_with_bytes(ReturnCode::InvalidAddress, &[]),
+            }
+        }
+        transport_size => {
+            let unit = match transport_size {
+                TransportSize::Word => 2,
+                TransportSize::DWord => 4,
+                _ => 1,
+            };
+            match db.get(start..start + item.length() as usize * unit) {
+                Some(data) => DataItemVal::init_with_bytes(ReturnCode::Success, data),
+                None => DataItemVal::init_with_bytes(ReturnCode::InvalidAddress, &[]),
+            }
+        }
+    }
+}
+
+fn write_item(
+    memory: &mut HashMap<(u8, u16), Vec<u8>>,
+    item: &ItemRequest,
+    data: &DataItemVal,
+    reject_bit_write: bool,
+) -> DataItemWriteResponse {
+    let Some(db) = memory.get_mut(&memory_key(item.area().clone(), item.db_number())) else {
+        return DataItemWriteResponse::init(ReturnCode::Err);
+    };
+    let start = item.byte_addr() as usize;
+    let return_code = match item.transport_size_type() {
+        TransportSize::Bit if reject_bit_write => ReturnCode::NotSupported,
+        TransportSize::Bit => match (db.get_mut(start), data.data.first()) {
+            (Some(byte), Some(value)) => {
+                if *value > 0 {
+                    *byte |= 1 << item.bit_addr();
+                } else {
+                    *byte &= !(1 << item.bit_addr());
+                }
+                ReturnCode::Success
+            }
+            _ => ReturnCode::InvalidAddress,
+        },
+        _ => match db.get_mut(start..start + data.data.len()) {
+            Some(dst) => {
+                dst.copy_from_slice(&data.data);
+                ReturnCode::Success
+            }
+            None => ReturnCode::InvalidAddress,
+        },
+    };
+    DataItemWriteResponse::init(return_code)
+}
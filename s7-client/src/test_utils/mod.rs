@@ -0,0 +1,3 @@
+mod mock_server;
+
+pub use mock_server::MockS7Server;
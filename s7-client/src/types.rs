@@ -0,0 +1,352 @@
+use crate::error::*;
+use s7_comm::{CpuState, DataItemVal, ItemRequest, ReturnCode, TransportSize};
+use std::ops::Range;
+
+/// the memory area of an item, the db number is only sent for
+/// `MemoryArea::DataBlocks`
+pub type MemoryArea = s7_comm::Area;
+
+/// max items of a read var or write var job
+pub(crate) const MAX_ITEMS_PER_JOB: usize = 20;
+/// tpkt header and copt header of data
+pub(crate) const ISO_HEAD_LEN: usize = 4 + 3;
+/// s7 header, function and item count of a job
+const JOB_HEAD_LEN: usize = 10 + 2;
+/// s7 header, error class, error code, function and item count of an ack
+const ACK_HEAD_LEN: usize = 12 + 2;
+/// the parameter of an item request
+const ITEM_REQUEST_LEN: usize = 12;
+/// return code, transport size and length of a data item
+const DATA_ITEM_HEAD_LEN: usize = 4;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReadItem {
+    pub area: MemoryArea,
+    pub db: u16,
+    pub byte_offset: u16,
+    pub length: u16,
+}
+
+impl ReadItem {
+    pub fn db(db: u16, byte_offset: u16, length: u16) -> Self {
+        Self {
+            area: MemoryArea::DataBlocks,
+            db,
+            byte_offset,
+            length,
+        }
+    }
+
+    pub(crate) fn to_request(&self) -> ItemRequest {
+        ItemRequest::init_byte(
+            db_number(&self.area, self.db),
+            self.area.clone(),
+            self.byte_offset,
+            self.length,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WriteItem {
+    pub area: MemoryArea,
+    pub db: u16,
+    pub byte_offset: u16,
+    pub data: Vec<u8>,
+}
+
+impl WriteItem {
+    pub fn db(db: u16, byte_offset: u16, data: Vec<u8>) -> Self {
+        Self {
+            area: MemoryArea::DataBlocks,
+            db,
+            byte_offset,
+            data,
+        }
+    }
+
+    pub(crate) fn db_number(&self) -> Option<u16> {
+        db_number(&self.area, self.db)
+    }
+}
+
+/// the typed value of an item of a multi-item read
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReadValue {
+    Bool(bool),
+    Int(i16),
+    Real(f32),
+    /// the raw bytes of an item added by `S7ReadBuilder::add_item`
+    Bytes(Vec<u8>),
+}
+
+/// the value of an item, or the return code if the plc rejected the item
+pub type ReadResult = std::result::Result<ReadValue, ReturnCode>;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum ReadValueType {
+    Bool,
+    Int,
+    Real,
+    Bytes,
+}
+
+impl ReadValueType {
+    pub(crate) fn decode(&self, item: DataItemVal) -> Result<ReadResult> {
+        if !item.return_code.is_success() {
+            return Ok(Err(item.return_code));
+        }
+        Ok(Ok(match self {
+            ReadValueType::Bool => ReadValue::Bool(item.as_bool()?),
+            ReadValueType::Int => ReadValue::Int(item.as_i16()?),
+            ReadValueType::Real => ReadValue::Real(item.as_f32()?),
+            ReadValueType::Bytes => ReadValue::Bytes(item.data),
+        }))
+    }
+}
+
+pub(crate) fn db_number(area: &MemoryArea, db: u16) -> Option<u16> {
+    (*area == MemoryArea::DataBlocks).then_some(db)
+}
+
+/// the bytes of a data item, with the fill byte of odd length
+fn data_item_len(len: usize) -> usize {
+    DATA_ITEM_HEAD_LEN + len + len % 2
+}
+
+/// Split the read items into jobs whose request and response both fit in
+/// the pdu length.
+pub(crate) fn partition_read(items: &[ReadItem], pdu_len: u16) -> Result<Vec<Range<usize>>> {
+    partition(
+        items
+            .iter()
+            .map(|x| (ITEM_REQUEST_LEN, data_item_len(x.length as usize))),
+        pdu_len,
+    )
+}
+
+/// Split the write items into jobs whose request and response both fit in
+/// the pdu length.
+pub(crate) fn partition_write(items: &[WriteItem], pdu_len: u16) -> Result<Vec<Range<usize>>> {
+    partition(items.iter().map(|x| write_item_size(x.data.len())), pdu_len)
+}
+
+/// Split a read of `len` bytes of a db from `byte_offset` into items which
+/// fit in the response of the pdu length, one item per job.
+pub(crate) fn chunk_db_read(
+    db: u16,
+    byte_offset: u16,
+    len: u16,
+    pdu_len: u16,
+) -> Result<Vec<ReadItem>> {
+    // the read may end at the last byte 65535
+    if byte_offset as u32 + len as u32 > u16::MAX as u32 + 1 {
+        return Err(Error::Err(format!(
+            "DB{} {}+{} exceeds the address space",
+            db, byte_offset, len
+        )));
+    }
+    let max_len = max_read_item_len(pdu_len);
+    if max_len == 0 {
+        return Err(Error::Err(format!("pdu length {} is too small", pdu_len)));
+    }
+    Ok((0..len as usize)
+        .step_by(max_len)
+        .map(|offset| {
+            let chunk = max_len.min(len as usize - offset);
+            ReadItem::db(db, byte_offset + offset as u16, chunk as u16)
+        })
+        .collect())
+}
+
+/// the bytes of the data of a read item which fits in the response of the
+/// pdu length alone, even so that no fill byte is needed
+pub(crate) fn max_read_item_len(pdu_len: u16) -> usize {
+    (pdu_len as usize).saturating_sub(ACK_HEAD_LEN + DATA_ITEM_HEAD_LEN) & !1
+}
+
+/// The byte address and the byte length of `count` values of `size` bytes
+/// from the value `offset` of a db, e.g. the word offset 2 is the byte
+/// address 4.
+pub(crate) fn db_value_range(db: u16, offset: u16, count: u16, size: u16) -> Result<(u16, u16)> {
+    let range = offset.checked_mul(size).and_then(|addr| {
+        count
+            .checked_mul(size)
+            // the values may end at the last byte 65535
+            .filter(|len| addr as u32 + *len as u32 <= u16::MAX as u32 + 1)
+            .map(|len| (addr, len))
+    });
+    range.ok_or_else(|| {
+        Error::Err(format!(
+            "DB{} {} values of {} bytes from {} exceed the address space",
+            db, count, size, offset
+        ))
+    })
+}
+
+/// the bytes of a read item in the request and in the response
+pub(crate) fn read_item_size(item: &ItemRequest) -> (usize, usize) {
+    let len = match item.transport_size_type() {
+        TransportSize::Bit => 1,
+        TransportSize::Counter | TransportSize::Timer => item.length() as usize * 2,
+        _ => item.length() as usize,
+    };
+    (ITEM_REQUEST_LEN, data_item_len(len))
+}
+
+/// the bytes of a write item of `len` bytes in the request and in the
+/// response
+pub(crate) fn write_item_size(len: usize) -> (usize, usize) {
+    (ITEM_REQUEST_LEN + data_item_len(len), 1)
+}
+
+/// the bytes of the request and of the response of a job with the items
+pub(crate) fn job_size(sizes: impl Iterator<Item = (usize, usize)>) -> (usize, usize) {
+    sizes.fold((JOB_HEAD_LEN, ACK_HEAD_LEN), |(request, response), x| {
+        (request + x.0, response + x.1)
+    })
+}
+
+/// `sizes`: the bytes of every item in the request and in the response
+fn partition(
+    sizes: impl Iterator<Item = (usize, usize)>,
+    pdu_len: u16,
+) -> Result<Vec<Range<usize>>> {
+    let sizes: Vec<_> = sizes.collect();
+    let pdu = pdu_len as usize;
+    if let Some(index) = sizes.iter().position(|(request, response)| {
+        JOB_HEAD_LEN + request > pdu || ACK_HEAD_LEN + response > pdu
+    }) {
+        return Err(Error::Err(format!(
+            "item {} exceeds the pdu length {}",
+            index, pdu
+        )));
+    }
+    Ok(split_by_pdu(&sizes, pdu_len))
+}
+
+/// Split the items into the minimum jobs in order, an item which doesn't fit
+/// in the pdu length takes a job alone.
+pub(crate) fn split_by_pdu(sizes: &[(usize, usize)], pdu_len: u16) -> Vec<Range<usize>> {
+    let pdu_len = pdu_len as usize;
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let (mut request_len, mut response_len) = (JOB_HEAD_LEN, ACK_HEAD_LEN);
+    for (end, (request, response)) in sizes.iter().enumerate() {
+        if end > start
+            && (end - start == MAX_ITEMS_PER_JOB
+                || request_len + request > pdu_len
+                || response_len + response > pdu_len)
+        {
+            batches.push(start..end);
+            start = end;
+            request_len = JOB_HEAD_LEN;
+            response_len = ACK_HEAD_LEN;
+        }
+        request_len += request;
+        response_len += response;
+    }
+    if sizes.len() > start {
+        batches.push(start..sizes.len());
+    }
+    batches
+}
+
+/// the cpu state before and after a start or stop of
+/// `S7Client::start_plc_checked` or `S7Client::stop_plc_checked`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ControlOutcome {
+    pub previous: CpuState,
+    pub current: CpuState,
+}
+
+impl ControlOutcome {
+    /// the cpu changed its state, i.e. it wasn't in the state already
+    pub fn changed(&self) -> bool {
+        self.previous != self.current
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check_partition_read() {
+        // 14 + 4 + 100 + 4 + 100 fits in 240, the third item doesn't
+        let items = vec![ReadItem::db(1, 0, 100); 3];
+        assert_eq!(partition_read(&items, 240).unwrap(), vec![0..2, 2..3]);
+        // the fill byte of odd length
+        let items = vec![ReadItem::db(1, 0, 111); 2];
+        assert_eq!(partition_read(&items, 245).unwrap(), vec![0..1, 1..2]);
+        assert_eq!(partition_read(&items, 246).unwrap(), vec![0..2]);
+
+        let items = vec![ReadItem::db(1, 0, 1); 25];
+        assert_eq!(partition_read(&items, 960).unwrap(), vec![0..20, 20..25]);
+
+        assert!(partition_read(&[], 240).unwrap().is_empty());
+        assert!(partition_read(&[ReadItem::db(1, 0, 223)], 240).is_err());
+    }
+
+    #[test]
+    fn check_chunk_db_read() {
+        // 240 - 14 - 4 = 222 bytes per chunk
+        let items = chunk_db_read(1, 10, 2048, 240).unwrap();
+        assert_eq!(items.len(), 10);
+        assert_eq!(items[0], ReadItem::db(1, 10, 222));
+        assert_eq!(items[1], ReadItem::db(1, 232, 222));
+        assert_eq!(items[9], ReadItem::db(1, 10 + 9 * 222, 2048 - 9 * 222));
+        assert_eq!(
+            partition_read(&items, 240).unwrap().len(),
+            10,
+            "one chunk per job"
+        );
+
+        // odd space is rounded down to even
+        let items = chunk_db_read(1, 0, 444, 241).unwrap();
+        assert_eq!(
+            items,
+            vec![ReadItem::db(1, 0, 222), ReadItem::db(1, 222, 222)]
+        );
+        assert_eq!(
+            chunk_db_read(1, 0, 222, 240).unwrap(),
+            vec![ReadItem::db(1, 0, 222)]
+        );
+        assert!(chunk_db_read(1, 0, 0, 240).unwrap().is_empty());
+
+        assert_eq!(
+            chunk_db_read(1, 65534, 2, 240).unwrap(),
+            vec![ReadItem::db(1, 65534, 2)]
+        );
+        assert!(chunk_db_read(1, 65535, 2, 240).is_err());
+        assert!(chunk_db_read(1, 0, 2, 19).is_err());
+    }
+
+    #[test]
+    fn check_db_value_range() {
+        assert_eq!(db_value_range(1, 2, 3, 2).unwrap(), (4, 6));
+        assert_eq!(db_value_range(1, 2, 3, 4).unwrap(), (8, 12));
+        assert_eq!(db_value_range(1, 16383, 1, 4).unwrap(), (65532, 4));
+        assert_eq!(db_value_range(1, 0, 0, 2).unwrap(), (0, 0));
+
+        assert!(db_value_range(1, 32768, 1, 2).is_err());
+        assert!(db_value_range(1, 0, 32768, 2).is_err());
+        assert!(db_value_range(1, 16383, 2, 4).is_err());
+    }
+
+    #[test]
+    fn check_split_by_pdu() {
+        // an oversized item takes a job alone
+        let sizes = [(12, 6), (12, 300), (12, 6), (12, 6)];
+        assert_eq!(split_by_pdu(&sizes, 240), vec![0..1, 1..2, 2..4]);
+        assert!(split_by_pdu(&[], 240).is_empty());
+    }
+
+    #[test]
+    fn check_partition_write() {
+        // 12 + 3 * (12 + 4 + 50) = 210
+        let items = vec![WriteItem::db(1, 0, vec![0; 50]); 4];
+        assert_eq!(partition_write(&items, 240).unwrap(), vec![0..3, 3..4]);
+        assert!(partition_write(&[WriteItem::db(1, 0, vec![0; 213])], 240).is_err());
+    }
+}
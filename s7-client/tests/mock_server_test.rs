@@ -0,0 +1,1028 @@
+use anyhow::Result;
+use s7_client::{
+    build_s7_read,
+    copt::DisconnectComm,
+    s7_comm::{
+        self, BlockListEntry, BlockType, CpuFamily, CpuState, PlcDateTime, ReturnCode, SzlId,
+    },
+    test_utils::MockS7Server,
+    Area, BitAddr, ConnectMode, ConnectionType, ControlOutcome, DataSizeType, Error, Options,
+    ReadItem, ReadTransport, ReadValue, S7Client, S7ClientConfig, WriteItem,
+};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+async fn connect(addr: SocketAddr) -> Result<S7Client> {
+    let options = Options::new(
+        addr.ip(),
+        addr.port(),
+        ConnectMode::init_rack_slot(ConnectionType::PG, 0, 1),
+    );
+    Ok(S7Client::connect(options).await?)
+}
+
+#[tokio::test]
+async fn write_and_read_back() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 16]);
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+
+    let resp = client
+        .write_bytes(
+            Some(1),
+            s7_client::s7_comm::Area::DataBlocks,
+            4,
+            &[1, 2, 3, 4],
+        )
+        .await?;
+    assert!(resp.return_code.is_ok());
+
+    let item = client
+        .read(&Area::DataBausteine(
+            1,
+            DataSizeType::Byte { addr: 4, len: 4 },
+        ))
+        .await?;
+    assert!(item.return_code.is_ok());
+    assert_eq!(item.data, [1, 2, 3, 4]);
+
+    let resp = client
+        .write_bit(Some(1), s7_client::s7_comm::Area::DataBlocks, 0, 6, true)
+        .await?;
+    assert!(resp.return_code.is_ok());
+
+    let item = client
+        .read(&Area::DataBausteine(
+            1,
+            DataSizeType::Bit {
+                addr: 0,
+                bit_addr: BitAddr::Addr6,
+            },
+        ))
+        .await?;
+    assert!(item.return_code.is_ok());
+    assert_eq!(item.data, [1]);
+    assert_eq!(server.db(1).unwrap()[0..8], [0x40, 0, 0, 0, 1, 2, 3, 4]);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn connect_rack_slot_and_db_bytes() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(2, vec![0; 8]);
+    let addr = server.start().await;
+
+    let mut client = S7Client::connect_rack_slot(addr, 0, 1).await?;
+    assert_eq!(client.pdu_length(), 480);
+
+    client.write_db_bytes(2, 2, &[0xaa, 0xbb]).await?;
+    assert_eq!(client.read_db_bytes(2, 1, 3).await?, [0, 0xaa, 0xbb]);
+    assert_eq!(server.db(2).unwrap(), [0, 0, 0xaa, 0xbb, 0, 0, 0, 0]);
+
+    assert!(matches!(
+        client.read_db_bytes(3, 0, 1).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 0, .. }))
+    ));
+    assert!(client.write_db_bytes(2, 7, &[1, 2]).await.is_err());
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn disconnect() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let addr = server.start().await;
+    let client = connect(addr).await?;
+    client.disconnect().await?;
+
+    // the mock server records the request when it reads it
+    for _ in 0..100 {
+        if server.disconnect_request().is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(
+        server.disconnect_request(),
+        Some(DisconnectComm {
+            destination_ref: [0, 1],
+            source_ref: [0, 1],
+            reason: DisconnectComm::REASON_NORMAL,
+        })
+    );
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn connection_stats() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0x12, 0x34]);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    // copt connect and setup communication
+    let stats = client.stats();
+    assert_eq!((stats.pdus_sent, stats.pdus_received), (2, 2));
+    assert!(stats.uptime.is_some());
+
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0x12, 0x34]);
+    let read = client.stats();
+    assert_eq!((read.pdus_sent, read.pdus_received), (3, 3));
+    assert_eq!(read.bytes_sent - stats.bytes_sent, 31);
+    assert_eq!(read.bytes_received - stats.bytes_received, 27);
+    assert_eq!(read.errors, 0);
+
+    server.set_close_on_next_frame();
+    assert!(client.read_db_bytes(1, 0, 2).await.is_err());
+    assert_eq!(client.stats().errors, 1);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_db_of_transport() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, (0..16).collect());
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    assert_eq!(
+        client
+            .read_db(1, 1, ReadTransport::Bit(BitAddr::Addr0), 1)
+            .await?,
+        [0x01]
+    );
+    assert_eq!(
+        client.read_db(1, 2, ReadTransport::Byte, 3).await?,
+        [0x02, 0x03, 0x04]
+    );
+    assert_eq!(
+        client.read_db(1, 2, ReadTransport::Word, 2).await?,
+        [0x02, 0x03, 0x04, 0x05]
+    );
+    assert_eq!(
+        client.read_db(1, 8, ReadTransport::DWord, 2).await?,
+        (8..16).collect::<Vec<u8>>()
+    );
+    assert!(matches!(
+        client.read_db(1, 12, ReadTransport::DWord, 2).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 0, .. }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn connect_with_config() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0x12, 0x34]);
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default()
+        .slot(1)
+        .connection_timeout(Duration::from_secs(1))
+        .request_timeout(Duration::from_secs(1))
+        .max_pdu_size(240);
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+    assert_eq!(client.pdu_length(), 240);
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0x12, 0x34]);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn negotiated_max_amq() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_max_amq(1);
+    let data: Vec<u8> = (0..1000).map(|x| x as u8).collect();
+    server.set_db(1, data.clone());
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default()
+        .slot(1)
+        .max_amq_calling(3)
+        .max_pdu_size(240);
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+    assert_eq!((client.max_amq_calling(), client.max_amq_called()), (1, 1));
+
+    // the chunks are several jobs, each sent after the ack of the previous one
+    let pdus_sent = client.stats().pdus_sent;
+    assert_eq!(client.read_db_bytes_chunked(1, 0, 1000).await?, data);
+    assert!(client.stats().pdus_sent - pdus_sent > 1);
+    client.write_db_bytes(1, 0, &[0; 100]).await?;
+    assert_eq!(server.pipelined_frames(), 0);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_and_write_multi() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, (0..=255).cycle().take(600).collect());
+    server.set_db(2, vec![0; 4]);
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default().slot(1).max_pdu_size(240);
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+
+    // two jobs: the responses of 200 bytes do not fit in one pdu together
+    let items = vec![
+        ReadItem::db(1, 0, 200),
+        ReadItem::db(2, 1, 2),
+        ReadItem::db(1, 300, 200),
+        ReadItem::db(1, 599, 1),
+    ];
+    let data = client.read_multi(items).await?;
+    assert_eq!(data.len(), 4);
+    assert_eq!(data[0], (0..200).collect::<Vec<u8>>());
+    assert_eq!(data[1], [0, 0]);
+    assert_eq!(data[2][..2], [44, 45]);
+    assert_eq!(data[3], [87]);
+
+    let items = vec![
+        WriteItem::db(2, 0, vec![1, 2]),
+        WriteItem::db(1, 0, vec![0xff; 150]),
+        WriteItem::db(1, 598, vec![0xaa, 0xbb]),
+    ];
+    client.write_multi(items).await?;
+    assert_eq!(server.db(2).unwrap(), [1, 2, 0, 0]);
+    assert_eq!(server.db(1).unwrap()[..150], [0xff; 150]);
+    assert_eq!(server.db(1).unwrap()[598..], [0xaa, 0xbb]);
+
+    let items = vec![ReadItem::db(1, 0, 2), ReadItem::db(3, 0, 2)];
+    assert!(matches!(
+        client.read_multi(items).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 1, .. }))
+    ));
+    let items = vec![WriteItem::db(2, 0, vec![0]), WriteItem::db(2, 4, vec![0])];
+    assert!(matches!(
+        client.write_multi(items).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 1, .. }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_and_write_db_values() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 32]);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    client.write_db_words(1, 1, &[0x1234, 0xabcd]).await?;
+    assert_eq!(server.db(1).unwrap()[..6], [0, 0, 0x12, 0x34, 0xab, 0xcd]);
+    assert_eq!(client.read_db_words(1, 1, 2).await?, [0x1234, 0xabcd]);
+
+    client.write_db_dwords(1, 2, &[0x12345678]).await?;
+    assert_eq!(server.db(1).unwrap()[8..12], [0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(client.read_db_dwords(1, 2, 1).await?, [0x12345678]);
+
+    client.write_db_ints(1, 6, &[-2, 300]).await?;
+    assert_eq!(server.db(1).unwrap()[12..16], [0xff, 0xfe, 0x01, 0x2c]);
+    assert_eq!(client.read_db_ints(1, 6, 2).await?, [-2, 300]);
+
+    client.write_db_reals(1, 4, &[1.5, -0.25]).await?;
+    assert_eq!(server.db(1).unwrap()[16..20], [0x3f, 0xc0, 0x00, 0x00]);
+    assert_eq!(client.read_db_reals(1, 4, 2).await?, [1.5, -0.25]);
+
+    // the byte address of the word 32768 exceeds u16, nothing is sent
+    let sent = client.stats().pdus_sent;
+    assert!(client.read_db_words(1, 32768, 1).await.is_err());
+    assert!(client.write_db_dwords(1, 16383, &[0, 0]).await.is_err());
+    assert_eq!(client.stats().pdus_sent, sent);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_and_write_bits() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0b0000_1000, 0]);
+    server.set_area(s7_comm::Area::Merker, vec![0, 0b0100_0000]);
+    server.set_area(s7_comm::Area::ProcessInput, vec![0b0000_0001]);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    assert!(client.read_db_bit(1, 0, 3).await?);
+    assert!(!client.read_db_bit(1, 0, 2).await?);
+    assert!(client.read_merker_bit(1, 6).await?);
+    assert!(client.read_input_bit(0, 0).await?);
+    assert!(!client.read_input_bit(0, 1).await?);
+
+    // toggle M1.6 and DB1.DBX1.7
+    for value in [false, true] {
+        client
+            .write_bit(None, s7_comm::Area::Merker, 1, 6, value)
+            .await?;
+        assert_eq!(client.read_merker_bit(1, 6).await?, value);
+        client
+            .write_bit(Some(1), s7_comm::Area::DataBlocks, 1, 7, value)
+            .await?;
+        assert_eq!(client.read_db_bit(1, 1, 7).await?, value);
+    }
+    assert_eq!(
+        server.area(s7_comm::Area::Merker),
+        Some(vec![0, 0b0100_0000])
+    );
+    assert_eq!(server.db(1), Some(vec![0b0000_1000, 0b1000_0000]));
+
+    // the bit address is checked before sending
+    let sent = client.stats().pdus_sent;
+    assert!(matches!(
+        client.read_db_bit(1, 0, 8).await,
+        Err(Error::InvalidBitAddr(8))
+    ));
+    assert!(matches!(
+        client
+            .write_bit(None, s7_comm::Area::Merker, 0, 9, true)
+            .await,
+        Err(Error::InvalidBitAddr(9))
+    ));
+    assert_eq!(client.stats().pdus_sent, sent);
+
+    assert!(matches!(
+        client.read_merker_bit(5, 0).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 0, .. }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_counter_and_timer() -> Result<()> {
+    let mut server = MockS7Server::new();
+    // C0 = 0, C1 = 123
+    server.set_area(s7_comm::Area::Counter, vec![0x00, 0x00, 0x01, 0x23]);
+    // T0 = 2min 5s in the time base of 1s, T1 = 9990s, T2 isn't bcd
+    server.set_area(
+        s7_comm::Area::Timer,
+        vec![0x21, 0x25, 0x39, 0x99, 0x0a, 0x00],
+    );
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    assert_eq!(client.read_counter(0).await?, 0);
+    assert_eq!(client.read_counter(1).await?, 123);
+    assert_eq!(client.read_timer(0).await?, Duration::from_secs(125));
+    assert_eq!(client.read_timer(1).await?, Duration::from_secs(9990));
+    assert!(client.read_timer(2).await.is_err());
+    assert!(matches!(
+        client.read_counter(2).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 0, .. }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_exceeding_pdu_length() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 300]);
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default().slot(1).max_pdu_size(240);
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+
+    // 12 bytes of the job, 12 bytes of the item and 4 + 250 bytes of the data
+    assert!(matches!(
+        client.write_db_bytes(1, 0, &[0xff; 250]).await,
+        Err(Error::FrameTooLarge {
+            limit: 240,
+            actual: 278
+        })
+    ));
+    assert_eq!(server.db(1).unwrap(), [0; 300]);
+
+    // the frame is not sent, the connection is still usable
+    client.write_db_bytes(1, 0, &[0xff; 200]).await?;
+    assert_eq!(server.db(1).unwrap()[..200], [0xff; 200]);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn connection_lost() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 4]);
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+    client.set_tcp_keepalive(Duration::from_secs(10))?;
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0, 0]);
+
+    server.set_close_on_next_frame();
+    assert!(matches!(
+        client.read_db_bytes(1, 0, 2).await,
+        Err(Error::ConnectionLost)
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn reconnect_after_connection_lost() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0x12, 0x34]);
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default().slot(1).with_auto_reconnect(
+        3,
+        Duration::from_millis(10),
+        Duration::from_millis(50),
+    );
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+    server.set_close_on_next_frame();
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0x12, 0x34]);
+    server.set_close_on_next_frame();
+    client.write_db_bytes(1, 1, &[0x56]).await?;
+    assert_eq!(server.db(1).unwrap(), [0x12, 0x56]);
+
+    let mut client = connect(addr).await?;
+    server.set_close_on_next_frame();
+    assert!(matches!(
+        client.read_db_bytes(1, 0, 2).await,
+        Err(Error::ConnectionLost)
+    ));
+    client.reconnect().await?;
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0x12, 0x56]);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn keepalive_reconnects_dropped_session() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0x12, 0x34]);
+    let addr = server.start().await;
+
+    let mut client = connect(addr)
+        .await?
+        .with_keepalive(Duration::from_millis(20));
+    let reconnects = Arc::new(Mutex::new(Vec::new()));
+    let handler_reconnects = reconnects.clone();
+    client.set_reconnect_handler(move |addr| handler_reconnects.lock().unwrap().push(addr));
+
+    client.keepalive().await?;
+    assert!(reconnects.lock().unwrap().is_empty());
+
+    server.set_close_on_next_frame();
+    client.keepalive().await?;
+    assert_eq!(*reconnects.lock().unwrap(), [addr]);
+    assert_eq!(client.read_db_bytes(1, 0, 2).await?, [0x12, 0x34]);
+
+    let mut client = connect(addr).await?;
+    assert!(client.keepalive().await.is_err());
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_timeout() -> Result<()> {
+    let config = S7ClientConfig::default()
+        .slot(1)
+        .request_timeout(Duration::from_millis(100));
+
+    // accepts the connection but never answers
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+    let silent = listener.local_addr()?;
+    let accept = tokio::spawn(async move {
+        let connection = listener.accept().await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        connection
+    });
+    assert!(matches!(
+        S7Client::connect_with_config(silent, config.clone()).await,
+        Err(Error::Timeout {
+            operation: "copt_connect"
+        })
+    ));
+    accept.abort();
+
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 2]);
+    let addr = server.start().await;
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+    server.set_ignore_next_frame();
+    assert!(matches!(
+        client
+            .read_vec(&[Area::DataBausteine(
+                1,
+                DataSizeType::Byte { addr: 0, len: 2 }
+            )])
+            .await,
+        Err(Error::Timeout {
+            operation: "read_vec"
+        })
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_db_bytes_chunked() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let data: Vec<u8> = (0..=255).cycle().take(2048).collect();
+    server.set_db(1, data.clone());
+    let addr = server.start().await;
+
+    let config = S7ClientConfig::default().slot(1).max_pdu_size(240);
+    let mut client = S7Client::connect_with_config(addr, config).await?;
+
+    assert_eq!(client.read_db_bytes_chunked(1, 0, 2048).await?, data);
+    assert_eq!(
+        client.read_db_bytes_chunked(1, 221, 3).await?,
+        data[221..224]
+    );
+
+    // the third chunk is out of the db
+    assert!(matches!(
+        client.read_db_bytes_chunked(1, 1600, 600).await,
+        Err(Error::S7CommErr(s7_comm::Error::ItemError { index: 2, .. }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_values_of_mixed_types() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let mut db = vec![0; 12];
+    db[0] = 0b0000_0100;
+    db[2..4].copy_from_slice(&(-1234i16).to_be_bytes());
+    db[4..8].copy_from_slice(&12.5f32.to_be_bytes());
+    server.set_db(1, db);
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+    let builder = build_s7_read()
+        .add_db_real(1, 4)
+        .add_db_int(1, 2)
+        .add_db_bool(1, 0, 2)
+        .add_db_bool(1, 0, 3)
+        .add_db_int(2, 0)
+        .add_db_real(1, 10);
+    let results = client.read_values(builder).await?;
+    assert_eq!(
+        results,
+        [
+            Ok(ReadValue::Real(12.5)),
+            Ok(ReadValue::Int(-1234)),
+            Ok(ReadValue::Bool(true)),
+            Ok(ReadValue::Bool(false)),
+            Err(ReturnCode::Err),
+            Err(ReturnCode::InvalidAddress),
+        ]
+    );
+
+    let builder = (0..21).fold(build_s7_read(), |builder, x| builder.add_db_int(1, x));
+    assert!(matches!(
+        client.read_values(builder).await,
+        Err(Error::TooManyItems { .. })
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_verified() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 8]);
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+    client
+        .write_db_bytes_verified(1, 2, &[0x12, 0x34, 0x56])
+        .await?;
+    assert_eq!(server.db(1).unwrap(), [0, 0, 0x12, 0x34, 0x56, 0, 0, 0]);
+
+    // the plc program overwrites the bytes before the read back
+    server.set_discard_next_write();
+    assert!(matches!(
+        client
+            .write_db_bytes_verified(1, 2, &[0x12, 0x78, 0x56])
+            .await,
+        Err(Error::VerificationMismatch { offset: 3 })
+    ));
+
+    client.write_db_bit_verified(1, 0, 3, true).await?;
+    assert_eq!(server.db(1).unwrap()[0], 0b0000_1000);
+    server.set_discard_next_write();
+    assert!(matches!(
+        client.write_db_bit_verified(1, 1, 0, true).await,
+        Err(Error::VerificationMismatch { offset: 1 })
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_values_optimized() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let mut db = vec![0; 12];
+    db[0] = 0b0000_0100;
+    db[2..4].copy_from_slice(&(-1234i16).to_be_bytes());
+    db[4..8].copy_from_slice(&12.5f32.to_be_bytes());
+    server.set_db(1, db);
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+    let builder = build_s7_read()
+        .add_db_real(1, 4)
+        .add_db_int(1, 2)
+        .add_db_bool(1, 0, 2)
+        .add_db_int(2, 0)
+        .optimize(4);
+    let pdus_sent = client.stats().pdus_sent;
+    assert_eq!(
+        client.read_values(builder.clone()).await?,
+        [
+            Ok(ReadValue::Real(12.5)),
+            Ok(ReadValue::Int(-1234)),
+            Ok(ReadValue::Bool(true)),
+            Err(ReturnCode::Err),
+        ]
+    );
+    assert_eq!(client.stats().pdus_sent, pdus_sent + 1);
+
+    // the merged item of DB1 0..14 fails, its items are read unmerged
+    let builder = builder.add_db_real(1, 10);
+    let pdus_sent = client.stats().pdus_sent;
+    assert_eq!(
+        client.read_values(builder).await?,
+        [
+            Ok(ReadValue::Real(12.5)),
+            Ok(ReadValue::Int(-1234)),
+            Ok(ReadValue::Bool(true)),
+            Err(ReturnCode::Err),
+            Err(ReturnCode::InvalidAddress),
+        ]
+    );
+    assert_eq!(client.stats().pdus_sent, pdus_sent + 2);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn plc_state_stop_and_start() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let addr = server.start().await;
+
+    let mut client = connect(addr).await?;
+    assert_eq!(client.get_plc_state().await?, CpuState::Run);
+
+    client.stop_plc().await?;
+    assert!(server.is_stopped());
+    assert_eq!(client.get_plc_state().await?, CpuState::Stop);
+
+    client.start_plc().await?;
+    assert_eq!(client.get_plc_state().await?, CpuState::Run);
+
+    server.set_protected(true);
+    assert!(matches!(
+        client.stop_plc().await,
+        Err(Error::S7CommErr(s7_comm::Error::NakResponse {
+            error_class: 0xd0,
+            error_code: 0xa1
+        }))
+    ));
+    assert!(!server.is_stopped());
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn plc_stop_checked() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let outcome = client.stop_plc_checked().await?;
+    assert_eq!(
+        outcome,
+        ControlOutcome {
+            previous: CpuState::Run,
+            current: CpuState::Stop,
+        }
+    );
+    assert!(outcome.changed());
+
+    // already in stop
+    let outcome = client.stop_plc_checked().await?;
+    assert_eq!(outcome.previous, CpuState::Stop);
+    assert!(!outcome.changed());
+
+    let outcome = client.start_plc_checked().await?;
+    assert_eq!(outcome.current, CpuState::Run);
+    assert!(outcome.changed());
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_szl_of_several_data_units() -> Result<()> {
+    let mut server = MockS7Server::new();
+    // 3 records of 28 bytes
+    let mut szl = vec![0x00, 0x11, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x03];
+    for index in 1..=3u8 {
+        let mut record = vec![index; 28];
+        record[..2].copy_from_slice(&[0x00, index]);
+        szl.extend_from_slice(&record);
+    }
+    server.set_szl(SzlId::ModuleIdent.into(), szl);
+    server.set_szl_fragment_size(40);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let response = client.read_szl(SzlId::ModuleIdent.into(), 0x0000).await?;
+    assert_eq!(response.header.szl_id, 0x0011);
+    assert_eq!(response.header.item_count, 3);
+    assert_eq!(response.items.len(), 3);
+    assert_eq!(response.items[2][..3], [0x00, 0x03, 0x03]);
+    assert_eq!(client.get_plc_state().await?, CpuState::Run);
+
+    let mut szl = vec![0x00, 0x11, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x01, 0x00, 0x01];
+    szl.extend_from_slice(b"6ES7 214-1AG40-0XB0 ");
+    szl.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x01]);
+    server.set_szl(SzlId::ModuleIdent.into(), szl);
+    assert_eq!(client.cpu_family().await?, CpuFamily::S71200);
+
+    let mut szl = vec![0x00, 0x11, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x03];
+    for (index, version) in [
+        (0x01, [0x00, 0x01]),
+        (0x06, [0x56, 0x01]),
+        (0x07, [0x56, 0x04]),
+    ] {
+        szl.extend_from_slice(&[0x00, index]);
+        szl.extend_from_slice(b"6ES7 214-1AG40-0XB0 ");
+        szl.extend_from_slice(&[0x00, 0x00, version[0], version[1], 0x02, 0x03]);
+    }
+    server.set_szl(SzlId::ModuleIdent.into(), szl);
+    let mut szl = vec![0x00, 0x1c, 0x00, 0x00, 0x00, 0x22, 0x00, 0x02];
+    szl.extend_from_slice(&[0x00, 0x02]);
+    szl.extend_from_slice(&[b"CPU 1214C DC/DC/DC".as_slice(), &[0; 14]].concat());
+    szl.extend_from_slice(&[0x00, 0x05]);
+    szl.extend_from_slice(&[b"S C-X4U123452012".as_slice(), &[0; 16]].concat());
+    server.set_szl(SzlId::ComponentIdent.into(), szl);
+    let info = client.cpu_info().await?;
+    assert_eq!(info.order_number, "6ES7 214-1AG40-0XB0");
+    assert_eq!(info.hardware_version, "V1.2.3");
+    assert_eq!(info.firmware_version, "V4.2.3");
+    assert_eq!(info.serial_number, "S C-X4U123452012");
+    assert_eq!(info.module_name, "CPU 1214C DC/DC/DC");
+    assert_eq!(client.get_cpu_info().await?, info);
+
+    assert!(matches!(
+        client.read_szl(SzlId::ProtectionLevel.into(), 0x0004).await,
+        Err(Error::S7CommErr(s7_comm::Error::NakResponse {
+            error_class: 0xd0,
+            error_code: 0x41
+        }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn list_blocks_and_get_block_info() -> Result<()> {
+    let mut server = MockS7Server::new();
+    for (block_type, block_num) in [
+        (BlockType::Db, 10),
+        (BlockType::Fc, 1),
+        (BlockType::Db, 2),
+        (BlockType::Ob, 1),
+    ] {
+        server.add_block(block_type, block_num);
+    }
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let entry = |block_type, block_num| BlockListEntry {
+        block_type,
+        block_num,
+    };
+    assert_eq!(
+        client.list_blocks().await?,
+        vec![
+            entry(BlockType::Ob, 1),
+            entry(BlockType::Db, 2),
+            entry(BlockType::Db, 10),
+            entry(BlockType::Fc, 1),
+        ]
+    );
+
+    let info = client.get_block_info(BlockType::Db, 10).await?;
+    assert_eq!(info.block_type, BlockType::Db);
+    assert_eq!(info.block_number, 10);
+    assert_eq!(info.mc7_code_length, 20);
+    assert_eq!(info.load_memory_size, 158);
+    assert!(matches!(
+        client.get_block_info(BlockType::Db, 3).await,
+        Err(Error::S7CommErr(s7_comm::Error::NakResponse {
+            error_class: 0xd2,
+            error_code: 0x09
+        }))
+    ));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn upload_and_download_block() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let mut block: Vec<u8> = (0..1000).map(|x| x as u8).collect();
+    // the mc7 code length of the block header
+    block[34..36].copy_from_slice(&20u16.to_be_bytes());
+    server.set_block(BlockType::Fc, 1, block.clone());
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    // uploaded in several acks
+    assert_eq!(client.upload_block(BlockType::Fc, 1).await?, block);
+    assert!(matches!(
+        client.upload_block(BlockType::Fc, 2).await,
+        Err(Error::BlockRejected {
+            block_type: BlockType::Fc,
+            block_num: 2,
+            source: s7_comm::Error::NakResponse {
+                error_class: 0xd2,
+                error_code: 0x09
+            }
+        })
+    ));
+
+    // restored as another block, in chunks of the pdu length. The ack of
+    // download ended is not answered, list blocks waits for it to be handled
+    client.download_block(BlockType::Fc, 2, &block).await?;
+    assert_eq!(client.list_blocks().await?.len(), 2);
+    assert_eq!(server.block(BlockType::Fc, 2), Some(block.clone()));
+
+    // no block header
+    assert!(matches!(
+        client.download_block(BlockType::Fc, 3, &block[..20]).await,
+        Err(Error::BlockRejected {
+            source: s7_comm::Error::InsufficientData { .. },
+            ..
+        })
+    ));
+    server.set_protected(true);
+    assert!(matches!(
+        client.download_block(BlockType::Fc, 3, &block).await,
+        Err(Error::BlockRejected {
+            source: s7_comm::Error::NakResponse {
+                error_class: 0xd0,
+                error_code: 0xa1
+            },
+            ..
+        })
+    ));
+    assert_eq!(server.block(BlockType::Fc, 3), None);
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_and_write_clock() -> Result<()> {
+    let mut server = MockS7Server::new();
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let clock = PlcDateTime::decode(&[0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35])?;
+    client.write_clock(clock).await?;
+    assert_eq!(server.clock(), Some(clock));
+    assert_eq!(client.read_clock().await?, clock);
+
+    let now = PlcDateTime::now();
+    client.write_clock(now).await?;
+    assert_eq!(client.read_clock().await?, now);
+
+    // month 13
+    assert!(client
+        .write_clock(PlcDateTime { month: 13, ..now })
+        .await
+        .is_err());
+    assert_eq!(server.clock(), Some(now));
+    // not taken as 2050
+    assert!(client
+        .write_clock(PlcDateTime { year: 150, ..now })
+        .await
+        .is_err());
+    assert_eq!(server.clock(), Some(now));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_db_bit_read_modify_write() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0b1000_0001, 0xff]);
+    server.set_reject_bit_write(true);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let resp = client
+        .write_bit(Some(1), s7_client::s7_comm::Area::DataBlocks, 0, 3, true)
+        .await?;
+    assert_eq!(resp.return_code, ReturnCode::NotSupported);
+
+    let resp = client.write_db_bit_rmw(1, 0, 3, true).await?;
+    assert!(resp.return_code.is_ok());
+    assert_eq!(server.db(1), Some(vec![0b1000_1001, 0xff]));
+
+    let resp = client.write_db_bit_rmw(1, 1, 0, false).await?;
+    assert!(resp.return_code.is_ok());
+    assert_eq!(server.db(1), Some(vec![0b1000_1001, 0xfe]));
+
+    assert!(client.write_db_bit_rmw(1, 0, 8, true).await.is_err());
+
+    let resp = client.write_db_masked(1, 0, 0xff, 0x02).await?;
+    assert_eq!(resp.len(), 1);
+    assert_eq!(resp[0].return_code, ReturnCode::NotSupported);
+    assert!(client.write_db_bit_rmw(2, 0, 0, true).await.is_err());
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_db_masked() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0b1010_1010]);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let resp = client
+        .write_db_masked(1, 0, 0b0111_1111, 0b0000_0101)
+        .await?;
+    assert_eq!(resp.len(), 3);
+    assert!(resp.iter().all(|x| x.return_code.is_ok()));
+    assert_eq!(server.db(1), Some(vec![0b0010_1111]));
+
+    assert!(client.write_db_masked(1, 0, 0xff, 0).await?.is_empty());
+    assert_eq!(server.db(1), Some(vec![0b0010_1111]));
+
+    server.stop();
+    Ok(())
+}
+
+#[tokio::test]
+async fn decode_error_handler_receives_raw_frame() -> Result<()> {
+    let mut server = MockS7Server::new();
+    server.set_db(1, vec![0; 4]);
+    let addr = server.start().await;
+    let mut client = connect(addr).await?;
+
+    let captured = Arc::new(Mutex::new(Vec::new()));
+    let handler_captured = captured.clone();
+    client.set_decode_error_handler(move |raw, _| {
+        handler_captured.lock().unwrap().extend_from_slice(raw);
+    });
+
+    // unknown copt pdu type 0x00
+    let malformed = vec![0x03, 0x00, 0x00, 0x07, 0x02, 0x00, 0x80];
+    server.set_next_raw_response(malformed.clone());
+    let rs = client
+        .read(&Area::DataBausteine(
+            1,
+            DataSizeType::Byte { addr: 0, len: 4 },
+        ))
+        .await;
+    assert!(rs.is_err());
+    assert_eq!(*captured.lock().unwrap(), malformed);
+
+    server.stop();
+    Ok(())
+}
@@ -22,7 +22,7 @@ async fn main() -> Result<()> {
         let frame = init_copt_connect_request().to_bytes::<CoptEncoder<S7CommEncoder>>()?;
         req.write_all(frame.as_ref()).await.unwrap();
         let mut bytes = BytesMut::new();
-        let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+        let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
         loop {
             let size = req.read(&mut buf).await.unwrap();
             if size == 0 {
@@ -43,7 +43,7 @@ async fn main() -> Result<()> {
         let frame = init_s7_setup().to_bytes::<CoptEncoder<S7CommEncoder>>()?;
         req.write_all(frame.as_ref()).await.unwrap();
         let mut bytes = BytesMut::new();
-        let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+        let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
         loop {
             let size = req.read(&mut buf).await.unwrap();
             bytes.extend_from_slice(buf[0..size].as_ref());
@@ -65,7 +65,7 @@ async fn main() -> Result<()> {
         let frame = init_s7_write().to_bytes::<CoptEncoder<S7CommEncoder>>()?;
         req.write_all(frame.as_ref()).await.unwrap();
         let mut bytes = BytesMut::new();
-        let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+        let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
         loop {
             let size = req.read(&mut buf).await.unwrap();
             bytes.extend_from_slice(buf[0..size].as_ref());
@@ -87,7 +87,7 @@ async fn main() -> Result<()> {
         let frame = init_s7_read().to_bytes::<CoptEncoder<S7CommEncoder>>()?;
         req.write_all(frame.as_ref()).await.unwrap();
         let mut bytes = BytesMut::new();
-        let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+        let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
         loop {
             let size = req.read(&mut buf).await.unwrap();
             bytes.extend_from_slice(buf[0..size].as_ref());
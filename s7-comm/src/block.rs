@@ -0,0 +1,286 @@
+use crate::{error::*, packet::*, user_data::*};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// subfunction of block functions: list the count of blocks of every type
+pub const SUBFUNCTION_LIST_BLOCKS: u8 = 0x01;
+/// subfunction of block functions: list the blocks of a type
+pub const SUBFUNCTION_LIST_BLOCKS_OF_TYPE: u8 = 0x02;
+/// subfunction of block functions: get the info of a block
+pub const SUBFUNCTION_GET_BLOCK_INFO: u8 = 0x03;
+/// the prefix of a block type in block functions, block type is ascii
+pub(crate) const BLOCK_TYPE_PREFIX: u8 = 0x30;
+/// length of a record of list blocks response: block type and count
+const LIST_BLOCKS_RECORD_LEN: usize = 4;
+/// length of a record of list blocks of type response: block number, flags
+/// and language
+const LIST_BLOCKS_OF_TYPE_RECORD_LEN: usize = 4;
+
+/// the file system of the block in get block info request, 'A': active
+pub(crate) const BLOCK_FILE_SYSTEM_ACTIVE: u8 = 0x41;
+/// length of the data of block info response
+const BLOCK_INFO_LEN: usize = 78;
+/// offset of the block security in the data of block info response
+const BLOCK_INFO_SECURITY_OFFSET: usize = 18;
+/// seconds from 1970-01-01 to 1984-01-01, the epoch of the timestamps of
+/// block info
+const SECONDS_TO_1984: u64 = 441_763_200;
+const BLOCK_SECURITY_KNOW_HOW_PROTECT: u32 = 3;
+
+/// Protection attributes of a block.
+///
+/// The block info response only carries the know-how protection, a block
+/// can't be marked copy-protected on its own: a know-how protected block
+/// can't be uploaded in a readable form.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct BlockProtection {
+    pub know_how_protected: bool,
+    /// raw block security, 0: none, 3: know how protect
+    pub security: u32,
+}
+
+impl BlockProtection {
+    /// decode from the data of block info response, which starts after the
+    /// return code, transport size and length of the data item
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let Some(security) = data.get(BLOCK_INFO_SECURITY_OFFSET..BLOCK_INFO_SECURITY_OFFSET + 4)
+        else {
+            return Err(Error::Other(format!(
+                "block info data's length is not enough: {}",
+                data.len()
+            )));
+        };
+        let security = u32::from_be_bytes([security[0], security[1], security[2], security[3]]);
+        Ok(Self {
+            know_how_protected: security == BLOCK_SECURITY_KNOW_HOW_PROTECT,
+            security,
+        })
+    }
+}
+
+/// type of a block, the ascii code used by block functions
+#[derive(Debug, Copy, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq, Ord, PartialOrd)]
+#[repr(u8)]
+pub enum BlockType {
+    /// '8'
+    Ob = 0x38,
+    /// 'A'
+    Db = 0x41,
+    /// 'B'
+    Sdb = 0x42,
+    /// 'C'
+    Fc = 0x43,
+    /// 'D'
+    Sfc = 0x44,
+    /// 'E'
+    Fb = 0x45,
+    /// 'F'
+    Sfb = 0x46,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// a block of the plc, ordered by block type then block number
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct BlockListEntry {
+    pub block_type: BlockType,
+    pub block_num: u16,
+}
+
+impl Frame {
+    /// list the count of blocks of every type
+    pub fn list_blocks(pdu_ref: u16) -> Frame {
+        Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::BlockFunctions,
+            SUBFUNCTION_LIST_BLOCKS,
+            UserDataData::init_empty(),
+        )
+    }
+
+    /// get the info of the block, the block number is sent as 5 ascii digits
+    pub fn get_block_info(pdu_ref: u16, block_type: BlockType, block_number: u16) -> Frame {
+        let mut data = vec![BLOCK_TYPE_PREFIX, block_type.into()];
+        data.extend_from_slice(format!("{:05}", block_number).as_bytes());
+        data.push(BLOCK_FILE_SYSTEM_ACTIVE);
+        Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::BlockFunctions,
+            SUBFUNCTION_GET_BLOCK_INFO,
+            UserDataData::init_with_octet_string(data),
+        )
+    }
+
+    /// list the blocks of the type
+    pub fn list_blocks_of_type(pdu_ref: u16, block_type: BlockType) -> Frame {
+        Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::BlockFunctions,
+            SUBFUNCTION_LIST_BLOCKS_OF_TYPE,
+            UserDataData::init_with_octet_string(vec![BLOCK_TYPE_PREFIX, block_type.into()]),
+        )
+    }
+}
+
+/// the count of blocks of every type of a list blocks response frame
+pub fn decode_list_blocks(frame: &Frame) -> Result<Vec<(BlockType, u16)>> {
+    let data = frame.user_data_response(
+        UserDataFunctionGroup::BlockFunctions,
+        SUBFUNCTION_LIST_BLOCKS,
+    )?;
+    check_records_len(data, LIST_BLOCKS_RECORD_LEN)?;
+    Ok(data
+        .chunks_exact(LIST_BLOCKS_RECORD_LEN)
+        .map(|x| (BlockType::from(x[1]), u16::from_be_bytes([x[2], x[3]])))
+        .collect())
+}
+
+/// the block number and flags of every block of a list blocks of type
+/// response frame
+///
+/// A plc with many blocks of the type splits the response into several data
+/// units, only the records of this data unit are returned.
+pub fn decode_list_blocks_of_type(frame: &Frame) -> Result<Vec<(u16, u8)>> {
+    decode_list_blocks_of_type_data(frame.user_data_response(
+        UserDataFunctionGroup::BlockFunctions,
+        SUBFUNCTION_LIST_BLOCKS_OF_TYPE,
+    )?)
+}
+
+/// the block number and flags of every block of the data of list blocks of
+/// type responses, e.g. the data joined from all data units
+pub fn decode_list_blocks_of_type_data(data: &[u8]) -> Result<Vec<(u16, u8)>> {
+    check_records_len(data, LIST_BLOCKS_OF_TYPE_RECORD_LEN)?;
+    Ok(data
+        .chunks_exact(LIST_BLOCKS_OF_TYPE_RECORD_LEN)
+        .map(|x| (u16::from_be_bytes([x[0], x[1]]), x[2]))
+        .collect())
+}
+
+fn check_records_len(data: &[u8], record_len: usize) -> Result<()> {
+    if !data.len().is_multiple_of(record_len) {
+        return Err(Error::InsufficientData {
+            needed: data.len() + record_len - data.len() % record_len,
+            got: data.len(),
+        });
+    }
+    Ok(())
+}
+
+/// metadata of a block of get block info response
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlockInfo {
+    pub block_type: BlockType,
+    pub block_number: u16,
+    pub flags: u8,
+    pub language: u8,
+    pub load_memory_size: u32,
+    pub protection: BlockProtection,
+    pub last_modified: SystemTime,
+    pub last_interface_change: SystemTime,
+    pub local_data_size: u16,
+    pub mc7_code_length: u16,
+    pub author: String,
+    pub family: String,
+    pub name: String,
+    /// high nibble: major, low nibble: minor
+    pub version: u8,
+    pub checksum: u16,
+}
+
+impl BlockInfo {
+    /// decode from the data of block info response, which starts after the
+    /// return code, transport size and length of the data item
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < BLOCK_INFO_LEN {
+            return Err(Error::InsufficientData {
+                needed: BLOCK_INFO_LEN,
+                got: data.len(),
+            });
+        }
+        let u16_at = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        Ok(Self {
+            block_type: sub_block_type(data[11]),
+            block_number: u16_at(12),
+            flags: data[9],
+            language: data[10],
+            load_memory_size: u32::from_be_bytes([data[14], data[15], data[16], data[17]]),
+            protection: BlockProtection::decode(data)?,
+            last_modified: decode_timestamp(&data[22..28]),
+            last_interface_change: decode_timestamp(&data[28..34]),
+            local_data_size: u16_at(38),
+            mc7_code_length: u16_at(40),
+            author: decode_name(&data[42..50]),
+            family: decode_name(&data[50..58]),
+            name: decode_name(&data[58..66]),
+            version: data[66],
+            checksum: u16_at(68),
+        })
+    }
+}
+
+/// the info of the block of a get block info response frame
+pub fn decode_block_info(frame: &Frame) -> Result<BlockInfo> {
+    BlockInfo::decode(frame.user_data_response(
+        UserDataFunctionGroup::BlockFunctions,
+        SUBFUNCTION_GET_BLOCK_INFO,
+    )?)
+}
+
+/// the block type of block info is the sub block type, not ascii
+fn sub_block_type(sub_block_type: u8) -> BlockType {
+    match sub_block_type {
+        0x08 => BlockType::Ob,
+        0x0a => BlockType::Db,
+        0x0b => BlockType::Sdb,
+        0x0c => BlockType::Fc,
+        0x0d => BlockType::Sfc,
+        0x0e => BlockType::Fb,
+        0x0f => BlockType::Sfb,
+        x => BlockType::NotSupport(x),
+    }
+}
+
+/// milliseconds since midnight and days since 1984-01-01
+fn decode_timestamp(data: &[u8]) -> SystemTime {
+    let millis = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as u64;
+    let days = u16::from_be_bytes([data[4], data[5]]) as u64;
+    UNIX_EPOCH + Duration::from_secs(SECONDS_TO_1984 + days * 86400) + Duration::from_millis(millis)
+}
+
+/// an ascii name padded by zeros or spaces
+fn decode_name(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches(['\0', ' '])
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::BlockProtection;
+
+    fn block_info_data(security: u8) -> Vec<u8> {
+        let mut data = vec![
+            0x01, 0x38, 0x00, 0x00, 0x00, 0x00, 0x70, 0x70, 0x00, 0x01, 0x01, 0x0a, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x9e, 0x00, 0x00, 0x00, security,
+        ];
+        data.resize(78, 0);
+        data
+    }
+
+    #[test]
+    fn check_know_how_protected_block() {
+        let protection = BlockProtection::decode(&block_info_data(3)).unwrap();
+        assert!(protection.know_how_protected);
+        assert_eq!(protection.security, 3);
+    }
+
+    #[test]
+    fn check_unprotected_block() {
+        let protection = BlockProtection::decode(&block_info_data(0)).unwrap();
+        assert!(!protection.know_how_protected);
+        assert_eq!(protection.security, 0);
+
+        assert!(BlockProtection::decode(&[0x01, 0x38]).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{block::*, error::*, packet::*, plc_control::*};
+use bytes::{Buf, BufMut, BytesMut};
+
+/// the status of upload and download block ack: more data follows
+const STATUS_MORE_DATA: u8 = 0x01;
+/// the unknown word before the data of upload and download block ack
+const BLOCK_DATA_UNKNOWN: u16 = 0x00fb;
+/// the unknown word of request download and of start upload ack
+const UP_DOWNLOAD_UNKNOWN: u16 = 0x0100;
+/// the file system of a downloaded block, 'P': passive, the plc activates
+/// the block after download ended
+const BLOCK_FILE_SYSTEM_PASSIVE: u8 = 0x50;
+/// the prefix of the file name of a block
+const FILENAME_PREFIX: u8 = 0x5f;
+/// the unknown first character of the second part of request download
+const PART2_PREFIX: u8 = 0x31;
+/// length of the header of a block in the format of upload and download
+pub const BLOCK_HEADER_LEN: usize = 36;
+/// offset of the mc7 code length in the header of a block
+const BLOCK_HEADER_MC7_LENGTH_OFFSET: usize = 34;
+
+/// The parameter of the jobs of block upload and download.
+///
+/// Upload is requested by the client: start upload, upload until the ack has
+/// no more data, end upload. Download is driven by the plc: after the ack of
+/// request download the plc sends download block jobs, which the client acks
+/// with the data of the block, until it sends download ended.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UpDownload {
+    pub status: u8,
+    /// the error code of end upload and download ended, unknown otherwise
+    pub error_code: u16,
+    pub upload_id: u32,
+    /// the file name of the block, e.g. `_0A00001P`, not sent by upload and
+    /// end upload
+    pub filename: Option<Vec<u8>>,
+    /// the load memory length and the mc7 code length of the block, only
+    /// sent by request download
+    pub part2: Option<Vec<u8>>,
+}
+
+impl UpDownload {
+    pub fn init(upload_id: u32, filename: Option<Vec<u8>>) -> Self {
+        Self {
+            status: 0,
+            error_code: 0,
+            upload_id,
+            filename,
+            part2: None,
+        }
+    }
+
+    /// the block type and block number of the file name
+    pub fn block(&self) -> Option<BlockListEntry> {
+        let filename = self.filename.as_ref()?;
+        let block_num = std::str::from_utf8(filename.get(3..8)?)
+            .ok()?
+            .parse()
+            .ok()?;
+        Some(BlockListEntry {
+            block_type: BlockType::from(*filename.get(2)?),
+            block_num,
+        })
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        // function, status, error code, upload id
+        let mut len = 1 + 1 + 2 + 4;
+        for x in [&self.filename, &self.part2].into_iter().flatten() {
+            len += 1 + x.len() as u16;
+        }
+        len
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.put_u8(self.status);
+        dst.put_u16(self.error_code);
+        dst.put_u32(self.upload_id);
+        for x in [self.filename, self.part2].into_iter().flatten() {
+            dst.put_u8(x.len() as u8);
+            dst.extend_from_slice(x.as_slice());
+        }
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut, function: u8) -> Result<Self> {
+        if src.len() < 7 {
+            return Err(Error::InsufficientData {
+                needed: 7,
+                got: src.len(),
+            });
+        }
+        let status = src.get_u8();
+        let error_code = src.get_u16();
+        let upload_id = src.get_u32();
+        let filename = match function {
+            0x1e | 0x1f => None,
+            _ => Some(decode_string(src)?),
+        };
+        let part2 = match function {
+            0x1a => Some(decode_string(src)?),
+            _ => None,
+        };
+        Ok(Self {
+            status,
+            error_code,
+            upload_id,
+            filename,
+            part2,
+        })
+    }
+}
+
+/// ack of start upload, a rejected start upload carries only the function
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct StartUploadAck {
+    pub status: u8,
+    unknown: u16,
+    pub upload_id: u32,
+    /// the length of the block in ascii digits
+    pub block_length: Vec<u8>,
+}
+
+impl StartUploadAck {
+    pub fn init(upload_id: u32, block_length: usize) -> Self {
+        Self {
+            status: 0,
+            unknown: UP_DOWNLOAD_UNKNOWN,
+            upload_id,
+            block_length: format!("{:07}", block_length).into_bytes(),
+        }
+    }
+
+    /// the length of the block
+    pub fn block_length(&self) -> Option<usize> {
+        std::str::from_utf8(&self.block_length).ok()?.parse().ok()
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        // function, status, unknown, upload id, length of block length
+        1 + 1 + 2 + 4 + 1 + self.block_length.len() as u16
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.put_u8(self.status);
+        dst.put_u16(self.unknown);
+        dst.put_u32(self.upload_id);
+        dst.put_u8(self.block_length.len() as u8);
+        dst.extend_from_slice(self.block_length.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut, length: usize) -> Result<Self> {
+        let mut parameter = BytesMut::from(split_to(src, length)?.as_slice());
+        if parameter.len() < 7 {
+            return Ok(Self::default());
+        }
+        Ok(Self {
+            status: parameter.get_u8(),
+            unknown: parameter.get_u16(),
+            upload_id: parameter.get_u32(),
+            block_length: decode_string(&mut parameter)?,
+        })
+    }
+}
+
+/// ack of upload and of download block, the data of the block
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct BlockDataAck {
+    /// 0x01: more data follows
+    pub status: u8,
+    unknown: u16,
+    pub data: Vec<u8>,
+}
+
+impl BlockDataAck {
+    pub fn init(more_data: bool, data: Vec<u8>) -> Self {
+        Self {
+            status: if more_data { STATUS_MORE_DATA } else { 0 },
+            unknown: BLOCK_DATA_UNKNOWN,
+            data,
+        }
+    }
+
+    pub fn more_data(&self) -> bool {
+        self.status & STATUS_MORE_DATA > 0
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        // function, status
+        2
+    }
+
+    pub fn bytes_len_data(&self) -> u16 {
+        // length, unknown
+        4 + self.data.len() as u16
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.put_u8(self.status);
+        dst.put_u16(self.data.len() as u16);
+        dst.put_u16(self.unknown);
+        dst.extend_from_slice(self.data.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut, length: usize) -> Result<Self> {
+        let parameter = split_to(src, length)?;
+        let status = parameter.first().copied().unwrap_or_default();
+        // a rejected ack has no data
+        if src.len() < 4 {
+            return Ok(Self {
+                status,
+                ..Default::default()
+            });
+        }
+        let length = src.get_u16() as usize;
+        let unknown = src.get_u16();
+        Ok(Self {
+            status,
+            unknown,
+            data: split_to(src, length)?,
+        })
+    }
+}
+
+fn decode_string(src: &mut BytesMut) -> Result<Vec<u8>> {
+    let Some(length) = src.first().copied() else {
+        return Err(Error::InsufficientData { needed: 1, got: 0 });
+    };
+    src.advance(1);
+    split_to(src, length as usize)
+}
+
+/// the file name of a block, e.g. `_0A00001P` for DB1 to the passive file
+/// system
+fn filename(block_type: BlockType, block_number: u16, file_system: u8) -> Vec<u8> {
+    let mut filename = vec![FILENAME_PREFIX, BLOCK_TYPE_PREFIX, block_type.into()];
+    filename.extend_from_slice(format!("{:05}", block_number).as_bytes());
+    filename.push(file_system);
+    filename
+}
+
+/// the mc7 code length of the header of a block in the format of upload and
+/// download
+pub fn block_mc7_length(block: &[u8]) -> Result<u16> {
+    let Some(length) = block.get(BLOCK_HEADER_MC7_LENGTH_OFFSET..BLOCK_HEADER_LEN) else {
+        return Err(Error::InsufficientData {
+            needed: BLOCK_HEADER_LEN,
+            got: block.len(),
+        });
+    };
+    Ok(u16::from_be_bytes([length[0], length[1]]))
+}
+
+impl Frame {
+    /// start the upload of the block from the active file system
+    pub fn start_upload(pdu_ref: u16, block_type: BlockType, block_number: u16) -> Frame {
+        let filename = filename(block_type, block_number, BLOCK_FILE_SYSTEM_ACTIVE);
+        Self::up_download(
+            pdu_ref,
+            Job::StartUpload,
+            UpDownload::init(0, Some(filename)),
+        )
+    }
+
+    /// upload the next data of the block
+    pub fn upload(pdu_ref: u16, upload_id: u32) -> Frame {
+        Self::up_download(pdu_ref, Job::Upload, UpDownload::init(upload_id, None))
+    }
+
+    pub fn end_upload(pdu_ref: u16, upload_id: u32) -> Frame {
+        Self::up_download(pdu_ref, Job::EndUpload, UpDownload::init(upload_id, None))
+    }
+
+    /// Request the download of the block of `load_memory_length` bytes to
+    /// the passive file system, the lengths are sent as 6 ascii digits.
+    pub fn request_download(
+        pdu_ref: u16,
+        block_type: BlockType,
+        block_number: u16,
+        load_memory_length: u32,
+        mc7_length: u32,
+    ) -> Frame {
+        let filename = filename(block_type, block_number, BLOCK_FILE_SYSTEM_PASSIVE);
+        let mut part2 = vec![PART2_PREFIX];
+        part2.extend_from_slice(format!("{:06}{:06}", load_memory_length, mc7_length).as_bytes());
+        let job = UpDownload {
+            error_code: UP_DOWNLOAD_UNKNOWN,
+            part2: Some(part2),
+            ..UpDownload::init(0, Some(filename))
+        };
+        Self::up_download(pdu_ref, Job::RequestDownload, job)
+    }
+
+    /// the job of the plc requesting the next data of the downloaded block
+    pub fn download_block(pdu_ref: u16, block_type: BlockType, block_number: u16) -> Frame {
+        let filename = filename(block_type, block_number, BLOCK_FILE_SYSTEM_PASSIVE);
+        Self::up_download(
+            pdu_ref,
+            Job::DownloadBlock,
+            UpDownload::init(0, Some(filename)),
+        )
+    }
+
+    /// the job of the plc ending the download of the block
+    pub fn download_ended(pdu_ref: u16, block_type: BlockType, block_number: u16) -> Frame {
+        let filename = filename(block_type, block_number, BLOCK_FILE_SYSTEM_PASSIVE);
+        Self::up_download(
+            pdu_ref,
+            Job::DownloadEnded,
+            UpDownload::init(0, Some(filename)),
+        )
+    }
+
+    pub fn start_upload_ack(pdu_ref: u16, upload_id: u32, block_length: usize) -> Frame {
+        let ack = StartUploadAck::init(upload_id, block_length);
+        Frame::AckData {
+            header: HearderAckData::init(pdu_ref, ack.bytes_len_parameter(), 0, 0, 0),
+            ack_data: AckData::StartUpload(ack),
+        }
+    }
+
+    pub fn upload_ack(pdu_ref: u16, more_data: bool, data: Vec<u8>) -> Frame {
+        Self::block_data_ack(
+            pdu_ref,
+            AckData::Upload,
+            BlockDataAck::init(more_data, data),
+        )
+    }
+
+    /// the ack of the client to download block with the next data of the
+    /// block
+    pub fn download_block_ack(pdu_ref: u16, more_data: bool, data: Vec<u8>) -> Frame {
+        Self::block_data_ack(
+            pdu_ref,
+            AckData::DownloadBlock,
+            BlockDataAck::init(more_data, data),
+        )
+    }
+
+    pub fn end_upload_ack(pdu_ref: u16) -> Frame {
+        Self::up_download_ack(pdu_ref, AckData::EndUpload)
+    }
+
+    pub fn request_download_ack(pdu_ref: u16) -> Frame {
+        Self::up_download_ack(pdu_ref, AckData::RequestDownload)
+    }
+
+    /// the ack of the client to download ended
+    pub fn download_ended_ack(pdu_ref: u16) -> Frame {
+        Self::up_download_ack(pdu_ref, AckData::DownloadEnded)
+    }
+
+    fn up_download(pdu_ref: u16, job: fn(UpDownload) -> Job, data: UpDownload) -> Frame {
+        Frame::Job {
+            header: Header::init(pdu_ref, data.bytes_len_parameter(), 0),
+            job: job(data),
+        }
+    }
+
+    fn block_data_ack(
+        pdu_ref: u16,
+        ack_data: fn(BlockDataAck) -> AckData,
+        ack: BlockDataAck,
+    ) -> Frame {
+        Frame::AckData {
+            header: HearderAckData::init(
+                pdu_ref,
+                ack.bytes_len_parameter(),
+                ack.bytes_len_data(),
+                0,
+                0,
+            ),
+            ack_data: ack_data(ack),
+        }
+    }
+
+    fn up_download_ack(pdu_ref: u16, ack_data: fn(PlcControlAck) -> AckData) -> Frame {
+        Frame::AckData {
+            header: HearderAckData::init(pdu_ref, 1, 0, 0, 0),
+            ack_data: ack_data(PlcControlAck {
+                parameter: Vec::new(),
+            }),
+        }
+    }
+}
+
+/// the upload id and the length of the block of a start upload ack frame
+pub fn decode_start_upload(frame: &Frame) -> Result<(u32, usize)> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::StartUpload(ack),
+        } => {
+            header.check()?;
+            let Some(block_length) = ack.block_length() else {
+                return Err(Error::Other(format!(
+                    "invalid block length: {:?}",
+                    ack.block_length
+                )));
+            };
+            Ok((ack.upload_id, block_length))
+        }
+        frame => Err(Error::Other(format!("not a start upload ack: {:?}", frame))),
+    }
+}
+
+/// the data of the block and whether more data follows of an upload ack
+/// frame
+pub fn decode_upload(frame: &Frame) -> Result<(&[u8], bool)> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::Upload(ack),
+        } => {
+            header.check()?;
+            Ok((ack.data.as_slice(), ack.more_data()))
+        }
+        frame => Err(Error::Other(format!("not an upload ack: {:?}", frame))),
+    }
+}
+
+/// `Ok(())` if the end upload ack frame accepted the job
+pub fn check_end_upload(frame: &Frame) -> Result<()> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::EndUpload(_),
+        } => header.check(),
+        frame => Err(Error::Other(format!("not an end upload ack: {:?}", frame))),
+    }
+}
+
+/// `Ok(())` if the request download ack frame accepted the block
+pub fn check_request_download(frame: &Frame) -> Result<()> {
+    match frame {
+        Frame::AckData {
+            header,
+            ack_data: AckData::RequestDownload(_),
+        } => header.check(),
+        frame => Err(Error::Other(format!(
+            "not a request download ack: {:?}",
+            frame
+        ))),
+    }
+}
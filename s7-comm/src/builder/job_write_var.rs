@@ -30,6 +30,37 @@ impl FrameJobWriteVarBuilder {
         self.add_item((req, data_val))
     }
 
+    /// Change a db byte to `(byte & and_mask) | or_mask` without reading it.
+    ///
+    /// S7comm has no masked-write item, so every changed bit becomes a bit
+    /// item of this job and the other bits of the byte are never written.
+    /// Plcs which reject bit writes, e.g. optimized dbs of s7-1200/1500,
+    /// reject these items as well.
+    pub fn write_db_masked(
+        self,
+        db_number: u16,
+        byte_addr: u16,
+        and_mask: u8,
+        or_mask: u8,
+    ) -> Self {
+        (0..8u8).fold(self, |builder, bit_addr| {
+            let bit = 1 << bit_addr;
+            if or_mask & bit > 0 {
+                builder.add_item((
+                    ItemRequest::init_db_bit(db_number, byte_addr, bit_addr),
+                    DataItemVal::init_with_bit(ReturnCode::Reserved, true),
+                ))
+            } else if and_mask & bit == 0 {
+                builder.add_item((
+                    ItemRequest::init_db_bit(db_number, byte_addr, bit_addr),
+                    DataItemVal::init_with_bit(ReturnCode::Reserved, false),
+                ))
+            } else {
+                builder
+            }
+        })
+    }
+
     pub fn build(self) -> Frame {
         let Self { pdu_ref, items } = self;
 
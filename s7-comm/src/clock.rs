@@ -0,0 +1,69 @@
+use crate::{date_time::*, error::*, packet::*, user_data::*};
+
+/// subfunction of time functions: read clock
+pub const SUBFUNCTION_READ_CLOCK: u8 = 0x01;
+/// subfunction of time functions: set clock
+pub const SUBFUNCTION_SET_CLOCK: u8 = 0x02;
+/// length of the clock data: reserved, century and DATE_AND_TIME
+const CLOCK_DATA_LEN: usize = 2 + DATE_TIME_LEN;
+
+impl Frame {
+    /// read the clock of the cpu
+    pub fn read_clock(pdu_ref: u16) -> Frame {
+        Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::TimeFunctions,
+            SUBFUNCTION_READ_CLOCK,
+            UserDataData::init_empty(),
+        )
+    }
+
+    /// set the clock of the cpu
+    pub fn set_clock(pdu_ref: u16, date_time: &DateTimeParts) -> Result<Frame> {
+        let mut data = Vec::with_capacity(CLOCK_DATA_LEN);
+        data.push(0x00);
+        // the century in bcd, 0x19 or 0x20
+        data.push(u8_to_bcd((date_time.year / 100) as u8));
+        data.extend_from_slice(date_time.encode()?.as_slice());
+        Ok(Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::TimeFunctions,
+            SUBFUNCTION_SET_CLOCK,
+            UserDataData::init_with_octet_string(data),
+        ))
+    }
+}
+
+/// the clock of a read clock response frame
+pub fn decode_clock(frame: &Frame) -> Result<DateTimeParts> {
+    let data =
+        frame.user_data_response(UserDataFunctionGroup::TimeFunctions, SUBFUNCTION_READ_CLOCK)?;
+    if data.len() < CLOCK_DATA_LEN {
+        return Err(Error::InsufficientData {
+            needed: CLOCK_DATA_LEN,
+            got: data.len(),
+        });
+    }
+    // the year is decoded by the window of DATE_AND_TIME, the century byte is
+    // ignored
+    DateTimeParts::decode(&data[2..CLOCK_DATA_LEN])
+}
+
+/// `Ok(())` if the set clock response frame accepted the clock
+///
+/// the response carries no data, the plc rejects the clock with the error
+/// code of the parameter
+pub fn check_set_clock(frame: &Frame) -> Result<()> {
+    match frame {
+        Frame::UserData { parameter, .. }
+            if parameter.function_group() == UserDataFunctionGroup::TimeFunctions
+                && parameter.subfunction == SUBFUNCTION_SET_CLOCK =>
+        {
+            parameter.check()
+        }
+        frame => Err(Error::Other(format!(
+            "not a set clock response: {:?}",
+            frame
+        ))),
+    }
+}
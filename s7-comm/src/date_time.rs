@@ -0,0 +1,524 @@
+use crate::error::*;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// length of the DATE_AND_TIME in bytes
+pub const DATE_TIME_LEN: usize = 8;
+/// the last DATE, 2168-12-31, in days since 1990-01-01
+pub const DATE_MAX_DAYS: u16 = 65378;
+/// the longest S5TIME, 999 in the time base of 10s
+pub const S5TIME_MAX: Duration = Duration::from_secs(9990);
+/// the time bases of S5TIME in milliseconds, by the bits 12 and 13
+pub(crate) const S5TIME_BASES: [u64; 4] = [10, 100, 1_000, 10_000];
+
+/// Fields of a DATE_AND_TIME, which is encoded as BCD:
+///
+/// | byte | content                                      |
+/// |------|----------------------------------------------|
+/// | 0    | year, 90..=99: 1990..=1999, 0..=89: 2000..=2089 |
+/// | 1    | month                                        |
+/// | 2    | day                                          |
+/// | 3    | hour                                         |
+/// | 4    | minute                                       |
+/// | 5    | second                                       |
+/// | 6    | the two most significant digits of millisecond |
+/// | 7    | high nibble: the least significant digit of millisecond, low nibble: weekday, 1: sunday |
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DateTimeParts {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+    /// 1: sunday, 2: monday .. 7: saturday
+    pub weekday: u8,
+}
+
+impl DateTimeParts {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let [year, month, day, hour, minute, second, ms_high, ms_low_weekday] = data else {
+            return Err(Error::Other(format!(
+                "date and time's length should be {}: {}",
+                DATE_TIME_LEN,
+                data.len()
+            )));
+        };
+        let year = bcd_to_u8(*year)? as u16;
+        let year = if year >= 90 { 1900 + year } else { 2000 + year };
+        let millisecond =
+            bcd_to_u8(*ms_high)? as u16 * 10 + bcd_digit(*ms_low_weekday >> 4)? as u16;
+        let parts = Self {
+            year,
+            month: bcd_to_u8(*month)?,
+            day: bcd_to_u8(*day)?,
+            hour: bcd_to_u8(*hour)?,
+            minute: bcd_to_u8(*minute)?,
+            second: bcd_to_u8(*second)?,
+            millisecond,
+            weekday: bcd_digit(*ms_low_weekday & 0x0f)?,
+        };
+        parts.check()?;
+        Ok(parts)
+    }
+
+    pub fn encode(&self) -> Result<[u8; DATE_TIME_LEN]> {
+        self.check()?;
+        Ok([
+            u8_to_bcd((self.year % 100) as u8),
+            u8_to_bcd(self.month),
+            u8_to_bcd(self.day),
+            u8_to_bcd(self.hour),
+            u8_to_bcd(self.minute),
+            u8_to_bcd(self.second),
+            u8_to_bcd((self.millisecond / 10) as u8),
+            ((self.millisecond % 10) as u8) << 4 | self.weekday,
+        ])
+    }
+
+    /// `None` if the fields are not a valid date and time, the weekday is
+    /// ignored
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        if !(1990..=2089).contains(&self.year)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+            || self.millisecond > 999
+        {
+            return None;
+        }
+        let days = days_from_civil(self.year as i64, self.month, self.day)?;
+        let seconds = days as u64 * SECONDS_PER_DAY
+            + self.hour as u64 * 3600
+            + self.minute as u64 * 60
+            + self.second as u64;
+        Some(
+            UNIX_EPOCH
+                + Duration::from_secs(seconds)
+                + Duration::from_millis(self.millisecond as u64),
+        )
+    }
+
+    /// The sub-millisecond part is truncated, a time out of 1990..=2089 is
+    /// saturated to the first or the last millisecond of the range.
+    pub fn from_system_time(t: SystemTime) -> Self {
+        let since_epoch = t.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let days = (since_epoch.as_secs() / SECONDS_PER_DAY) as i64;
+        let (days, seconds, millisecond) = if days < DAYS_TO_1990 {
+            (DAYS_TO_1990, 0, 0)
+        } else if days >= DAYS_TO_2090 {
+            (DAYS_TO_2090 - 1, SECONDS_PER_DAY - 1, 999)
+        } else {
+            (
+                days,
+                since_epoch.as_secs() % SECONDS_PER_DAY,
+                since_epoch.subsec_millis() as u16,
+            )
+        };
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year: year as u16,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds % 3600 / 60) as u8,
+            second: (seconds % 60) as u8,
+            millisecond,
+            // 1970-01-01 is a thursday
+            weekday: ((days + 4) % 7 + 1) as u8,
+        }
+    }
+
+    fn check(&self) -> Result<()> {
+        if !(1990..=2089).contains(&self.year)
+            || !(1..=12).contains(&self.month)
+            || !(1..=31).contains(&self.day)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+            || self.millisecond > 999
+            || !(1..=7).contains(&self.weekday)
+        {
+            return Err(Error::Other(format!("invalid date and time: {:?}", self)));
+        }
+        Ok(())
+    }
+}
+
+/// days from 1970-01-01 to 1990-01-01, the first day of DATE_AND_TIME
+const DAYS_TO_1990: i64 = 7305;
+/// days from 1970-01-01 to 2090-01-01, the day after the last of
+/// DATE_AND_TIME
+const DAYS_TO_2090: i64 = 43830;
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// the days of a DATE since 1990-01-01, up to `DATE_MAX_DAYS`
+pub(crate) fn check_date(days: u16) -> Result<u16> {
+    if days > DATE_MAX_DAYS {
+        return Err(Error::OutOfRange {
+            ty: "DATE",
+            value: days as i64,
+        });
+    }
+    Ok(days)
+}
+
+/// Encode the duration as S5TIME: the time base in the bits 12 and 13 and
+/// the value in 3 BCD digits. The smallest time base which holds the
+/// duration is taken and the duration is truncated to its resolution, e.g.
+/// 12.345s is 123 of the time base of 100ms.
+pub(crate) fn encode_s5time(duration: Duration) -> Result<[u8; 2]> {
+    let ms = duration.as_millis();
+    if duration > S5TIME_MAX {
+        return Err(Error::OutOfRange {
+            ty: "S5TIME",
+            value: ms.min(i64::MAX as u128) as i64,
+        });
+    }
+    let index = S5TIME_BASES
+        .iter()
+        .position(|base| ms / *base as u128 <= 999)
+        .unwrap_or(S5TIME_BASES.len() - 1);
+    let value = (ms / S5TIME_BASES[index] as u128) as u16;
+    Ok([
+        (index as u8) << 4 | (value / 100) as u8,
+        u8_to_bcd((value % 100) as u8),
+    ])
+}
+
+/// decode the S5TIME, the bits 14 and 15 are ignored
+pub(crate) fn decode_s5time(data: [u8; 2]) -> Result<Duration> {
+    let [high, low] = data;
+    let base = S5TIME_BASES[(high >> 4 & 0x03) as usize];
+    let value = bcd_digit(high & 0x0f)? as u64 * 100 + bcd_to_u8(low)? as u64;
+    Ok(Duration::from_millis(value * base))
+}
+
+/// decode the counter value of 3 BCD digits, the bits 12..16 are ignored
+pub fn decode_counter(data: [u8; 2]) -> Result<u16> {
+    let [high, low] = data;
+    Ok(bcd_digit(high & 0x0f)? as u16 * 100 + bcd_to_u8(low)? as u16)
+}
+
+fn bcd_digit(nibble: u8) -> Result<u8> {
+    if nibble > 9 {
+        return Err(Error::Other(format!("invalid bcd digit: {:#x}", nibble)));
+    }
+    Ok(nibble)
+}
+
+fn bcd_to_u8(bcd: u8) -> Result<u8> {
+    Ok(bcd_digit(bcd >> 4)? * 10 + bcd_digit(bcd & 0x0f)?)
+}
+
+pub(crate) fn u8_to_bcd(val: u8) -> u8 {
+    ((val / 10) << 4) | (val % 10)
+}
+
+/// days since 1970-01-01 of the date of the proleptic gregorian calendar,
+/// `None` if the date doesn't exist
+fn days_from_civil(year: i64, month: u8, day: u8) -> Option<i64> {
+    if !(1..=12).contains(&month) || day == 0 {
+        return None;
+    }
+    let (month, day) = (month as i64, day as i64);
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    // the day overflows into the next month
+    (civil_from_days(days) == (year, month as u8, day as u8)).then_some(days)
+}
+
+/// the year, month and day of the days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<DateTimeParts> for chrono::NaiveDateTime {
+    type Error = Error;
+
+    fn try_from(parts: DateTimeParts) -> Result<Self> {
+        chrono::NaiveDate::from_ymd_opt(parts.year as i32, parts.month as u32, parts.day as u32)
+            .and_then(|date| {
+                date.and_hms_milli_opt(
+                    parts.hour as u32,
+                    parts.minute as u32,
+                    parts.second as u32,
+                    parts.millisecond as u32,
+                )
+            })
+            .ok_or_else(|| Error::Other(format!("invalid date and time: {:?}", parts)))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDateTime> for DateTimeParts {
+    type Error = Error;
+
+    /// the sub-millisecond part is truncated
+    fn try_from(date_time: chrono::NaiveDateTime) -> Result<Self> {
+        use chrono::{Datelike, Timelike};
+        if !(1990..=2089).contains(&date_time.year()) {
+            return Err(Error::Other(format!(
+                "date and time out of 1990..=2089: {}",
+                date_time
+            )));
+        }
+        let parts = Self {
+            year: date_time.year() as u16,
+            month: date_time.month() as u8,
+            day: date_time.day() as u8,
+            hour: date_time.hour() as u8,
+            minute: date_time.minute() as u8,
+            second: date_time.second() as u8,
+            // a leap second is carried in the nanoseconds
+            millisecond: (date_time.nanosecond() / 1_000_000).min(999) as u16,
+            weekday: date_time.weekday().number_from_sunday() as u8,
+        };
+        parts.check()?;
+        Ok(parts)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_counter, decode_s5time, encode_s5time, DateTimeParts, S5TIME_MAX};
+    use crate::Error;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn check_s5time() {
+        let cases = [
+            (Duration::from_millis(0), [0x00, 0x00]),
+            (Duration::from_millis(9990), [0x09, 0x99]),
+            // 100ms base
+            (Duration::from_millis(12_300), [0x11, 0x23]),
+            // 1s base
+            (Duration::from_secs(125), [0x21, 0x25]),
+            (Duration::from_secs(999), [0x29, 0x99]),
+            // 10s base
+            (Duration::from_secs(1_000), [0x31, 0x00]),
+            (S5TIME_MAX, [0x39, 0x99]),
+        ];
+        for (duration, bytes) in cases {
+            assert_eq!(encode_s5time(duration).unwrap(), bytes, "{:?}", duration);
+            assert_eq!(decode_s5time(bytes).unwrap(), duration, "{:02x?}", bytes);
+        }
+
+        // truncated to the resolution of the base
+        assert_eq!(
+            encode_s5time(Duration::from_millis(125_999)).unwrap(),
+            [0x21, 0x25]
+        );
+        assert_eq!(
+            encode_s5time(Duration::from_millis(15)).unwrap(),
+            [0x00, 0x01]
+        );
+
+        assert!(matches!(
+            encode_s5time(S5TIME_MAX + Duration::from_millis(1)),
+            Err(Error::OutOfRange {
+                ty: "S5TIME",
+                value: 9_990_001
+            })
+        ));
+        assert!(decode_s5time([0x0a, 0x00]).is_err());
+        assert!(decode_s5time([0x00, 0x1a]).is_err());
+        // the bits 14 and 15 are ignored
+        assert_eq!(
+            decode_s5time([0xc0, 0x01]).unwrap(),
+            Duration::from_millis(10)
+        );
+    }
+
+    #[test]
+    fn check_counter() {
+        assert_eq!(decode_counter([0x00, 0x00]).unwrap(), 0);
+        assert_eq!(decode_counter([0x01, 0x23]).unwrap(), 123);
+        assert_eq!(decode_counter([0x09, 0x99]).unwrap(), 999);
+        assert_eq!(decode_counter([0xf0, 0x42]).unwrap(), 42);
+        assert!(decode_counter([0x0a, 0x00]).is_err());
+        assert!(decode_counter([0x00, 0x0b]).is_err());
+    }
+
+    #[test]
+    fn check_date_time() {
+        let bytes = [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35];
+        let parts = DateTimeParts::decode(&bytes).unwrap();
+        assert_eq!(
+            parts,
+            DateTimeParts {
+                year: 2023,
+                month: 6,
+                day: 15,
+                hour: 13,
+                minute: 45,
+                second: 30,
+                millisecond: 123,
+                weekday: 5,
+            }
+        );
+        assert_eq!(parts.encode().unwrap(), bytes);
+
+        let parts =
+            DateTimeParts::decode(&[0x99, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x96]).unwrap();
+        assert_eq!(parts.year, 1999);
+        assert_eq!(parts.millisecond, 999);
+    }
+
+    #[test]
+    fn check_invalid_date_time() {
+        assert!(DateTimeParts::decode(&[0x23, 0x06, 0x15]).is_err());
+        // not bcd
+        assert!(DateTimeParts::decode(&[0x23, 0x0a, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35]).is_err());
+        // month 13
+        assert!(DateTimeParts::decode(&[0x23, 0x13, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35]).is_err());
+    }
+
+    #[test]
+    fn check_year_window() {
+        let parts = DateTimeParts {
+            year: 1990,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+            weekday: 2,
+        };
+        let bytes = parts.encode().unwrap();
+        assert_eq!(bytes[0], 0x90);
+        assert_eq!(DateTimeParts::decode(&bytes).unwrap(), parts);
+
+        let parts = DateTimeParts {
+            year: 2089,
+            month: 12,
+            day: 31,
+            hour: 23,
+            minute: 59,
+            second: 59,
+            millisecond: 999,
+            weekday: 7,
+        };
+        let bytes = parts.encode().unwrap();
+        assert_eq!(bytes[0], 0x89);
+        assert_eq!(DateTimeParts::decode(&bytes).unwrap(), parts);
+
+        assert!(DateTimeParts {
+            year: 1989,
+            ..parts
+        }
+        .encode()
+        .is_err());
+        assert!(DateTimeParts {
+            year: 2090,
+            ..parts
+        }
+        .encode()
+        .is_err());
+    }
+
+    #[test]
+    fn check_century_rollover() {
+        let last =
+            DateTimeParts::decode(&[0x99, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x96]).unwrap();
+        assert_eq!(last.year, 1999);
+        let first =
+            DateTimeParts::decode(&[0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x07]).unwrap();
+        assert_eq!(first.year, 2000);
+        assert_eq!(first.encode().unwrap()[0], 0x00);
+        let parts =
+            DateTimeParts::decode(&[0x89, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x97]).unwrap();
+        assert_eq!(parts.year, 2089);
+    }
+
+    #[test]
+    fn check_system_time() {
+        // 2000-01-01 00:00:00.000, a saturday
+        let time = UNIX_EPOCH + Duration::from_secs(946_684_800);
+        let parts = DateTimeParts::from_system_time(time);
+        assert_eq!(
+            parts.encode().unwrap(),
+            [0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x07]
+        );
+        assert_eq!(parts.to_system_time(), Some(time));
+
+        assert!(DateTimeParts {
+            day: 30,
+            month: 2,
+            ..parts
+        }
+        .to_system_time()
+        .is_none());
+        assert!(DateTimeParts {
+            year: 2090,
+            ..parts
+        }
+        .to_system_time()
+        .is_none());
+    }
+
+    #[test]
+    fn check_invalid_bcd_nibbles() {
+        let bytes = [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35];
+        for (index, invalid) in [
+            (0, 0xa3),
+            (0, 0x2f),
+            (2, 0x1b),
+            (4, 0xc5),
+            (5, 0x3a),
+            (6, 0x1e),
+            // the least significant digit of millisecond
+            (7, 0xa5),
+        ] {
+            let mut bytes = bytes;
+            bytes[index] = invalid;
+            assert!(DateTimeParts::decode(&bytes).is_err(), "{:02x?}", bytes);
+        }
+        // weekday out of 1..=7
+        for weekday in [0x30, 0x38, 0x3f] {
+            let mut bytes = bytes;
+            bytes[7] = weekday;
+            assert!(DateTimeParts::decode(&bytes).is_err(), "{:02x?}", bytes);
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn check_chrono() {
+        use chrono::NaiveDate;
+
+        let date_time = NaiveDate::from_ymd_opt(2023, 6, 15)
+            .unwrap()
+            .and_hms_milli_opt(13, 45, 30, 123)
+            .unwrap();
+        let parts = DateTimeParts::try_from(date_time).unwrap();
+        // 2023-06-15 is a thursday
+        assert_eq!(parts.weekday, 5);
+        assert_eq!(
+            parts.encode().unwrap(),
+            [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35]
+        );
+        assert_eq!(chrono::NaiveDateTime::try_from(parts).unwrap(), date_time);
+
+        let date_time = NaiveDate::from_ymd_opt(2090, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert!(DateTimeParts::try_from(date_time).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{ErrorClass, ReturnCode};
 use copt::error::ToCoptError;
 use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
 use std::io;
@@ -8,12 +9,65 @@ pub enum Error {
     #[error(transparent)]
     IoErr(#[from] io::Error),
 
+    #[error("insufficient data: need {needed} bytes, got {got} bytes")]
+    InsufficientData { needed: usize, got: usize },
+
+    #[error("unknown rosctr: {0:#04x}")]
+    UnknownRosctr(u8),
+
+    #[error("unknown function: {0:#04x}")]
+    UnknownFunction(u8),
+
+    #[error(
+        "data item val can't be {expected}: transport size {transport_size:#04x}, {len} bytes"
+    )]
+    TypeMismatch {
+        expected: &'static str,
+        transport_size: u8,
+        len: usize,
+    },
+
+    #[error(
+        "nak response, error class: {error_class:#04x}, error code: {error_code:#04x}, {}",
+        nak_description_of(.error_class, .error_code)
+    )]
+    NakResponse { error_class: u8, error_code: u8 },
+
+    #[error("item {index} error: {return_code}")]
+    ItemError {
+        index: usize,
+        return_code: ReturnCode,
+    },
+
+    /// the value doesn't fit in the s7 type, e.g. a S5TIME beyond 9990s in
+    /// milliseconds
+    #[error("{value} is out of the range of {ty}")]
+    OutOfRange { ty: &'static str, value: i64 },
+
+    /// the peer did not answer within the timeout of the transport, e.g.
+    /// `operation` is `"read_db"`
+    #[error("{operation} timed out")]
+    Timeout { operation: &'static str },
+
+    #[error("should recv {expected} items, but recv {actual}")]
+    ItemCountMismatch { expected: usize, actual: usize },
+
     #[error("{0}")]
     Other(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+impl Error {
+    /// the kind of the io error, e.g. to retry on `ConnectionReset`
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            Error::IoErr(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
 impl<T: TryFromPrimitive> From<TryFromPrimitiveError<T>> for Error {
     fn from(value: TryFromPrimitiveError<T>) -> Self {
         Self::Other(format!("{}", value))
@@ -22,6 +76,106 @@ impl<T: TryFromPrimitive> From<TryFromPrimitiveError<T>> for Error {
 
 impl ToCoptError for Error {
     fn to_err(self) -> copt::error::Error {
-        copt::error::Error::Other(self.to_string())
+        match self {
+            Error::IoErr(e) => copt::error::Error::IoErr(e),
+            e => copt::error::Error::Other(e.to_string()),
+        }
+    }
+}
+
+/// human-readable description of the error class and error code of an ack
+/// data header, more info: the `param_errcode_names` of wireshark's s7comm
+/// dissector
+pub fn nak_description(error_class: u8, error_code: u8) -> &'static str {
+    match u16::from_be_bytes([error_class, error_code]) {
+        0x0000 => "No error",
+        0x0110 => "Invalid block number",
+        0x0111 => "Invalid request length",
+        0x0112 => "Invalid parameter",
+        0x0113 => "Invalid block type",
+        0x0114 => "Block not found",
+        0x0115 => "Block already exists",
+        0x0116 => "Block is write-protected",
+        0x0117 => "The block/operating system update is too large",
+        0x0118 => "Invalid block number",
+        0x0119 => "Incorrect password entered",
+        0x011a => "PG resource error",
+        0x011b => "PLC resource error",
+        0x011c => "Protocol error",
+        0x011d => "Too many blocks (module-related restriction)",
+        0x011e => "There is no longer a connection to the database, or S7DOS handle is invalid",
+        0x011f => "Result buffer too small",
+        0x0120 => "End of block list",
+        0x0140 => "Insufficient memory available",
+        0x0141 => "Job cannot be processed because of a lack of resources",
+        0x8001 => "The requested service cannot be performed while the block is in the current status",
+        0x8003 => "S7 protocol error: Error occurred while transferring the block",
+        0x8100 => "Application, general error: Service unknown to remote module",
+        0x8104 => "This service is not implemented on the module or a frame error was reported",
+        0x8204 => "The type specification for the object is inconsistent",
+        0x8205 => "A copied block already exists and is not linked",
+        0x8301 => "Insufficient memory space or work memory on the module, or specified storage medium not accessible",
+        0x8302 => "Too few resources available or the processor resources are not available",
+        0x8304 => "No further parallel upload possible. There is a resource bottleneck",
+        0x8305 => "Function not available",
+        0x8306 => "Insufficient work memory (for copying, linking, loading AWP)",
+        0x8307 => "Not enough retentive work memory (for copying, linking, loading AWP)",
+        0x8401 => "S7 protocol error: Invalid service sequence (for example, loading or uploading a block)",
+        0x8402 => "Service cannot execute owing to status of the addressed object",
+        0x8404 => "S7 protocol: The function cannot be performed",
+        0x8405 => "Remote block is in DISABLE state (CFB). The function cannot be performed",
+        0x8500 => "S7 protocol error: Wrong frames",
+        0x8503 => "Alarm from the module: Service canceled prematurely",
+        0x8701 => "Error addressing the object on the communications partner (for example, area length error)",
+        0x8702 => "The requested service is not supported by the module",
+        0x8703 => "Access to object refused",
+        0x8704 => "Access error: Object damaged",
+        0xd001 => "Protocol error: Illegal job number",
+        0xd002 => "Parameter error: Illegal job variant",
+        0xd004 => "Parameter error: Illegal job status",
+        0xd011 => "Parameter error in path of the call environment: Block does not exist",
+        0xd012 => "Parameter error: Wrong address in block",
+        0xd015 => "Parameter error: Illegal tag address",
+        0xd031 => "Internal protocol error",
+        0xd032 => "Parameter error: Wrong result buffer length",
+        0xd033 => "Protocol error: Wrong job length",
+        0xd03f => "Coding error: Error in parameter section",
+        0xd041 => "Data error: Illegal status list ID",
+        0xd042 => "Data error: Illegal tag address",
+        0xd05f => "Coding error: Error in data section",
+        0xd061 => "Resource error: No memory space for job",
+        0xd081 => "Function not permitted in current mode",
+        0xd0a1 => "Function not permitted in current protection level",
+        0xd0ad => "Operating mode already set",
+        0xd209 => "(At least) one of the given blocks not found on the module",
+        0xd20e => "No (further) block available",
+        0xd210 => "Invalid block number",
+        0xd241 => "The function is not permitted in the current protection level",
+        0xd401 => "Information function unavailable",
+        0xd402 => "Information function unavailable",
+        0xd405 => "Service not supported or syntax error in function parameters",
+        0xd406 => "Required information currently unavailable",
+        0xd601 => "Syntax error in function parameter",
+        0xd602 => "Incorrect password entered",
+        0xd603 => "The connection has already been legitimized",
+        0xd604 => "The connection has already been enabled",
+        0xd605 => "Legitimization not possible because password does not exist",
+        0xdc01 => "Date and/or time invalid",
+        0xef01 => "S7 protocol error: Error at ID2; only 00H permitted in job",
+        0xef02 => "S7 protocol error: Error at ID2; set of resources does not exist",
+        _ => match ErrorClass::from(error_class) {
+            ErrorClass::NoError => "No error",
+            ErrorClass::ApplicationRelationship => "Application relationship error",
+            ErrorClass::ObjectDefinition => "Object definition error",
+            ErrorClass::NoResourcesAvailable => "No resources available error",
+            ErrorClass::ErrorOnServiceProcessing => "Error on service processing",
+            ErrorClass::ErrorOnSupplies => "Error on supplies",
+            ErrorClass::AccessError => "Access error",
+            ErrorClass::NotSupport(_) => "Unknown error",
+        },
     }
 }
+
+fn nak_description_of(error_class: &u8, error_code: &u8) -> &'static str {
+    nak_description(*error_class, *error_code)
+}
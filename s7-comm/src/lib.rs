@@ -1,19 +1,50 @@
+mod block;
+mod block_transfer;
 pub mod builder;
+mod clock;
+mod date_time;
 mod error;
+mod ob_start_info;
 mod packet;
+mod plc_control;
+mod szl;
+mod types;
+mod user_data;
 
 // use crate::packet::{AckData, Frame, Header, HearderAckData, Job};
+pub use block::*;
+pub use block_transfer::*;
 use bytes::{BufMut, BytesMut};
+pub use clock::*;
+pub use date_time::*;
 pub use error::*;
 use log::debug;
+pub use ob_start_info::*;
 pub use packet::*;
+pub use plc_control::*;
+pub use szl::*;
 use tokio_util::codec::{Decoder, Encoder};
+pub use types::*;
+pub use user_data::*;
 
 #[derive(Default)]
 pub struct S7CommEncoder;
 
-#[derive(Default)]
-pub struct S7CommDecoder;
+#[derive(Default, Debug, Clone, Copy)]
+pub struct S7CommDecoder {
+    lenient_padding: bool,
+}
+
+impl S7CommDecoder {
+    /// Tolerate a firmware which omits the fill byte after an odd length data
+    /// item of a read var response, off by default. The fill byte is always
+    /// 0, so a byte other than 0 is taken as the return code of the next
+    /// item.
+    pub fn lenient_padding(mut self, lenient_padding: bool) -> Self {
+        self.lenient_padding = lenient_padding;
+        self
+    }
+}
 
 impl Encoder<Frame> for S7CommEncoder {
     type Error = Error;
@@ -47,6 +78,38 @@ impl Encoder<Frame> for S7CommEncoder {
                         dst.put_u8(0x04);
                         data.encode(dst);
                     }
+                    Job::PlcControl(data) => {
+                        dst.put_u8(0x28);
+                        data.encode(dst);
+                    }
+                    Job::PlcStop(data) => {
+                        dst.put_u8(0x29);
+                        data.encode(dst);
+                    }
+                    Job::RequestDownload(data) => {
+                        dst.put_u8(0x1a);
+                        data.encode(dst);
+                    }
+                    Job::DownloadBlock(data) => {
+                        dst.put_u8(0x1b);
+                        data.encode(dst);
+                    }
+                    Job::DownloadEnded(data) => {
+                        dst.put_u8(0x1c);
+                        data.encode(dst);
+                    }
+                    Job::StartUpload(data) => {
+                        dst.put_u8(0x1d);
+                        data.encode(dst);
+                    }
+                    Job::Upload(data) => {
+                        dst.put_u8(0x1e);
+                        data.encode(dst);
+                    }
+                    Job::EndUpload(data) => {
+                        dst.put_u8(0x1f);
+                        data.encode(dst);
+                    }
                 }
             }
             Frame::AckData { header, ack_data } => {
@@ -80,8 +143,61 @@ impl Encoder<Frame> for S7CommEncoder {
                         dst.put_u8(0x04);
                         data.encode(dst);
                     }
+                    AckData::PlcControl(data) => {
+                        dst.put_u8(0x28);
+                        data.encode(dst);
+                    }
+                    AckData::PlcStop(data) => {
+                        dst.put_u8(0x29);
+                        data.encode(dst);
+                    }
+                    AckData::RequestDownload(data) => {
+                        dst.put_u8(0x1a);
+                        data.encode(dst);
+                    }
+                    AckData::DownloadBlock(data) => {
+                        dst.put_u8(0x1b);
+                        data.encode(dst);
+                    }
+                    AckData::DownloadEnded(data) => {
+                        dst.put_u8(0x1c);
+                        data.encode(dst);
+                    }
+                    AckData::StartUpload(data) => {
+                        dst.put_u8(0x1d);
+                        data.encode(dst);
+                    }
+                    AckData::Upload(data) => {
+                        dst.put_u8(0x1e);
+                        data.encode(dst);
+                    }
+                    AckData::EndUpload(data) => {
+                        dst.put_u8(0x1f);
+                        data.encode(dst);
+                    }
                 }
             }
+            Frame::UserData {
+                header,
+                parameter,
+                data,
+            } => {
+                let Header {
+                    protocol_id,
+                    reserved,
+                    pdu_ref,
+                    parameter_len,
+                    data_len,
+                } = header;
+                dst.put_u8(protocol_id);
+                dst.put_u8(0x07);
+                dst.extend_from_slice(reserved.to_be_bytes().as_slice());
+                dst.extend_from_slice(pdu_ref.to_be_bytes().as_slice());
+                dst.extend_from_slice(parameter_len.to_be_bytes().as_slice());
+                dst.extend_from_slice(data_len.to_be_bytes().as_slice());
+                parameter.encode(dst);
+                data.encode(dst);
+            }
         }
         Ok(())
     }
@@ -113,7 +229,7 @@ impl Decoder for S7CommDecoder {
         match *rosctr {
             1 => {
                 // job
-                if src.len() < (10 + parameter_length + data_length) as usize {
+                if src.len() < 10 + parameter_length as usize + data_length as usize {
                     return Ok(None);
                 }
                 let header = Header::decode(src);
@@ -122,7 +238,7 @@ impl Decoder for S7CommDecoder {
             }
             3 => {
                 // ack data
-                if src.len() < (12 + parameter_length + data_length) as usize {
+                if src.len() < 12 + parameter_length as usize + data_length as usize {
                     debug!(
                         "parameter_length: {}, data_length: {}",
                         parameter_length, data_length
@@ -130,10 +246,24 @@ impl Decoder for S7CommDecoder {
                     return Ok(None);
                 }
                 let header = HearderAckData::decode(src);
-                let ack_data = AckData::decode(src)?;
+                let ack_data = AckData::decode(src, header.parameter_len, self.lenient_padding)?;
                 Ok(Some(Frame::AckData { header, ack_data }))
             }
-            _ => Err(Error::Other(format!("not support rosctr: {}", rosctr))),
+            7 => {
+                // user data
+                if src.len() < 10 + parameter_length as usize + data_length as usize {
+                    return Ok(None);
+                }
+                let header = Header::decode(src);
+                let parameter = UserDataParameter::decode(src, header.parameter_len)?;
+                let data = UserDataData::decode(src)?;
+                Ok(Some(Frame::UserData {
+                    header,
+                    parameter,
+                    data,
+                }))
+            }
+            _ => Err(Error::UnknownRosctr(*rosctr)),
         }
     }
 }
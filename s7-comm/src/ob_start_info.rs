@@ -0,0 +1,84 @@
+use crate::{date_time::*, error::*};
+
+/// length of the start info in the temp area of an organization block
+pub const OB_START_INFO_LEN: usize = 20;
+
+/// The 20-byte start info at the beginning of the temp area of an
+/// organization block, e.g. OB82 (diagnostic interrupt):
+///
+/// | byte   | content                                           |
+/// |--------|---------------------------------------------------|
+/// | 0      | event class, e.g. 0x39: incoming event of OB82     |
+/// | 1      | fault id (start info), e.g. 0x42 of OB82           |
+/// | 2      | priority class                                    |
+/// | 3      | ob number                                         |
+/// | 4..12  | ob specific, e.g. io flag and module address of OB82 |
+/// | 12..20 | DATE_AND_TIME of the event                        |
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ObStartInfo {
+    pub event_class: u8,
+    pub fault_id: u8,
+    pub priority: u8,
+    pub ob_number: u8,
+    pub info: [u8; 8],
+    pub date_time: DateTimeParts,
+}
+
+impl ObStartInfo {
+    /// the word at byte 6, e.g. the module address of OB82/OB83/OB86
+    pub fn info_word(&self) -> u16 {
+        u16::from_be_bytes([self.info[2], self.info[3]])
+    }
+
+    /// the double word at byte 8
+    pub fn info_dword(&self) -> u32 {
+        u32::from_be_bytes([self.info[4], self.info[5], self.info[6], self.info[7]])
+    }
+}
+
+pub fn decode_ob_start_info(data: &[u8; OB_START_INFO_LEN]) -> Result<ObStartInfo> {
+    let mut info = [0u8; 8];
+    info.copy_from_slice(&data[4..12]);
+    Ok(ObStartInfo {
+        event_class: data[0],
+        fault_id: data[1],
+        priority: data[2],
+        ob_number: data[3],
+        info,
+        date_time: DateTimeParts::decode(&data[12..])?,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_ob_start_info;
+
+    #[test]
+    fn check_ob82_start_info() {
+        // incoming diagnostic interrupt of the input module at address 256
+        let data = [
+            0x39, 0x42, 0x1a, 0x52, 0x00, 0x54, 0x01, 0x00, 0x15, 0x00, 0x00, 0x00, 0x23, 0x06,
+            0x15, 0x13, 0x45, 0x30, 0x12, 0x35,
+        ];
+        let info = decode_ob_start_info(&data).unwrap();
+        assert_eq!(info.event_class, 0x39);
+        assert_eq!(info.fault_id, 0x42);
+        assert_eq!(info.priority, 26);
+        assert_eq!(info.ob_number, 82);
+        assert_eq!(info.info[1], 0x54);
+        assert_eq!(info.info_word(), 256);
+        assert_eq!(info.info_dword(), 0x1500_0000);
+        assert_eq!(
+            (
+                info.date_time.year,
+                info.date_time.month,
+                info.date_time.day
+            ),
+            (2023, 6, 15)
+        );
+
+        let mut data = data;
+        data[13] = 0x13;
+        assert!(decode_ob_start_info(&data).is_err());
+    }
+}
@@ -1,6 +1,10 @@
-use crate::{builder::*, error::*};
+use crate::{block_transfer::*, builder::*, date_time::*, error::*, plc_control::*, user_data::*};
 use bytes::{Buf, BufMut, BytesMut};
-use num_enum::{FromPrimitive, IntoPrimitive, TryFromPrimitive};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 /// more info: https://github.com/wireshark/wireshark/blob/master/epan/dissectors/packet-s7comm.c
 
 #[derive(Debug, Eq, PartialEq)]
@@ -12,6 +16,12 @@ pub enum Frame {
         header: HearderAckData,
         ack_data: AckData,
     },
+    /// 0x07
+    UserData {
+        header: Header,
+        parameter: UserDataParameter,
+        data: UserDataData,
+    },
 }
 
 impl Frame {
@@ -26,6 +36,43 @@ impl Frame {
     pub fn job_read_var(pdu_ref: u16) -> FrameJobReadVarBuilder {
         FrameJobReadVarBuilder::default().pdu_ref(pdu_ref)
     }
+
+    pub fn setup_communication(
+        pdu_ref: u16,
+        max_amq_calling: u16,
+        max_amq_called: u16,
+        pdu_length: u16,
+    ) -> Frame {
+        Self::job_setup(pdu_ref)
+            .max_amq_calling(max_amq_calling)
+            .max_amq_called(max_amq_called)
+            .pdu_length(pdu_length)
+            .build()
+    }
+
+    /// the negotiated pdu length, if the frame is the ack of setup communication
+    pub fn negotiated_pdu_length(&self) -> Option<u16> {
+        match self {
+            Frame::AckData {
+                ack_data: AckData::SetupCommunication(data),
+                ..
+            } => Some(data.pdu_length()),
+            _ => None,
+        }
+    }
+
+    /// the negotiated max amq calling and called, i.e. the number of jobs
+    /// each side may have unacknowledged, if the frame is the ack of setup
+    /// communication
+    pub fn negotiated_max_amq(&self) -> Option<(u16, u16)> {
+        match self {
+            Frame::AckData {
+                ack_data: AckData::SetupCommunication(data),
+                ..
+            } => Some((data.max_amq_calling(), data.max_amq_called())),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -109,6 +156,42 @@ impl HearderAckData {
         }
     }
 
+    pub fn pdu_ref(&self) -> u16 {
+        self.pdu_ref
+    }
+
+    pub fn parameter_len(&self) -> u16 {
+        self.parameter_len
+    }
+
+    pub fn data_len(&self) -> u16 {
+        self.data_len
+    }
+
+    pub fn error_class(&self) -> ErrorClass {
+        ErrorClass::from(self.error_class)
+    }
+
+    pub fn error_code(&self) -> u8 {
+        self.error_code
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error_class == 0 && self.error_code == 0
+    }
+
+    /// `Err(Error::NakResponse)` if the plc rejected the job
+    pub fn check(&self) -> Result<()> {
+        if self.is_ok() {
+            Ok(())
+        } else {
+            Err(Error::NakResponse {
+                error_class: self.error_class,
+                error_code: self.error_code,
+            })
+        }
+    }
+
     pub(crate) fn decode(src: &mut BytesMut) -> Self {
         let protocol_id = src.get_u8();
         src.get_u8();
@@ -130,6 +213,21 @@ impl HearderAckData {
     }
 }
 
+/// error class of ack data header
+#[derive(Debug, Copy, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[repr(u8)]
+pub enum ErrorClass {
+    NoError = 0x00,
+    ApplicationRelationship = 0x81,
+    ObjectDefinition = 0x82,
+    NoResourcesAvailable = 0x83,
+    ErrorOnServiceProcessing = 0x84,
+    ErrorOnSupplies = 0x85,
+    AccessError = 0x87,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
 // #[derive(IntoPrimitive, FromPrimitive)]
 // #[repr(u8)]
 // pub enum Rosctr {
@@ -148,9 +246,52 @@ pub enum Job {
     WriteVar(WriteVarJob),
     /// 0x04
     ReadVar(ReadVarJob),
+    /// 0x28
+    PlcControl(PlcControl),
+    /// 0x29
+    PlcStop(PlcStop),
+    /// 0x1a
+    RequestDownload(UpDownload),
+    /// 0x1b
+    DownloadBlock(UpDownload),
+    /// 0x1c
+    DownloadEnded(UpDownload),
+    /// 0x1d
+    StartUpload(UpDownload),
+    /// 0x1e
+    Upload(UpDownload),
+    /// 0x1f
+    EndUpload(UpDownload),
 }
 
 impl Job {
+    /// the function code of the job
+    pub fn function(&self) -> u8 {
+        match self {
+            Job::SetupCommunication(_) => 0xf0,
+            Job::WriteVar(_) => 0x05,
+            Job::ReadVar(_) => 0x04,
+            Job::PlcControl(_) => 0x28,
+            Job::PlcStop(_) => 0x29,
+            Job::RequestDownload(_) => 0x1a,
+            Job::DownloadBlock(_) => 0x1b,
+            Job::DownloadEnded(_) => 0x1c,
+            Job::StartUpload(_) => 0x1d,
+            Job::Upload(_) => 0x1e,
+            Job::EndUpload(_) => 0x1f,
+        }
+    }
+
+    /// the requested items of read var and write var, empty for the other
+    /// jobs
+    pub fn items(&self) -> &[ItemRequest] {
+        match self {
+            Job::WriteVar(job) => job.parameters_item(),
+            Job::ReadVar(job) => job.parameters_item(),
+            _ => &[],
+        }
+    }
+
     pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
         let function = src.get_u8();
         match function {
@@ -161,7 +302,7 @@ impl Job {
                     parameters_item.push(ItemRequest::decode(src)?);
                 }
                 Ok(Self::ReadVar(ReadVarJob {
-                    count: 0,
+                    count,
                     parameters_item,
                 }))
             }
@@ -172,11 +313,11 @@ impl Job {
                     parameters_item.push(ItemRequest::decode(src)?);
                 }
                 let mut data_item = Vec::with_capacity(count as usize);
-                for _ in 0..count {
-                    data_item.push(DataItemVal::decode(src)?);
+                for index in 0..count {
+                    data_item.push(DataItemVal::decode(src, index + 1 == count, false)?);
                 }
                 Ok(Self::WriteVar(WriteVarJob {
-                    count: 0,
+                    count,
                     parameters_item,
                     data_item,
                 }))
@@ -185,7 +326,15 @@ impl Job {
                 let data = SetupCommunication::decode(src)?;
                 Ok(Self::SetupCommunication(data))
             }
-            _ => Err(Error::Other(format!("not support function: {}", function))),
+            0x28 => Ok(Self::PlcControl(PlcControl::decode(src)?)),
+            0x29 => Ok(Self::PlcStop(PlcStop::decode(src)?)),
+            0x1a => Ok(Self::RequestDownload(UpDownload::decode(src, function)?)),
+            0x1b => Ok(Self::DownloadBlock(UpDownload::decode(src, function)?)),
+            0x1c => Ok(Self::DownloadEnded(UpDownload::decode(src, function)?)),
+            0x1d => Ok(Self::StartUpload(UpDownload::decode(src, function)?)),
+            0x1e => Ok(Self::Upload(UpDownload::decode(src, function)?)),
+            0x1f => Ok(Self::EndUpload(UpDownload::decode(src, function)?)),
+            _ => Err(Error::UnknownFunction(function)),
         }
     }
 }
@@ -198,17 +347,45 @@ pub enum AckData {
     WriteVar(WriteVarAckData),
     /// 0x04
     ReadVar(ReadVarAckData),
+    /// 0x28
+    PlcControl(PlcControlAck),
+    /// 0x29
+    PlcStop(PlcControlAck),
+    /// 0x1a
+    RequestDownload(PlcControlAck),
+    /// 0x1b
+    DownloadBlock(BlockDataAck),
+    /// 0x1c
+    DownloadEnded(PlcControlAck),
+    /// 0x1d
+    StartUpload(StartUploadAck),
+    /// 0x1e
+    Upload(BlockDataAck),
+    /// 0x1f
+    EndUpload(PlcControlAck),
 }
 
 impl AckData {
-    pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
+    /// `lenient_padding`: tolerate the missing fill byte of an odd length
+    /// data item of read var, see `S7CommDecoder::lenient_padding`
+    pub(crate) fn decode(
+        src: &mut BytesMut,
+        parameter_len: u16,
+        lenient_padding: bool,
+    ) -> Result<Self> {
+        // the parameter following the function
+        let parameter_len = parameter_len.saturating_sub(1) as usize;
         let function = src.get_u8();
         match function {
             0x04 => {
                 let count = src.get_u8();
                 let mut data_item = Vec::with_capacity(count as usize);
-                for _ in 0..count {
-                    data_item.push(DataItemVal::decode(src)?);
+                for index in 0..count {
+                    data_item.push(DataItemVal::decode(
+                        src,
+                        index + 1 == count,
+                        lenient_padding,
+                    )?);
                 }
                 Ok(Self::ReadVar(ReadVarAckData { count, data_item }))
             }
@@ -231,7 +408,27 @@ impl AckData {
                 let data = SetupCommunication::decode(src)?;
                 Ok(Self::SetupCommunication(data))
             }
-            _ => Err(Error::Other(format!("not support function: {}", function))),
+            0x28 => Ok(Self::PlcControl(PlcControlAck::decode(src, parameter_len)?)),
+            0x29 => Ok(Self::PlcStop(PlcControlAck::decode(src, parameter_len)?)),
+            0x1a => Ok(Self::RequestDownload(PlcControlAck::decode(
+                src,
+                parameter_len,
+            )?)),
+            0x1b => Ok(Self::DownloadBlock(BlockDataAck::decode(
+                src,
+                parameter_len,
+            )?)),
+            0x1c => Ok(Self::DownloadEnded(PlcControlAck::decode(
+                src,
+                parameter_len,
+            )?)),
+            0x1d => Ok(Self::StartUpload(StartUploadAck::decode(
+                src,
+                parameter_len,
+            )?)),
+            0x1e => Ok(Self::Upload(BlockDataAck::decode(src, parameter_len)?)),
+            0x1f => Ok(Self::EndUpload(PlcControlAck::decode(src, parameter_len)?)),
+            _ => Err(Error::UnknownFunction(function)),
         }
     }
 }
@@ -246,7 +443,7 @@ pub struct WriteVarJob {
 
 impl WriteVarJob {
     pub fn bytes_len_data(&self) -> u16 {
-        self.data_item.iter().fold(0, |len, x| len + x.bytes_len())
+        data_items_bytes_len(&self.data_item)
     }
 
     pub fn bytes_len_parameter(&self) -> u16 {
@@ -261,10 +458,18 @@ impl WriteVarJob {
         self.data_item.push(x.1);
     }
 
+    pub fn parameters_item(&self) -> &[ItemRequest] {
+        &self.parameters_item
+    }
+
+    pub fn data_item(&self) -> &[DataItemVal] {
+        &self.data_item
+    }
+
     pub(crate) fn encode(self, dst: &mut BytesMut) {
         dst.put_u8(self.count);
         self.parameters_item.into_iter().for_each(|x| x.encode(dst));
-        self.data_item.into_iter().for_each(|x| x.encode(dst));
+        encode_data_items(self.data_item, dst);
     }
 }
 
@@ -279,6 +484,21 @@ impl WriteVarAckData {
         self.data_item
     }
 
+    /// `Err(Error::ItemError)` with the first failed item
+    pub fn check(&self) -> Result<()> {
+        match self
+            .data_item
+            .iter()
+            .position(|x| !x.return_code.is_success())
+        {
+            Some(index) => Err(Error::ItemError {
+                index,
+                return_code: self.data_item[index].return_code.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
     pub fn add_response(mut self, response: DataItemWriteResponse) -> Self {
         self.count += 1;
         self.data_item.push(response);
@@ -312,12 +532,27 @@ impl ReadVarJob {
         self.parameters_item.push(x);
     }
 
+    pub fn parameters_item(&self) -> &[ItemRequest] {
+        &self.parameters_item
+    }
+
     pub(crate) fn encode(self, dst: &mut BytesMut) {
         dst.put_u8(self.count);
         self.parameters_item.into_iter().for_each(|x| x.encode(dst));
     }
 }
 
+/// how a read var response with fewer data items than the request is handled
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum MissingItems {
+    /// `Err(Error::ItemCountMismatch)`
+    #[default]
+    Error,
+    /// the missing items are failed with `ReturnCode::Err`, object does not
+    /// exist
+    Pad,
+}
+
 #[derive(Debug, Eq, PartialEq, Default)]
 pub struct ReadVarAckData {
     count: u8,
@@ -328,6 +563,48 @@ impl ReadVarAckData {
         self.data_item
     }
 
+    pub fn bytes_len_data(&self) -> u16 {
+        data_items_bytes_len(&self.data_item)
+    }
+
+    /// the result of every item, failed items carry their return code
+    pub fn results(self) -> Vec<std::result::Result<DataItemVal, ReturnCode>> {
+        self.data_item
+            .into_iter()
+            .map(|x| {
+                if x.return_code.is_success() {
+                    Ok(x)
+                } else {
+                    Err(x.return_code)
+                }
+            })
+            .collect()
+    }
+
+    /// the data items, or `Err(Error::ItemError)` with the first failed item
+    pub fn try_data_item(self) -> Result<Vec<DataItemVal>> {
+        self.results()
+            .into_iter()
+            .enumerate()
+            .map(|(index, x)| x.map_err(|return_code| Error::ItemError { index, return_code }))
+            .collect()
+    }
+
+    /// The data items of a request of `expected` items, the index of an item
+    /// is the index of its request item. More items than requested is always
+    /// an error.
+    pub fn data_item_of(self, expected: usize, missing: MissingItems) -> Result<Vec<DataItemVal>> {
+        let actual = self.data_item.len();
+        if actual > expected || (actual < expected && missing == MissingItems::Error) {
+            return Err(Error::ItemCountMismatch { expected, actual });
+        }
+        let mut data_item = self.data_item;
+        data_item.resize_with(expected, || {
+            DataItemVal::init_with_bytes(ReturnCode::Err, &[])
+        });
+        Ok(data_item)
+    }
+
     pub fn add_response(mut self, value: DataItemVal) -> Self {
         self.count += 1;
         self.data_item.push(value);
@@ -336,7 +613,25 @@ impl ReadVarAckData {
 
     pub(crate) fn encode(self, dst: &mut BytesMut) {
         dst.put_u8(self.count);
-        self.data_item.into_iter().for_each(|x| x.encode(dst));
+        encode_data_items(self.data_item, dst);
+    }
+}
+
+/// the fill byte follows every odd-length data item but the last
+fn data_items_bytes_len(items: &[DataItemVal]) -> u16 {
+    items.iter().enumerate().fold(0, |len, (index, x)| {
+        len + x.bytes_len() + x.fill_byte_len(index + 1 == items.len())
+    })
+}
+
+fn encode_data_items(items: Vec<DataItemVal>, dst: &mut BytesMut) {
+    let count = items.len();
+    for (index, x) in items.into_iter().enumerate() {
+        let fill_byte_len = x.fill_byte_len(index + 1 == count);
+        x.encode(dst);
+        if fill_byte_len > 0 {
+            dst.put_u8(0);
+        }
     }
 }
 
@@ -371,11 +666,10 @@ impl SetupCommunication {
 
     fn decode(src: &mut BytesMut) -> Result<Self> {
         if src.len() < Self::len() {
-            return Err(Error::Other(
-                "data of SetupCommunication not \
-                 enough"
-                    .to_string(),
-            ));
+            return Err(Error::InsufficientData {
+                needed: Self::len(),
+                got: src.len(),
+            });
         }
         let reserved = src.get_u8();
         let max_amq_calling = src.get_u16();
@@ -389,6 +683,14 @@ impl SetupCommunication {
         })
     }
 
+    pub fn max_amq_calling(&self) -> u16 {
+        self.max_amq_calling
+    }
+
+    pub fn max_amq_called(&self) -> u16 {
+        self.max_amq_called
+    }
+
     pub fn pdu_length(&self) -> u16 {
         self.pdu_length
     }
@@ -397,7 +699,8 @@ impl SetupCommunication {
 const PARAM_ITEM_VAR_SPEC: u8 = 0x12;
 const PARAM_ITEM_VAR_SPEC_LENGTH: u8 = 0x0a;
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ItemRequest {
     variable_specification: u8,
     follow_length: u8,
@@ -475,6 +778,89 @@ impl ItemRequest {
         }
     }
 
+    pub fn init_db_byte(db_number: u16, byte_addr: u16, length: u16) -> Self {
+        Self::init_byte(Some(db_number), Area::DataBlocks, byte_addr, length)
+    }
+
+    pub fn init_db_bit(db_number: u16, byte_addr: u16, bit_addr: u8) -> Self {
+        Self::init_bit(Some(db_number), Area::DataBlocks, byte_addr, bit_addr)
+    }
+
+    pub fn init_merker_byte(byte_addr: u16, length: u16) -> Self {
+        Self::init_byte(None, Area::Merker, byte_addr, length)
+    }
+
+    pub fn init_merker_bit(byte_addr: u16, bit_addr: u8) -> Self {
+        Self::init_bit(None, Area::Merker, byte_addr, bit_addr)
+    }
+
+    pub fn init_input_byte(byte_addr: u16, length: u16) -> Self {
+        Self::init_byte(None, Area::ProcessInput, byte_addr, length)
+    }
+
+    pub fn init_input_bit(byte_addr: u16, bit_addr: u8) -> Self {
+        Self::init_bit(None, Area::ProcessInput, byte_addr, bit_addr)
+    }
+
+    pub fn init_output_byte(byte_addr: u16, length: u16) -> Self {
+        Self::init_byte(None, Area::ProcessOutput, byte_addr, length)
+    }
+
+    pub fn init_output_bit(byte_addr: u16, bit_addr: u8) -> Self {
+        Self::init_bit(None, Area::ProcessOutput, byte_addr, bit_addr)
+    }
+
+    /// timers are addressed by number, `count` is the number of timers
+    pub fn init_timer(number: u16, count: u16) -> Self {
+        Self::new(
+            TransportSize::Timer,
+            DbNumber::NotIn,
+            Area::Timer,
+            number,
+            0,
+            count,
+        )
+    }
+
+    /// counters are addressed by number, `count` is the number of counters
+    pub fn init_counter(number: u16, count: u16) -> Self {
+        Self::new(
+            TransportSize::Counter,
+            DbNumber::NotIn,
+            Area::Counter,
+            number,
+            0,
+            count,
+        )
+    }
+
+    pub fn transport_size_type(&self) -> TransportSize {
+        self.transport_size_type
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn db_number(&self) -> u16 {
+        match self.db_number {
+            DbNumber::NotIn => 0,
+            DbNumber::DbNumber(x) => x,
+        }
+    }
+
+    pub fn area(&self) -> &Area {
+        &self.area
+    }
+
+    pub fn byte_addr(&self) -> u16 {
+        self.address.byte_addr
+    }
+
+    pub fn bit_addr(&self) -> u8 {
+        self.address.bit_addr
+    }
+
     pub fn bytes_len(&self) -> u16 {
         12
     }
@@ -492,9 +878,10 @@ impl ItemRequest {
 
     fn decode(src: &mut BytesMut) -> Result<Self> {
         if src.len() < 12 {
-            return Err(Error::Other(
-                "item request byte's length is not enough".to_string(),
-            ));
+            return Err(Error::InsufficientData {
+                needed: 12,
+                got: src.len(),
+            });
         }
 
         let variable_specification = src.get_u8();
@@ -519,6 +906,25 @@ impl ItemRequest {
     }
 }
 
+/// the max length of a STRING is at most 254
+fn check_string_max_len(max_len: u8) -> Result<()> {
+    if max_len > 254 {
+        return Err(Error::OutOfRange {
+            ty: "STRING max length",
+            value: max_len as i64,
+        });
+    }
+    Ok(())
+}
+
+/// the `len` bytes of the chars of the current length of a string
+fn string_chars(chars: &[u8], len: usize) -> Result<&[u8]> {
+    chars.get(..len).ok_or(Error::InsufficientData {
+        needed: len,
+        got: chars.len(),
+    })
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DataItemWriteResponse {
     pub return_code: ReturnCode,
@@ -534,20 +940,22 @@ impl DataItemWriteResponse {
     }
 
     fn decode(src: &mut BytesMut) -> Result<Self> {
-        if src.len() == 0 {
-            return Err(Error::Other("byte's length is zero".to_string()));
+        if src.is_empty() {
+            return Err(Error::InsufficientData { needed: 1, got: 0 });
         }
         Ok(Self {
-            return_code: ReturnCode::try_from(src.get_u8())?,
+            return_code: ReturnCode::from_u8_lossy(src.get_u8()),
         })
     }
 }
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct DataItemVal {
     pub return_code: ReturnCode,
     pub transport_size_type: DataTransportSize,
     // 位查询,返回长度为0x0001; 非位查询,长度须左移3位
     pub length: u16,
+    #[cfg_attr(feature = "serde", serde(with = "hex"))]
     pub data: Vec<u8>,
 }
 
@@ -570,10 +978,303 @@ impl DataItemVal {
         }
     }
 
+    /// WORD, length in bits
+    pub fn init_with_word(return_code: ReturnCode, data: u16) -> Self {
+        Self {
+            return_code,
+            transport_size_type: DataTransportSize::NoBit,
+            length: 16,
+            data: data.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// DWORD, length in bits
+    pub fn init_with_dword(return_code: ReturnCode, data: u32) -> Self {
+        Self {
+            return_code,
+            transport_size_type: DataTransportSize::NoBit,
+            length: 32,
+            data: data.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// INT, length in bits
+    pub fn init_with_int(return_code: ReturnCode, data: i16) -> Self {
+        Self {
+            return_code,
+            transport_size_type: DataTransportSize::Integer,
+            length: 16,
+            data: data.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// DINT, length in bits
+    pub fn init_with_dint(return_code: ReturnCode, data: i32) -> Self {
+        Self {
+            return_code,
+            transport_size_type: DataTransportSize::Integer,
+            length: 32,
+            data: data.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// REAL, length in bytes
+    pub fn init_with_real(return_code: ReturnCode, data: f32) -> Self {
+        Self {
+            return_code,
+            transport_size_type: DataTransportSize::Real,
+            length: 4,
+            data: data.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// DATE, the days since 1990-01-01, up to `DATE_MAX_DAYS`
+    pub fn init_with_date(return_code: ReturnCode, days: u16) -> Result<Self> {
+        Ok(Self::init_with_word(return_code, check_date(days)?))
+    }
+
+    /// TIME, signed milliseconds
+    pub fn init_with_time(return_code: ReturnCode, ms: i32) -> Self {
+        Self::init_with_dword(return_code, ms as u32)
+    }
+
+    /// S5TIME, see `encode_s5_time`
+    pub fn init_with_s5time(return_code: ReturnCode, duration: Duration) -> Result<Self> {
+        Ok(Self::init_with_bytes(
+            return_code,
+            &encode_s5time(duration)?,
+        ))
+    }
+
+    /// STRING of `max_len` chars, at most 254: max length, current length
+    /// and the chars, a char out of latin-1 is written as '?', the chars
+    /// beyond `max_len` are truncated
+    pub fn init_with_string(return_code: ReturnCode, data: &str, max_len: u8) -> Result<Self> {
+        check_string_max_len(max_len)?;
+        let chars: Vec<u8> = data
+            .chars()
+            .take(max_len as usize)
+            .map(|x| u8::try_from(x).unwrap_or(b'?'))
+            .collect();
+        let mut string = Vec::with_capacity(chars.len() + 2);
+        string.push(max_len);
+        string.push(chars.len() as u8);
+        string.extend_from_slice(&chars);
+        Ok(Self::init_with_bytes(return_code, &string))
+    }
+
+    /// STRING of the ascii `value`, see `init_with_string`, but it fails if
+    /// `value` isn't ascii or exceeds `max_len` instead of replacing or
+    /// truncating the chars
+    pub fn init_with_s7_string(return_code: ReturnCode, value: &str, max_len: u8) -> Result<Self> {
+        check_string_max_len(max_len)?;
+        if value.len() > max_len as usize {
+            return Err(Error::OutOfRange {
+                ty: "STRING",
+                value: value.len() as i64,
+            });
+        }
+        if !value.is_ascii() {
+            return Err(Error::Other(format!("STRING isn't ascii: {:?}", value)));
+        }
+        Self::init_with_string(return_code, value, max_len)
+    }
+
     pub fn bytes_len(&self) -> u16 {
         self.data.len() as u16 + 4
     }
 
+    fn fill_byte_len(&self, is_last: bool) -> u16 {
+        if is_last {
+            0
+        } else {
+            self.data.len() as u16 % 2
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::Bit, [x]) => Ok(*x > 0),
+            _ => Err(self.type_mismatch("bool")),
+        }
+    }
+
+    pub fn as_i16(&self) -> Result<i16> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit | DataTransportSize::Integer, [x0, x1]) => {
+                Ok(i16::from_be_bytes([*x0, *x1]))
+            }
+            _ => Err(self.type_mismatch("i16")),
+        }
+    }
+
+    pub fn as_f32(&self) -> Result<f32> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit | DataTransportSize::Real, [x0, x1, x2, x3]) => {
+                Ok(f32::from_be_bytes([*x0, *x1, *x2, *x3]))
+            }
+            _ => Err(self.type_mismatch("f32")),
+        }
+    }
+
+    /// WORD, `None` if it isn't a 2-byte BYTE/WORD/DWORD item
+    pub fn as_word(&self) -> Option<u16> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit, [x0, x1]) => Some(u16::from_be_bytes([*x0, *x1])),
+            _ => None,
+        }
+    }
+
+    /// DWORD, `None` if it isn't a 4-byte BYTE/WORD/DWORD item
+    pub fn as_dword(&self) -> Option<u32> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit, [x0, x1, x2, x3]) => {
+                Some(u32::from_be_bytes([*x0, *x1, *x2, *x3]))
+            }
+            _ => None,
+        }
+    }
+
+    /// INT, `None` if it isn't a 2-byte integer item
+    pub fn as_int(&self) -> Option<i16> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::Integer, [x0, x1]) => Some(i16::from_be_bytes([*x0, *x1])),
+            _ => None,
+        }
+    }
+
+    /// DINT, `None` if it isn't a 4-byte integer item
+    pub fn as_dint(&self) -> Option<i32> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::Integer | DataTransportSize::DInteger, [x0, x1, x2, x3]) => {
+                Some(i32::from_be_bytes([*x0, *x1, *x2, *x3]))
+            }
+            _ => None,
+        }
+    }
+
+    /// REAL, `None` if it isn't a real item
+    pub fn as_real(&self) -> Option<f32> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::Real, [x0, x1, x2, x3]) => {
+                Some(f32::from_be_bytes([*x0, *x1, *x2, *x3]))
+            }
+            _ => None,
+        }
+    }
+
+    /// decode the BCD-encoded DATE_AND_TIME
+    pub fn as_date_time(&self) -> Result<DateTimeParts> {
+        match (self.transport_size_type, self.data.len()) {
+            (DataTransportSize::NoBit | DataTransportSize::OctetString, DATE_TIME_LEN) => {
+                DateTimeParts::decode(self.data.as_slice())
+            }
+            _ => Err(self.type_mismatch("date and time")),
+        }
+    }
+
+    /// DATE, the days since 1990-01-01
+    pub fn as_date(&self) -> Result<u16> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit | DataTransportSize::Integer, [x0, x1]) => {
+                check_date(u16::from_be_bytes([*x0, *x1]))
+            }
+            _ => Err(self.type_mismatch("date")),
+        }
+    }
+
+    /// TIME, signed milliseconds
+    pub fn as_time(&self) -> Result<i32> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (
+                DataTransportSize::NoBit | DataTransportSize::Integer | DataTransportSize::DInteger,
+                [x0, x1, x2, x3],
+            ) => Ok(i32::from_be_bytes([*x0, *x1, *x2, *x3])),
+            _ => Err(self.type_mismatch("time")),
+        }
+    }
+
+    /// S5TIME, see `decode_s5_time`, e.g. a timer
+    pub fn as_s5time(&self) -> Result<Duration> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit | DataTransportSize::OctetString, [x0, x1]) => {
+                decode_s5time([*x0, *x1])
+            }
+            _ => Err(self.type_mismatch("s5time")),
+        }
+    }
+
+    /// the BCD value of a counter, see `decode_counter`
+    pub fn as_counter(&self) -> Result<u16> {
+        match (self.transport_size_type, self.data.as_slice()) {
+            (DataTransportSize::NoBit | DataTransportSize::OctetString, [x0, x1]) => {
+                decode_counter([*x0, *x1])
+            }
+            _ => Err(self.type_mismatch("counter")),
+        }
+    }
+
+    /// STRING, the latin-1 chars of the current length
+    pub fn as_string(&self) -> Result<String> {
+        Ok(self
+            .string_bytes()?
+            .iter()
+            .map(|x| char::from(*x))
+            .collect())
+    }
+
+    /// STRING, the ascii chars of the current length, see `as_string`, but
+    /// it fails if the chars aren't ascii
+    pub fn as_s7_string(&self) -> Result<&str> {
+        let chars = self.string_bytes()?;
+        match std::str::from_utf8(chars) {
+            Ok(x) if x.is_ascii() => Ok(x),
+            _ => Err(self.type_mismatch("string")),
+        }
+    }
+
+    /// the chars of the current length of a STRING
+    fn string_bytes(&self) -> Result<&[u8]> {
+        let [max_len, len, chars @ ..] = self.data.as_slice() else {
+            return Err(self.type_mismatch("string"));
+        };
+        if len > max_len {
+            return Err(self.type_mismatch("string"));
+        }
+        string_chars(chars, *len as usize)
+    }
+
+    /// WSTRING: max length and current length in u16, then the chars in
+    /// UTF-16
+    pub fn as_wstring(&self) -> Result<String> {
+        let [max0, max1, len0, len1, chars @ ..] = self.data.as_slice() else {
+            return Err(self.type_mismatch("wstring"));
+        };
+        let max_len = u16::from_be_bytes([*max0, *max1]);
+        let len = u16::from_be_bytes([*len0, *len1]);
+        if len > max_len {
+            return Err(self.type_mismatch("wstring"));
+        }
+        let chars = string_chars(chars, len as usize * 2)?;
+        let chars: Vec<u16> = chars
+            .chunks_exact(2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]))
+            .collect();
+        String::from_utf16(&chars).map_err(|_| self.type_mismatch("wstring"))
+    }
+
+    fn type_mismatch(&self, ty: &'static str) -> Error {
+        Error::TypeMismatch {
+            expected: ty,
+            transport_size: self.transport_size_type.into(),
+            len: self.data.len(),
+        }
+    }
+
     fn encode(self, dst: &mut BytesMut) {
         dst.put_u8(self.return_code.into());
         dst.put_u8(self.transport_size_type.into());
@@ -581,30 +1282,29 @@ impl DataItemVal {
         dst.extend_from_slice(self.data.as_slice());
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Self> {
+    fn decode(src: &mut BytesMut, is_last: bool, lenient_padding: bool) -> Result<Self> {
         if src.len() < 4 {
-            return Err(Error::Other(format!(
-                "data item val byte's length is not enough: {}",
-                src.len()
-            )));
+            return Err(Error::InsufficientData {
+                needed: 4,
+                got: src.len(),
+            });
         }
 
-        let return_code = ReturnCode::try_from(src.get_u8())?;
+        let return_code = ReturnCode::from_u8_lossy(src.get_u8());
         let transport_size_type = DataTransportSize::from(src.get_u8());
         let length = src.get_u16();
         let mut bytes_len = length as usize;
 
-        if transport_size_type == DataTransportSize::NoBit {
+        if transport_size_type.is_length_in_bits() {
             bytes_len >>= 3;
         }
 
         let fill_byte_len = bytes_len % 2;
         if src.len() < bytes_len {
-            return Err(Error::Other(format!(
-                "data item val byte's length is not enough: {} < {}",
-                src.len(),
-                bytes_len
-            )));
+            return Err(Error::InsufficientData {
+                needed: bytes_len,
+                got: src.len(),
+            });
         }
 
         let mut data = Vec::with_capacity(bytes_len);
@@ -612,8 +1312,13 @@ impl DataItemVal {
             data.push(src.get_u8())
         }
 
-        if fill_byte_len > 0 && src.len() >= 1 {
-            src.get_u8();
+        if !is_last && fill_byte_len > 0 && !src.is_empty() {
+            // a firmware omitting the fill byte: the fill byte is always 0,
+            // any other byte is the return code of the next item
+            let omitted = lenient_padding && src[0] != 0x00;
+            if !omitted {
+                src.get_u8();
+            }
         }
 
         Ok(Self {
@@ -625,7 +1330,8 @@ impl DataItemVal {
     }
 }
 
-#[derive(Debug, Clone, IntoPrimitive, TryFromPrimitive, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
 #[repr(u8)]
 pub enum ReturnCode {
     /// 0
@@ -644,14 +1350,55 @@ pub enum ReturnCode {
     Err = 0x0a,
     /// Success
     Success = 0xff,
+    /// firmware-specific codes, they are not documented, the discriminant
+    /// is unused, the code is the field
+    #[num_enum(catch_all)]
+    Unknown(u8) = 0x0b,
 }
 
 impl ReturnCode {
     pub fn is_ok(&self) -> bool {
         *self == ReturnCode::Success
     }
+
+    pub fn is_success(&self) -> bool {
+        self.is_ok()
+    }
+
+    pub fn is_error(&self) -> bool {
+        !self.is_success()
+    }
+
+    /// not a firmware-specific code
+    pub fn is_known(&self) -> bool {
+        !matches!(self, ReturnCode::Unknown(_))
+    }
+
+    /// never fails, the unrecognized code is kept by `ReturnCode::Unknown`
+    pub fn from_u8_lossy(val: u8) -> Self {
+        Self::from(val)
+    }
 }
 
+impl Display for ReturnCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let desc = match self {
+            ReturnCode::Reserved => "Reserved",
+            ReturnCode::HwFault => "Hardware error",
+            ReturnCode::NotAllow => "Accessing the object not allowed",
+            ReturnCode::InvalidAddress => "Invalid address",
+            ReturnCode::NotSupported => "Data type not supported",
+            ReturnCode::SizeMismatch => "Data type inconsistent",
+            ReturnCode::Err => "Object does not exist",
+            ReturnCode::Success => "Success",
+            ReturnCode::Unknown(code) => return write!(f, "Unknown return code: {:#04x}", code),
+        };
+        write!(f, "{}", desc)
+    }
+}
+
+impl std::error::Error for ReturnCode {}
+
 #[derive(Debug, Copy, Clone, IntoPrimitive, Eq, FromPrimitive, PartialEq)]
 #[repr(u8)]
 pub enum TransportType {
@@ -664,25 +1411,44 @@ pub enum TransportType {
     NotSupport(u8),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Copy, Clone, IntoPrimitive, Eq, FromPrimitive, PartialEq)]
 #[repr(u8)]
 pub enum DataTransportSize {
     Bit = 0x03,
+    /// BYTE/WORD/DWORD
     NoBit = 0x04,
+    Integer = 0x05,
+    DInteger = 0x06,
+    Real = 0x07,
+    OctetString = 0x09,
     #[num_enum(catch_all)]
     NotSupport(u8),
 }
 
+impl DataTransportSize {
+    /// the length of the data item is in bits, otherwise in bytes, e.g.
+    /// DInteger, Real and OctetString
+    pub fn is_length_in_bits(&self) -> bool {
+        matches!(self, DataTransportSize::NoBit | DataTransportSize::Integer)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Copy, Clone, IntoPrimitive, Eq, FromPrimitive, PartialEq)]
 #[repr(u8)]
 pub enum TransportSize {
     Bit = 0x01,
     NoBit = 0x02,
+    Word = 0x04,
+    DWord = 0x06,
+    Counter = 0x1c,
     Timer = 0x1d,
     #[num_enum(catch_all)]
     NotSupport(u8),
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Area {
@@ -696,7 +1462,8 @@ pub enum Area {
     NotSupport(u8),
 }
 
-#[derive(Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
 #[repr(u8)]
 pub enum Syntax {
     S7Any = 0x10,
@@ -704,7 +1471,8 @@ pub enum Syntax {
     NotSupport(u8),
 }
 
-#[derive(Debug, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
 #[repr(u16)]
 pub enum DbNumber {
     NotIn = 0,
@@ -712,7 +1480,8 @@ pub enum DbNumber {
     DbNumber(u16),
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Address {
     byte_addr: u16,
     bit_addr: u8,
@@ -721,7 +1490,7 @@ pub struct Address {
 impl Address {
     pub fn to_bytes(&self, area: &Area) -> [u8; 3] {
         match area {
-            Area::Timer => {
+            Area::Timer | Area::Counter => {
                 let [byte_1, byte_2] = self.byte_addr.to_be_bytes();
                 [0, byte_1, byte_2]
             }
@@ -738,7 +1507,7 @@ impl Address {
 
     pub fn from_bytes(area: &Area, index_0: u8, index_1: u8, index_2: u8) -> Self {
         let (byte_addr, bit_addr) = match area {
-            Area::Timer => {
+            Area::Timer | Area::Counter => {
                 let byte_addr = u16::from_be_bytes([index_1, index_2]);
                 (byte_addr, 0)
             }
@@ -761,7 +1530,16 @@ impl Address {
 
 #[cfg(test)]
 mod test {
-    use super::{Address, Area};
+    use super::{Address, Area, DataItemVal, DataTransportSize, Error, ItemRequest, ReturnCode};
+    use crate::DATE_MAX_DAYS;
+    use bytes::BytesMut;
+    use std::time::Duration;
+
+    fn encode_item(item: ItemRequest) -> Vec<u8> {
+        let mut dst = BytesMut::new();
+        item.encode(&mut dst);
+        dst.to_vec()
+    }
 
     #[test]
     fn check_common_address() {
@@ -783,4 +1561,314 @@ mod test {
         assert_eq!(addr.byte_addr, 301);
         assert_eq!(addr.to_bytes(&Area::Timer), [0, 1, 0x2d]);
     }
+
+    #[test]
+    fn check_db_item_request() {
+        assert_eq!(
+            encode_item(ItemRequest::init_db_byte(1, 100, 4)),
+            [0x12, 0x0a, 0x10, 0x02, 0x00, 0x04, 0x00, 0x01, 0x84, 0x00, 0x03, 0x20]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_db_bit(1, 0, 6)),
+            [0x12, 0x0a, 0x10, 0x01, 0x00, 0x01, 0x00, 0x01, 0x84, 0x00, 0x00, 0x06]
+        );
+    }
+
+    #[test]
+    fn check_db_number_above_255() {
+        assert_eq!(
+            encode_item(ItemRequest::init_db_byte(300, 0, 4)),
+            [0x12, 0x0a, 0x10, 0x02, 0x00, 0x04, 0x01, 0x2c, 0x84, 0x00, 0x00, 0x00]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_db_bit(65535, 1, 2)),
+            [0x12, 0x0a, 0x10, 0x01, 0x00, 0x01, 0xff, 0xff, 0x84, 0x00, 0x00, 0x0a]
+        );
+
+        let mut src = BytesMut::from(encode_item(ItemRequest::init_db_byte(300, 0, 4)).as_slice());
+        let item = ItemRequest::decode(&mut src).unwrap();
+        assert_eq!(item.db_number(), 300);
+        assert_eq!(item, ItemRequest::init_db_byte(300, 0, 4));
+    }
+
+    #[test]
+    fn check_merker_item_request() {
+        assert_eq!(
+            encode_item(ItemRequest::init_merker_byte(10, 4)),
+            [0x12, 0x0a, 0x10, 0x02, 0x00, 0x04, 0x00, 0x00, 0x83, 0x00, 0x00, 0x50]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_merker_bit(10, 7)),
+            [0x12, 0x0a, 0x10, 0x01, 0x00, 0x01, 0x00, 0x00, 0x83, 0x00, 0x00, 0x57]
+        );
+    }
+
+    #[test]
+    fn check_input_output_item_request() {
+        assert_eq!(
+            encode_item(ItemRequest::init_input_byte(2, 1)),
+            [0x12, 0x0a, 0x10, 0x02, 0x00, 0x01, 0x00, 0x00, 0x81, 0x00, 0x00, 0x10]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_input_bit(1, 3)),
+            [0x12, 0x0a, 0x10, 0x01, 0x00, 0x01, 0x00, 0x00, 0x81, 0x00, 0x00, 0x0b]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_output_byte(2, 1)),
+            [0x12, 0x0a, 0x10, 0x02, 0x00, 0x01, 0x00, 0x00, 0x82, 0x00, 0x00, 0x10]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_output_bit(1, 3)),
+            [0x12, 0x0a, 0x10, 0x01, 0x00, 0x01, 0x00, 0x00, 0x82, 0x00, 0x00, 0x0b]
+        );
+    }
+
+    #[test]
+    fn check_timer_counter_item_request() {
+        assert_eq!(
+            encode_item(ItemRequest::init_timer(5, 1)),
+            [0x12, 0x0a, 0x10, 0x1d, 0x00, 0x01, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x05]
+        );
+        assert_eq!(
+            encode_item(ItemRequest::init_counter(301, 2)),
+            [0x12, 0x0a, 0x10, 0x1c, 0x00, 0x02, 0x00, 0x00, 0x1c, 0x00, 0x01, 0x2d]
+        );
+    }
+
+    fn round_trip(item: DataItemVal) -> DataItemVal {
+        let mut dst = BytesMut::new();
+        item.clone().encode(&mut dst);
+        let decoded = DataItemVal::decode(&mut dst, true, false).unwrap();
+        assert_eq!(decoded, item);
+        decoded
+    }
+
+    #[test]
+    fn check_typed_data_item_val() {
+        let item = round_trip(DataItemVal::init_with_word(ReturnCode::Reserved, 0x1234));
+        assert_eq!(item.transport_size_type, DataTransportSize::NoBit);
+        assert_eq!(item.length, 16);
+        assert_eq!(item.data, [0x12, 0x34]);
+        assert_eq!(u16::from_be_bytes([item.data[0], item.data[1]]), 0x1234);
+        assert_eq!(item.as_word(), Some(0x1234));
+        assert_eq!(item.as_int(), None);
+        assert_eq!(item.as_dword(), None);
+
+        let item = round_trip(DataItemVal::init_with_dword(
+            ReturnCode::Reserved,
+            0x12345678,
+        ));
+        assert_eq!(item.transport_size_type, DataTransportSize::NoBit);
+        assert_eq!(item.length, 32);
+        assert_eq!(item.data, [0x12, 0x34, 0x56, 0x78]);
+        assert_eq!(item.as_dword(), Some(0x12345678));
+        assert_eq!(item.as_dint(), None);
+        assert_eq!(item.as_real(), None);
+
+        let item = round_trip(DataItemVal::init_with_int(ReturnCode::Reserved, -2));
+        assert_eq!(item.transport_size_type, DataTransportSize::Integer);
+        assert_eq!(item.length, 16);
+        assert_eq!(item.data, [0xff, 0xfe]);
+        assert_eq!(i16::from_be_bytes([item.data[0], item.data[1]]), -2);
+        assert_eq!(item.as_int(), Some(-2));
+        assert_eq!(item.as_word(), None);
+
+        let item = round_trip(DataItemVal::init_with_dint(ReturnCode::Reserved, -100000));
+        assert_eq!(item.transport_size_type, DataTransportSize::Integer);
+        assert_eq!(item.length, 32);
+        assert_eq!(item.data, [0xff, 0xfe, 0x79, 0x60]);
+        assert_eq!(
+            i32::from_be_bytes([item.data[0], item.data[1], item.data[2], item.data[3]]),
+            -100000
+        );
+        assert_eq!(item.as_dint(), Some(-100000));
+        assert_eq!(item.as_dword(), None);
+
+        let item = round_trip(DataItemVal::init_with_real(ReturnCode::Reserved, 1.5));
+        assert_eq!(item.transport_size_type, DataTransportSize::Real);
+        assert_eq!(item.length, 4);
+        assert_eq!(item.data, [0x3f, 0xc0, 0x00, 0x00]);
+        assert_eq!(item.as_real(), Some(1.5));
+        assert_eq!(item.as_dint(), None);
+        assert!(matches!(
+            item.as_date_time(),
+            Err(Error::TypeMismatch {
+                expected: "date and time",
+                transport_size: 0x07,
+                len: 4
+            })
+        ));
+        assert_eq!(
+            f32::from_be_bytes([item.data[0], item.data[1], item.data[2], item.data[3]]),
+            1.5
+        );
+    }
+
+    #[test]
+    fn check_string_data_item_val() {
+        let item = round_trip(DataItemVal::init_with_string(ReturnCode::Success, "", 10).unwrap());
+        assert_eq!(item.data, [10, 0]);
+        assert_eq!(item.as_string().unwrap(), "");
+
+        // full, the chars beyond the max length are truncated
+        let item =
+            round_trip(DataItemVal::init_with_string(ReturnCode::Success, "s7-comm!", 7).unwrap());
+        assert_eq!(item.data, b"\x07\x07s7-comm");
+        assert_eq!(item.as_string().unwrap(), "s7-comm");
+
+        // latin-1
+        let item = DataItemVal::init_with_string(ReturnCode::Success, "a°€", 4).unwrap();
+        assert_eq!(item.data, [4, 3, b'a', 0xb0, b'?']);
+        assert_eq!(item.as_string().unwrap(), "a°?");
+
+        // 255 isn't a valid max length
+        assert!(matches!(
+            DataItemVal::init_with_string(ReturnCode::Success, "", 255),
+            Err(Error::OutOfRange { value: 255, .. })
+        ));
+
+        // the bytes beyond the current length are ignored
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[8, 2, b'o', b'k', 0, 0]);
+        assert_eq!(item.as_string().unwrap(), "ok");
+        // truncated buffer
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[8, 4, b'o', b'k']);
+        assert!(matches!(
+            item.as_string(),
+            Err(Error::InsufficientData { needed: 4, got: 2 })
+        ));
+        // the current length exceeds the max length
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[1, 2, b'o', b'k']);
+        assert!(matches!(item.as_string(), Err(Error::TypeMismatch { .. })));
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[1]);
+        assert!(matches!(item.as_string(), Err(Error::TypeMismatch { .. })));
+
+        let item = DataItemVal::init_with_bytes(
+            ReturnCode::Success,
+            &[0x00, 0x04, 0x00, 0x02, 0x00, 0x73, 0x6c, 0x34, 0x00, 0x00],
+        );
+        assert_eq!(item.as_wstring().unwrap(), "s\u{6c34}");
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[0x00, 0x04, 0x00, 0x00]);
+        assert_eq!(item.as_wstring().unwrap(), "");
+        let item = DataItemVal::init_with_bytes(
+            ReturnCode::Success,
+            &[0x00, 0x04, 0x00, 0x02, 0x00, 0x73],
+        );
+        assert!(matches!(
+            item.as_wstring(),
+            Err(Error::InsufficientData { needed: 4, got: 2 })
+        ));
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[0x00, 0x01, 0x00, 0x02]);
+        assert!(matches!(item.as_wstring(), Err(Error::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn check_return_code() {
+        assert_eq!(ReturnCode::from_u8_lossy(0xff), ReturnCode::Success);
+        assert_eq!(ReturnCode::from_u8_lossy(0x0a), ReturnCode::Err);
+        assert_eq!(ReturnCode::from_u8_lossy(0x21), ReturnCode::Unknown(0x21));
+        assert_eq!(u8::from(ReturnCode::Unknown(0x21)), 0x21);
+        assert!(ReturnCode::Unknown(0x21).is_error());
+        assert!(!ReturnCode::Success.is_error());
+        assert_eq!(
+            ReturnCode::Unknown(0x21).to_string(),
+            "Unknown return code: 0x21"
+        );
+
+        let mut src = BytesMut::from([0x21u8, 0x00, 0x00, 0x00].as_ref());
+        let item = DataItemVal::decode(&mut src, true, false).unwrap();
+        assert_eq!(item.return_code, ReturnCode::Unknown(0x21));
+    }
+
+    #[test]
+    fn check_s7_string_data_item_val() {
+        let item =
+            round_trip(DataItemVal::init_with_s7_string(ReturnCode::Success, "s7", 10).unwrap());
+        assert_eq!(item.data, [10, 2, b's', b'7']);
+        assert_eq!(item.as_s7_string().unwrap(), "s7");
+        let item = DataItemVal::init_with_s7_string(ReturnCode::Success, "", 254).unwrap();
+        assert_eq!(item.as_s7_string().unwrap(), "");
+        let item = DataItemVal::init_with_s7_string(ReturnCode::Success, "ok", 2).unwrap();
+        assert_eq!(item.as_s7_string().unwrap(), "ok");
+
+        assert!(matches!(
+            DataItemVal::init_with_s7_string(ReturnCode::Success, "", 255),
+            Err(Error::OutOfRange { value: 255, .. })
+        ));
+        assert!(matches!(
+            DataItemVal::init_with_s7_string(ReturnCode::Success, "s7-comm", 2),
+            Err(Error::OutOfRange {
+                ty: "STRING",
+                value: 7
+            })
+        ));
+        assert!(DataItemVal::init_with_s7_string(ReturnCode::Success, "a°", 10).is_err());
+
+        // the chars beyond the current length are ignored
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[4, 2, b'o', b'k', 0, 0]);
+        assert_eq!(item.as_s7_string().unwrap(), "ok");
+        // the current length exceeds the max length or the data
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[1, 2, b'o', b'k']);
+        assert!(item.as_s7_string().is_err());
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[4, 3, b'o', b'k']);
+        assert!(item.as_s7_string().is_err());
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[4, 1, 0xb0]);
+        assert!(item.as_s7_string().is_err());
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[4]);
+        assert!(item.as_s7_string().is_err());
+    }
+
+    #[test]
+    fn check_date_and_time_data_item_val() {
+        // 2024-01-01
+        let item = round_trip(DataItemVal::init_with_date(ReturnCode::Success, 12418).unwrap());
+        assert_eq!(item.data, [0x30, 0x82]);
+        assert_eq!(item.as_date().unwrap(), 12418);
+        assert!(matches!(
+            DataItemVal::init_with_date(ReturnCode::Success, DATE_MAX_DAYS + 1),
+            Err(Error::OutOfRange { ty: "DATE", .. })
+        ));
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[0xff, 0xff]);
+        assert!(matches!(item.as_date(), Err(Error::OutOfRange { .. })));
+
+        let item = round_trip(DataItemVal::init_with_time(ReturnCode::Success, -1500));
+        assert_eq!(item.data, [0xff, 0xff, 0xfa, 0x24]);
+        assert_eq!(item.as_time().unwrap(), -1500);
+
+        // 2min 5s needs the time base of 1s
+        let duration = Duration::from_secs(125);
+        let item =
+            round_trip(DataItemVal::init_with_s5time(ReturnCode::Success, duration).unwrap());
+        assert_eq!(item.data, [0x21, 0x25]);
+        assert_eq!(item.as_s5time().unwrap(), duration);
+        assert!(matches!(
+            DataItemVal::init_with_s5time(ReturnCode::Success, Duration::from_secs(9991)),
+            Err(Error::OutOfRange { ty: "S5TIME", .. })
+        ));
+        assert!(matches!(item.as_time(), Err(Error::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn check_timer_counter_data_item_val() {
+        // the response of a timer or a counter is an octet string
+        let mut src = BytesMut::from([0xff, 0x09, 0x00, 0x02, 0x12, 0x50].as_ref());
+        let item = DataItemVal::decode(&mut src, true, false).unwrap();
+        assert_eq!(item.as_s5time().unwrap(), Duration::from_millis(25_000));
+        assert_eq!(item.as_counter().unwrap(), 250);
+
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &[0x01]);
+        assert!(matches!(item.as_counter(), Err(Error::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn check_date_time_data_item_val() {
+        let bytes = [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35];
+        let item = round_trip(DataItemVal::init_with_bytes(ReturnCode::Success, &bytes));
+        let parts = item.as_date_time().unwrap();
+        assert_eq!((parts.year, parts.month, parts.day), (2023, 6, 15));
+        assert_eq!((parts.hour, parts.minute, parts.second), (13, 45, 30));
+        assert_eq!((parts.millisecond, parts.weekday), (123, 5));
+
+        let item = DataItemVal::init_with_bytes(ReturnCode::Success, &bytes[..7]);
+        assert!(item.as_date_time().is_err());
+    }
 }
@@ -0,0 +1,174 @@
+use crate::{error::*, packet::*};
+use bytes::{Buf, BufMut, BytesMut};
+
+/// the pi service of start and stop
+pub const PI_SERVICE_PROGRAM: &str = "P_PROGRAM";
+/// parameter block of cold restart
+const PARAMETER_BLOCK_COLD_RESTART: &[u8] = b"C ";
+
+/// PLC control (function 0x28): call a pi service, e.g. start the cpu
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlcControl {
+    unknown: [u8; 7],
+    pub parameter_block: Vec<u8>,
+    pub pi_service: Vec<u8>,
+}
+
+impl PlcControl {
+    pub fn init(parameter_block: Vec<u8>, pi_service: &str) -> Self {
+        Self {
+            unknown: [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfd],
+            parameter_block,
+            pi_service: pi_service.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        // function, unknown, length of parameter block, length of pi service
+        1 + 7 + 2 + self.parameter_block.len() as u16 + 1 + self.pi_service.len() as u16
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.extend_from_slice(self.unknown.as_slice());
+        dst.put_u16(self.parameter_block.len() as u16);
+        dst.extend_from_slice(self.parameter_block.as_slice());
+        dst.put_u8(self.pi_service.len() as u8);
+        dst.extend_from_slice(self.pi_service.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
+        if src.len() < 9 {
+            return Err(Error::InsufficientData {
+                needed: 9,
+                got: src.len(),
+            });
+        }
+        let mut unknown = [0u8; 7];
+        src.copy_to_slice(&mut unknown);
+        let length = src.get_u16() as usize;
+        let parameter_block = split_to(src, length)?;
+        let Some(length) = src.first().copied() else {
+            return Err(Error::InsufficientData { needed: 1, got: 0 });
+        };
+        src.advance(1);
+        let pi_service = split_to(src, length as usize)?;
+        Ok(Self {
+            unknown,
+            parameter_block,
+            pi_service,
+        })
+    }
+}
+
+/// PLC stop (function 0x29)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlcStop {
+    unknown: [u8; 5],
+    pub pi_service: Vec<u8>,
+}
+
+impl PlcStop {
+    pub fn init(pi_service: &str) -> Self {
+        Self {
+            unknown: [0x00; 5],
+            pi_service: pi_service.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn bytes_len_parameter(&self) -> u16 {
+        // function, unknown, length of pi service
+        1 + 5 + 1 + self.pi_service.len() as u16
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.extend_from_slice(self.unknown.as_slice());
+        dst.put_u8(self.pi_service.len() as u8);
+        dst.extend_from_slice(self.pi_service.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
+        if src.len() < 6 {
+            return Err(Error::InsufficientData {
+                needed: 6,
+                got: src.len(),
+            });
+        }
+        let mut unknown = [0u8; 5];
+        src.copy_to_slice(&mut unknown);
+        let length = src.get_u8() as usize;
+        let pi_service = split_to(src, length)?;
+        Ok(Self {
+            unknown,
+            pi_service,
+        })
+    }
+}
+
+/// ack of PLC control and PLC stop, also of request download, download
+/// ended and end upload, the result is in the error class and error code of
+/// the header
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PlcControlAck {
+    /// the parameter following the function
+    pub parameter: Vec<u8>,
+}
+
+impl PlcControlAck {
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.extend_from_slice(self.parameter.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut, length: usize) -> Result<Self> {
+        Ok(Self {
+            parameter: split_to(src, length)?,
+        })
+    }
+}
+
+pub(crate) fn split_to(src: &mut BytesMut, length: usize) -> Result<Vec<u8>> {
+    if src.len() < length {
+        return Err(Error::InsufficientData {
+            needed: length,
+            got: src.len(),
+        });
+    }
+    Ok(src.split_to(length).to_vec())
+}
+
+impl Frame {
+    /// Stop the cpu.
+    ///
+    /// DANGER: the jobs of plc control change the operating state of the cpu.
+    /// Stopping the cpu of a running process line halts the machine and sets
+    /// the outputs to their safe state, a restart runs the program from its
+    /// startup. Only use them in commissioning or when the plant is safe.
+    pub fn plc_stop(pdu_ref: u16) -> Frame {
+        let job = PlcStop::init(PI_SERVICE_PROGRAM);
+        Frame::Job {
+            header: Header::init(pdu_ref, job.bytes_len_parameter(), 0),
+            job: Job::PlcStop(job),
+        }
+    }
+
+    /// Start the cpu with a warm restart, the non-retentive data is reset.
+    /// See the danger of `plc_stop`.
+    pub fn plc_start(pdu_ref: u16) -> Frame {
+        Self::plc_control(pdu_ref, PlcControl::init(Vec::new(), PI_SERVICE_PROGRAM))
+    }
+
+    /// Start the cpu with a cold restart, all the data is reset to the
+    /// initial values of the load memory. See the danger of `plc_stop`.
+    pub fn plc_cold_start(pdu_ref: u16) -> Frame {
+        Self::plc_control(
+            pdu_ref,
+            PlcControl::init(PARAMETER_BLOCK_COLD_RESTART.to_vec(), PI_SERVICE_PROGRAM),
+        )
+    }
+
+    fn plc_control(pdu_ref: u16, job: PlcControl) -> Frame {
+        Frame::Job {
+            header: Header::init(pdu_ref, job.bytes_len_parameter(), 0),
+            job: Job::PlcControl(job),
+        }
+    }
+}
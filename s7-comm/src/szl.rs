@@ -0,0 +1,98 @@
+use crate::{error::*, packet::*, user_data::*};
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+/// subfunction of cpu functions: read szl
+pub const SUBFUNCTION_READ_SZL: u8 = 0x01;
+
+/// common szl ids
+#[derive(Debug, Copy, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[repr(u16)]
+pub enum SzlId {
+    /// module identification
+    ModuleIdent = 0x0011,
+    /// component identification, e.g. the module name and the serial number
+    ComponentIdent = 0x001c,
+    /// list of the blocks
+    BlockList = 0x0022,
+    /// status of the modules
+    ModuleStatus = 0x0091,
+    /// protection level of the cpu
+    ProtectionLevel = 0x0232,
+    /// operating state of the cpu
+    CpuState = 0x0424,
+    #[num_enum(catch_all)]
+    NotSupport(u16),
+}
+
+/// header of the szl data, followed by `item_count` records of `item_size`
+/// bytes
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SzlHeader {
+    pub szl_id: u16,
+    pub szl_index: u16,
+    pub item_size: u16,
+    pub item_count: u16,
+}
+
+/// data of the read szl response, the records are kept as raw bytes
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SzlResponse {
+    pub header: SzlHeader,
+    pub items: Vec<Vec<u8>>,
+}
+
+impl SzlResponse {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let [id0, id1, index0, index1, size0, size1, count0, count1, records @ ..] = data else {
+            return Err(Error::InsufficientData {
+                needed: 8,
+                got: data.len(),
+            });
+        };
+        let header = SzlHeader {
+            szl_id: u16::from_be_bytes([*id0, *id1]),
+            szl_index: u16::from_be_bytes([*index0, *index1]),
+            item_size: u16::from_be_bytes([*size0, *size1]),
+            item_count: u16::from_be_bytes([*count0, *count1]),
+        };
+        let needed = header.item_size as usize * header.item_count as usize;
+        if records.len() < needed {
+            return Err(Error::InsufficientData {
+                needed,
+                got: records.len(),
+            });
+        }
+        let items = if header.item_size == 0 {
+            Vec::new()
+        } else {
+            records[..needed]
+                .chunks(header.item_size as usize)
+                .map(|x| x.to_vec())
+                .collect()
+        };
+        Ok(Self { header, items })
+    }
+
+    /// the szl data of a read szl response frame
+    pub fn from_frame(frame: &Frame) -> Result<Self> {
+        Self::decode(
+            frame.user_data_response(UserDataFunctionGroup::CpuFunctions, SUBFUNCTION_READ_SZL)?,
+        )
+    }
+}
+
+impl Frame {
+    /// read the system status list, e.g. szl id 0x0011 for the module
+    /// identification
+    pub fn read_szl(pdu_ref: u16, szl_id: u16, index: u16) -> Frame {
+        let mut data = Vec::with_capacity(4);
+        data.extend_from_slice(szl_id.to_be_bytes().as_slice());
+        data.extend_from_slice(index.to_be_bytes().as_slice());
+        Frame::user_data_request(
+            pdu_ref,
+            UserDataFunctionGroup::CpuFunctions,
+            SUBFUNCTION_READ_SZL,
+            UserDataData::init_with_octet_string(data),
+        )
+    }
+}
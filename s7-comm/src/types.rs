@@ -0,0 +1,817 @@
+use crate::{date_time::*, error::*, szl::*};
+use num_enum::{FromPrimitive, IntoPrimitive};
+use std::time::{Duration, SystemTime};
+
+/// szl id of the cpu operating state, the state is byte 3 of the record
+pub const SZL_ID_CPU_STATE: u16 = 0x0424;
+
+/// operating state of the cpu
+#[derive(Debug, Copy, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[repr(u8)]
+pub enum CpuState {
+    #[num_enum(default)]
+    Unknown = 0x00,
+    Stop = 0x04,
+    Run = 0x08,
+    Halt = 0x10,
+}
+
+impl CpuState {
+    /// the state of the read szl response of `SZL_ID_CPU_STATE`
+    pub fn from_szl(szl: &SzlResponse) -> Result<Self> {
+        if szl.header.szl_id & 0x0fff != SZL_ID_CPU_STATE & 0x0fff {
+            return Err(Error::Other(format!(
+                "not the szl of cpu state: {:#06x}",
+                szl.header.szl_id
+            )));
+        }
+        match szl.items.first().and_then(|x| x.get(3)) {
+            Some(state) => Ok(Self::from(*state)),
+            None => Err(Error::InsufficientData { needed: 4, got: 0 }),
+        }
+    }
+}
+
+/// the index of the module record of the module identification
+const MODULE_IDENT_INDEX_MODULE: u16 = 0x0001;
+
+/// family of the cpu, classified by the order number of the module
+/// identification
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CpuFamily {
+    S7300,
+    S7400,
+    S71200,
+    S71500,
+    /// the order number
+    Unknown(String),
+}
+
+impl CpuFamily {
+    /// e.g. "6ES7 315-2EH14-0AB0" of S7-300, the ET 200S cpu "6ES7 151-8"
+    /// is classified as S7-300 and the ET 200SP cpu "6ES7 510" as S7-1500
+    pub fn from_order_number(order_number: &str) -> Self {
+        let order_number = order_number.trim();
+        let series = order_number
+            .strip_prefix("6ES7")
+            .map(|x| x.trim_start())
+            .and_then(|x| x.get(..3));
+        match series {
+            Some(x) if x.starts_with("31") || x == "151" => Self::S7300,
+            Some(x) if x.starts_with("41") => Self::S7400,
+            Some(x) if x.starts_with("21") => Self::S71200,
+            Some(x) if x.starts_with("51") => Self::S71500,
+            _ => Self::Unknown(order_number.to_string()),
+        }
+    }
+
+    /// the family of the read szl response of `SzlId::ModuleIdent`
+    pub fn from_szl(szl: &SzlResponse) -> Result<Self> {
+        let module_ident: u16 = SzlId::ModuleIdent.into();
+        if szl.header.szl_id & 0x00ff != module_ident {
+            return Err(Error::Other(format!(
+                "not the szl of module identification: {:#06x}",
+                szl.header.szl_id
+            )));
+        }
+        let record = szl
+            .items
+            .iter()
+            .find(|x| x.get(..2) == Some(MODULE_IDENT_INDEX_MODULE.to_be_bytes().as_slice()))
+            .or(szl.items.first());
+        match record.and_then(|x| x.get(2..22)) {
+            Some(order_number) => Ok(Self::from_order_number(&String::from_utf8_lossy(
+                order_number,
+            ))),
+            None => Err(Error::InsufficientData {
+                needed: 22,
+                got: record.map_or(0, |x| x.len()),
+            }),
+        }
+    }
+}
+
+/// the index of the records of the basic hardware and the basic firmware of
+/// the module identification, their version is in `Ausbg` and `Ausbe`
+const MODULE_IDENT_INDEX_HARDWARE: u16 = 0x0006;
+const MODULE_IDENT_INDEX_FIRMWARE: u16 = 0x0007;
+/// a record of the module identification: index, order number (`MlfB`),
+/// `BGTyp`, `Ausbg` and `Ausbe`
+const MODULE_IDENT_ORDER_NUMBER: std::ops::Range<usize> = 2..22;
+const MODULE_IDENT_VERSION: std::ops::Range<usize> = 24..28;
+/// the index of the records of the module name and the serial number of the
+/// component identification, a record is the index and 32 chars
+const COMPONENT_IDENT_INDEX_MODULE_NAME: u16 = 0x0002;
+const COMPONENT_IDENT_INDEX_SERIAL_NUMBER: u16 = 0x0005;
+const COMPONENT_IDENT_NAME: std::ops::Range<usize> = 2..34;
+
+/// identification of the cpu, e.g. for the inventory of the plcs
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CpuInfo {
+    /// e.g. "6ES7 315-2EH14-0AB0"
+    pub order_number: String,
+    /// e.g. "V3.3.0"
+    pub hardware_version: String,
+    pub firmware_version: String,
+    pub serial_number: String,
+    pub module_name: String,
+}
+
+impl CpuInfo {
+    /// the info of the read szl responses of `SzlId::ModuleIdent` and
+    /// `SzlId::ComponentIdent`, both of index 0x0000, all the records
+    pub fn from_szl(module_ident: &SzlResponse, component_ident: &SzlResponse) -> Result<Self> {
+        check_szl_id(module_ident, SzlId::ModuleIdent, "module identification")?;
+        check_szl_id(
+            component_ident,
+            SzlId::ComponentIdent,
+            "component identification",
+        )?;
+        Ok(Self {
+            order_number: ascii_field(szl_field(
+                module_ident,
+                MODULE_IDENT_INDEX_MODULE,
+                MODULE_IDENT_ORDER_NUMBER,
+            )?),
+            hardware_version: version_field(szl_field(
+                module_ident,
+                MODULE_IDENT_INDEX_HARDWARE,
+                MODULE_IDENT_VERSION,
+            )?),
+            firmware_version: version_field(szl_field(
+                module_ident,
+                MODULE_IDENT_INDEX_FIRMWARE,
+                MODULE_IDENT_VERSION,
+            )?),
+            serial_number: ascii_field(szl_field(
+                component_ident,
+                COMPONENT_IDENT_INDEX_SERIAL_NUMBER,
+                COMPONENT_IDENT_NAME,
+            )?),
+            module_name: ascii_field(szl_field(
+                component_ident,
+                COMPONENT_IDENT_INDEX_MODULE_NAME,
+                COMPONENT_IDENT_NAME,
+            )?),
+        })
+    }
+
+    /// e.g. "6ES7 315-2EH14-0AB0 V3.3.0 S C-X4U123452012"
+    pub fn display_string(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.order_number, self.firmware_version, self.serial_number
+        )
+    }
+}
+
+fn check_szl_id(szl: &SzlResponse, szl_id: SzlId, name: &str) -> Result<()> {
+    let szl_id: u16 = szl_id.into();
+    if szl.header.szl_id & 0x00ff != szl_id {
+        return Err(Error::Other(format!(
+            "not the szl of {}: {:#06x}",
+            name, szl.header.szl_id
+        )));
+    }
+    Ok(())
+}
+
+/// the field of the record of the index
+fn szl_field(szl: &SzlResponse, index: u16, field: std::ops::Range<usize>) -> Result<&[u8]> {
+    let record = szl
+        .items
+        .iter()
+        .find(|x| x.get(..2) == Some(index.to_be_bytes().as_slice()))
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "no record {:#06x} of szl {:#06x}",
+                index, szl.header.szl_id
+            ))
+        })?;
+    record.get(field.clone()).ok_or(Error::InsufficientData {
+        needed: field.end,
+        got: record.len(),
+    })
+}
+
+/// `Ausbg` is 'V' and the major version, `Ausbe` the minor version and the
+/// patch, e.g. `56 03 03 00` is "V3.3.0"
+fn version_field(field: &[u8]) -> String {
+    format!("V{}.{}.{}", field[1], field[2], field[3])
+}
+
+/// the ascii of the field without the padding spaces and nul bytes
+fn ascii_field(field: &[u8]) -> String {
+    String::from_utf8_lossy(field)
+        .trim_matches(|x: char| x == '\0' || x.is_ascii_whitespace())
+        .to_string()
+}
+
+/// length of a record of the module status
+const MODULE_STATUS_RECORD_LEN: usize = 16;
+/// the module fault bit of the io status of a record
+const MODULE_STATUS_FAULT: u16 = 0x0001;
+
+/// the rack and the slot of a module
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ModuleSlot {
+    pub rack: u8,
+    pub slot: u8,
+}
+
+/// which modules of the module status report a fault, for a quick health
+/// check of the io
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IoFaultSummary {
+    /// the count of the modules
+    pub modules: usize,
+    pub faulted: Vec<ModuleSlot>,
+}
+
+impl IoFaultSummary {
+    pub fn any_fault(&self) -> bool {
+        !self.faulted.is_empty()
+    }
+
+    /// The summary of the read szl response of `SzlId::ModuleStatus`. Of
+    /// every record the rack is the low byte of the first address, the slot
+    /// is the high byte of the second address, the io status is at 12..14.
+    pub fn from_szl(szl: &SzlResponse) -> Result<Self> {
+        let module_status: u16 = SzlId::ModuleStatus.into();
+        if szl.header.szl_id & 0x00ff != module_status {
+            return Err(Error::Other(format!(
+                "not the szl of module status: {:#06x}",
+                szl.header.szl_id
+            )));
+        }
+        let mut faulted = Vec::new();
+        for record in &szl.items {
+            if record.len() < MODULE_STATUS_RECORD_LEN {
+                return Err(Error::InsufficientData {
+                    needed: MODULE_STATUS_RECORD_LEN,
+                    got: record.len(),
+                });
+            }
+            let status = u16::from_be_bytes([record[12], record[13]]);
+            if status & MODULE_STATUS_FAULT > 0 {
+                faulted.push(ModuleSlot {
+                    rack: record[1],
+                    slot: record[2],
+                });
+            }
+        }
+        Ok(Self {
+            modules: szl.items.len(),
+            faulted,
+        })
+    }
+}
+
+/// A DATE_AND_TIME with the two-digit year of the plc, a thin wrapper of
+/// `DateTimeParts`, which holds the layout, the checks and the conversion
+/// from and to `SystemTime`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PlcDateTime {
+    /// 90..=99: 1990..=1999, 0..=89: 2000..=2089
+    pub year: u8,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+    /// 1: sunday, 2: monday .. 7: saturday
+    pub weekday: u8,
+}
+
+impl PlcDateTime {
+    /// fails like `DateTimeParts::encode` if a field is out of range, e.g. a
+    /// year above 99 or a month of 13
+    pub fn encode(&self) -> Result<[u8; DATE_TIME_LEN]> {
+        DateTimeParts::try_from(*self)?.encode()
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        Ok(DateTimeParts::decode(data)?.into())
+    }
+
+    /// the system time, e.g. to sync the clock of the plc
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    /// see `DateTimeParts::to_system_time`
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        DateTimeParts::try_from(*self).ok()?.to_system_time()
+    }
+
+    /// see `DateTimeParts::from_system_time`
+    pub fn from_system_time(t: SystemTime) -> Self {
+        DateTimeParts::from_system_time(t).into()
+    }
+}
+
+impl From<DateTimeParts> for PlcDateTime {
+    fn from(parts: DateTimeParts) -> Self {
+        Self {
+            year: (parts.year % 100) as u8,
+            month: parts.month,
+            day: parts.day,
+            hour: parts.hour,
+            minute: parts.minute,
+            second: parts.second,
+            millisecond: parts.millisecond,
+            weekday: parts.weekday,
+        }
+    }
+}
+
+impl TryFrom<PlcDateTime> for DateTimeParts {
+    type Error = Error;
+
+    /// fails for a year above 99, which would pass as a year of
+    /// 1990..=2089
+    fn try_from(date_time: PlcDateTime) -> Result<Self> {
+        if date_time.year > 99 {
+            return Err(Error::Other(format!(
+                "invalid date and time: {:?}",
+                date_time
+            )));
+        }
+        let year = date_time.year as u16;
+        Ok(Self {
+            year: if year >= 90 { 1900 + year } else { 2000 + year },
+            month: date_time.month,
+            day: date_time.day,
+            hour: date_time.hour,
+            minute: date_time.minute,
+            second: date_time.second,
+            millisecond: date_time.millisecond,
+            weekday: date_time.weekday,
+        })
+    }
+}
+
+/// the bit 0..=7 of the byte at `byte_offset`, e.g. of the bytes of a db read,
+/// `None` if the offset or the bit is out of range
+pub fn get_bit(bytes: &[u8], byte_offset: usize, bit: u8) -> Option<bool> {
+    if bit > 7 {
+        return None;
+    }
+    bytes.get(byte_offset).map(|x| x >> bit & 1 > 0)
+}
+
+/// set the bit 0..=7 of the byte at `byte_offset`, e.g. before writing the
+/// bytes back, the bytes are left unchanged if the offset or the bit is out
+/// of range
+pub fn set_bit(bytes: &mut [u8], byte_offset: usize, bit: u8, value: bool) -> Result<()> {
+    if bit > 7 {
+        return Err(Error::OutOfRange {
+            ty: "bit",
+            value: bit as i64,
+        });
+    }
+    let len = bytes.len();
+    let byte = bytes.get_mut(byte_offset).ok_or(Error::InsufficientData {
+        needed: byte_offset + 1,
+        got: len,
+    })?;
+    if value {
+        *byte |= 1 << bit;
+    } else {
+        *byte &= !(1 << bit);
+    }
+    Ok(())
+}
+
+/// A S5TIME word: the time base in the bits 12 and 13 and the value in 3 BCD
+/// digits, e.g. `0x2125` is 125 of the time base of 1s. Unlike
+/// `encode_s5_time`, which truncates, a duration is only taken if a time base
+/// holds it exactly.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct S5Time(u16);
+
+impl S5Time {
+    /// the duration in the smallest time base which holds it, fails if it
+    /// exceeds `S5TIME_MAX` or isn't a multiple of that time base
+    pub fn from_duration(duration: Duration) -> Result<Self> {
+        let [high, low] = encode_s5time(duration)?;
+        let s5_time = Self(u16::from_be_bytes([high, low]));
+        if s5_time.to_duration() != duration {
+            return Err(Error::Other(format!(
+                "{:?} is not a multiple of the time base {}ms of S5TIME",
+                duration,
+                s5_time.time_base()
+            )));
+        }
+        Ok(s5_time)
+    }
+
+    pub fn to_duration(&self) -> Duration {
+        Duration::from_millis(self.bcd_value() as u64 * self.time_base() as u64)
+    }
+
+    /// the time base in milliseconds: 10, 100, 1000 or 10000
+    pub fn time_base(&self) -> u16 {
+        S5TIME_BASES[(self.0 >> 12 & 0x03) as usize] as u16
+    }
+
+    /// the value of the 3 BCD digits, 0..=999
+    pub fn bcd_value(&self) -> u16 {
+        (self.0 >> 8 & 0x0f) * 100 + (self.0 >> 4 & 0x0f) * 10 + (self.0 & 0x0f)
+    }
+}
+
+impl TryFrom<u16> for S5Time {
+    type Error = Error;
+
+    /// the bits 14 and 15 are ignored
+    fn try_from(word: u16) -> Result<Self> {
+        decode_s5time(word.to_be_bytes())?;
+        Ok(Self(word & 0x3fff))
+    }
+}
+
+impl From<S5Time> for u16 {
+    fn from(s5_time: S5Time) -> Self {
+        s5_time.0
+    }
+}
+
+/// decode the S5TIME word, e.g. of a timer read, the bits 14 and 15 are
+/// ignored
+pub fn decode_s5_time(raw: u16) -> Result<Duration> {
+    decode_s5time(raw.to_be_bytes())
+}
+
+/// encode the duration as S5TIME word, the smallest time base which holds
+/// the duration is taken and the duration is truncated to its resolution,
+/// e.g. 12.345s is 123 of the time base of 100ms
+pub fn encode_s5_time(d: Duration) -> Result<u16> {
+    Ok(u16::from_be_bytes(encode_s5time(d)?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        decode_s5_time, encode_s5_time, get_bit, set_bit, CpuFamily, CpuInfo, CpuState,
+        IoFaultSummary, ModuleSlot, PlcDateTime, S5Time,
+    };
+    use crate::{Error, SzlHeader, SzlResponse};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn check_cpu_state() {
+        let mut szl = SzlResponse {
+            header: SzlHeader {
+                szl_id: 0x0424,
+                szl_index: 0,
+                item_size: 20,
+                item_count: 1,
+            },
+            items: vec![vec![0; 20]],
+        };
+        szl.items[0][..4].copy_from_slice(&[0x51, 0x44, 0xff, 0x08]);
+        assert_eq!(CpuState::from_szl(&szl).unwrap(), CpuState::Run);
+        szl.items[0][3] = 0x04;
+        assert_eq!(CpuState::from_szl(&szl).unwrap(), CpuState::Stop);
+        szl.items[0][3] = 0x03;
+        assert_eq!(CpuState::from_szl(&szl).unwrap(), CpuState::Unknown);
+
+        szl.items.clear();
+        assert!(CpuState::from_szl(&szl).is_err());
+        szl.header.szl_id = 0x0011;
+        assert!(CpuState::from_szl(&szl).is_err());
+    }
+
+    /// the szl of a record list, the records are padded with spaces
+    fn szl_of(szl_id: u16, item_size: usize, records: &[(u16, &[u8])]) -> SzlResponse {
+        let mut bytes = szl_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&[0x00, 0x00]);
+        bytes.extend_from_slice(&(item_size as u16).to_be_bytes());
+        bytes.extend_from_slice(&(records.len() as u16).to_be_bytes());
+        for (index, data) in records {
+            let mut record = index.to_be_bytes().to_vec();
+            record.extend_from_slice(data);
+            record.resize(item_size, b' ');
+            bytes.extend_from_slice(&record);
+        }
+        SzlResponse::decode(&bytes).unwrap()
+    }
+
+    #[test]
+    fn check_cpu_info() {
+        // module identification of a CPU 315-2 PN/DP
+        let module_ident = szl_of(
+            0x0011,
+            28,
+            &[
+                (0x0001, b"6ES7 315-2EH14-0AB0 \x00\xc0\x00\x04\x00\x01"),
+                (0x0006, b"6ES7 315-2EH14-0AB0 \x00\xc0\x56\x04\x00\x00"),
+                (0x0007, b"                    \x00\xc0\x56\x03\x03\x00"),
+            ],
+        );
+        let component_ident = szl_of(
+            0x001c,
+            34,
+            &[
+                (0x0001, b"SIMATIC 300(1)"),
+                (0x0002, b"CPU 315-2 PN/DP"),
+                (0x0005, b"S C-X4U123452012"),
+            ],
+        );
+        let info = CpuInfo::from_szl(&module_ident, &component_ident).unwrap();
+        assert_eq!(
+            info,
+            CpuInfo {
+                order_number: "6ES7 315-2EH14-0AB0".to_string(),
+                hardware_version: "V4.0.0".to_string(),
+                firmware_version: "V3.3.0".to_string(),
+                serial_number: "S C-X4U123452012".to_string(),
+                module_name: "CPU 315-2 PN/DP".to_string(),
+            }
+        );
+        assert_eq!(
+            info.display_string(),
+            "6ES7 315-2EH14-0AB0 V3.3.0 S C-X4U123452012"
+        );
+
+        // no firmware record
+        let mut szl = module_ident.clone();
+        szl.items.truncate(2);
+        assert!(CpuInfo::from_szl(&szl, &component_ident).is_err());
+        // a short record
+        let mut szl = component_ident.clone();
+        szl.items[2].truncate(20);
+        assert!(matches!(
+            CpuInfo::from_szl(&module_ident, &szl),
+            Err(Error::InsufficientData {
+                needed: 34,
+                got: 20
+            })
+        ));
+        assert!(CpuInfo::from_szl(&component_ident, &module_ident).is_err());
+    }
+
+    #[test]
+    fn check_get_and_set_bit() {
+        let mut bytes = [0u8; 2];
+        for bit in 0..=7 {
+            set_bit(&mut bytes, 1, bit, true).unwrap();
+            assert_eq!(bytes, [0, 1 << bit], "bit {}", bit);
+            assert_eq!(get_bit(&bytes, 1, bit), Some(true));
+            assert_eq!(get_bit(&bytes, 0, bit), Some(false));
+            for other in (0..=7).filter(|x| *x != bit) {
+                assert_eq!(get_bit(&bytes, 1, other), Some(false));
+            }
+            set_bit(&mut bytes, 1, bit, false).unwrap();
+            assert_eq!(bytes, [0, 0]);
+        }
+
+        // the other bits are kept
+        let mut bytes = [0b1010_0101];
+        set_bit(&mut bytes, 0, 1, true).unwrap();
+        set_bit(&mut bytes, 0, 7, false).unwrap();
+        assert_eq!(bytes, [0b0010_0111]);
+
+        assert_eq!(get_bit(&bytes, 1, 0), None);
+        assert_eq!(get_bit(&bytes, 0, 8), None);
+        assert_eq!(get_bit(&[], 0, 0), None);
+        assert!(matches!(
+            set_bit(&mut bytes, 1, 0, true),
+            Err(Error::InsufficientData { needed: 2, got: 1 })
+        ));
+        assert!(matches!(
+            set_bit(&mut bytes, 0, 8, true),
+            Err(Error::OutOfRange {
+                ty: "bit",
+                value: 8
+            })
+        ));
+        assert_eq!(bytes, [0b0010_0111]);
+    }
+
+    #[test]
+    fn check_s5_time() {
+        let cases = [
+            // the bounds of every time base
+            (0, 0x0000, 10, 0),
+            (10, 0x0001, 10, 1),
+            (9_990, 0x0999, 10, 999),
+            (10_000, 0x1100, 100, 100),
+            (99_900, 0x1999, 100, 999),
+            (100_000, 0x2100, 1_000, 100),
+            (999_000, 0x2999, 1_000, 999),
+            (1_000_000, 0x3100, 10_000, 100),
+            (9_990_000, 0x3999, 10_000, 999),
+            // the smallest time base which holds it
+            (1_230, 0x0123, 10, 123),
+            (12_300, 0x1123, 100, 123),
+            (125_000, 0x2125, 1_000, 125),
+        ];
+        for (ms, word, time_base, bcd_value) in cases {
+            let duration = Duration::from_millis(ms);
+            let s5_time = S5Time::from_duration(duration).unwrap();
+            assert_eq!(u16::from(s5_time), word, "{}ms", ms);
+            assert_eq!(s5_time.time_base(), time_base, "{}ms", ms);
+            assert_eq!(s5_time.bcd_value(), bcd_value, "{}ms", ms);
+            assert_eq!(s5_time.to_duration(), duration);
+            assert_eq!(S5Time::try_from(word).unwrap(), s5_time);
+            assert_eq!(encode_s5_time(duration).unwrap(), word);
+            assert_eq!(decode_s5_time(word).unwrap(), duration);
+        }
+        // the words truncate unlike `S5Time`
+        assert_eq!(
+            encode_s5_time(Duration::from_millis(12_345)).unwrap(),
+            0x1123
+        );
+        assert!(encode_s5_time(Duration::from_millis(9_990_001)).is_err());
+        assert!(decode_s5_time(0x00a0).is_err());
+
+        // beyond 9990s
+        assert!(matches!(
+            S5Time::from_duration(Duration::from_millis(9_990_001)),
+            Err(Error::OutOfRange { ty: "S5TIME", .. })
+        ));
+        assert!(S5Time::from_duration(Duration::from_secs(10_000)).is_err());
+        // too granular for the time base
+        for ms in [5, 9_995, 10_010, 100_100, 1_001_000] {
+            assert!(
+                S5Time::from_duration(Duration::from_millis(ms)).is_err(),
+                "{}ms",
+                ms
+            );
+        }
+        assert!(S5Time::from_duration(Duration::from_micros(10_001)).is_err());
+
+        // the bits 14 and 15 are ignored, the digits must be bcd
+        assert_eq!(
+            S5Time::try_from(0xc001).unwrap().to_duration(),
+            Duration::from_millis(10)
+        );
+        assert!(S5Time::try_from(0x000a).is_err());
+        assert!(S5Time::try_from(0x00a0).is_err());
+        assert!(S5Time::try_from(0x0a00).is_err());
+    }
+
+    #[test]
+    fn check_io_fault_summary() {
+        // the cpu, a faulted module of slot 4 and a module of slot 5
+        let bytes = [
+            0x00, 0x91, 0x00, 0x00, 0x00, 0x10, 0x00, 0x03, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x80, 0x02, 0xc0, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00,
+        ];
+        let szl = SzlResponse::decode(&bytes).unwrap();
+        let summary = IoFaultSummary::from_szl(&szl).unwrap();
+        assert_eq!(summary.modules, 3);
+        assert!(summary.any_fault());
+        assert_eq!(summary.faulted, vec![ModuleSlot { rack: 0, slot: 4 }]);
+
+        let mut szl = szl;
+        szl.items.remove(1);
+        assert!(!IoFaultSummary::from_szl(&szl).unwrap().any_fault());
+        szl.header.szl_id = 0x0011;
+        assert!(IoFaultSummary::from_szl(&szl).is_err());
+    }
+
+    #[test]
+    fn check_cpu_family() {
+        for (order_number, family) in [
+            ("6ES7 315-2EH14-0AB0 ", CpuFamily::S7300),
+            ("6ES7 314-6EH04-0AB0", CpuFamily::S7300),
+            ("6ES7 151-8AB01-0AB0", CpuFamily::S7300),
+            ("6ES7 416-3XR05-0AB0", CpuFamily::S7400),
+            ("6ES7 214-1AG40-0XB0", CpuFamily::S71200),
+            ("6ES7 511-1AK02-0AB0", CpuFamily::S71500),
+            ("6ES7 510-1DJ01-0AB0", CpuFamily::S71500),
+            ("6ES7512-1CK01-0AB0", CpuFamily::S71500),
+        ] {
+            assert_eq!(CpuFamily::from_order_number(order_number), family);
+        }
+        assert_eq!(
+            CpuFamily::from_order_number("6ES7 288-1SR20-0AA0 "),
+            CpuFamily::Unknown("6ES7 288-1SR20-0AA0".to_string())
+        );
+        assert_eq!(
+            CpuFamily::from_order_number(""),
+            CpuFamily::Unknown(String::new())
+        );
+
+        let mut record = vec![0x00, 0x01];
+        record.extend_from_slice(b"6ES7 315-2EH14-0AB0 ");
+        record.extend_from_slice(&[0x00, 0x00, 0x00, 0x04, 0x00, 0x01]);
+        let szl = SzlResponse {
+            header: SzlHeader {
+                szl_id: 0x0011,
+                szl_index: 0,
+                item_size: 28,
+                item_count: 1,
+            },
+            items: vec![record],
+        };
+        assert_eq!(CpuFamily::from_szl(&szl).unwrap(), CpuFamily::S7300);
+        assert!(CpuFamily::from_szl(&SzlResponse {
+            items: Vec::new(),
+            ..szl.clone()
+        })
+        .is_err());
+        assert!(CpuFamily::from_szl(&SzlResponse {
+            header: SzlHeader {
+                szl_id: 0x0424,
+                ..szl.header
+            },
+            ..szl
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn check_plc_date_time() {
+        let bytes = [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35];
+        let date_time = PlcDateTime::decode(&bytes).unwrap();
+        assert_eq!(
+            date_time,
+            PlcDateTime {
+                year: 23,
+                month: 6,
+                day: 15,
+                hour: 13,
+                minute: 45,
+                second: 30,
+                millisecond: 123,
+                weekday: 5,
+            }
+        );
+        assert_eq!(date_time.encode().unwrap(), bytes);
+
+        // not bcd
+        assert!(PlcDateTime::decode(&[0x23, 0x0a, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35]).is_err());
+        assert!(PlcDateTime::decode(&bytes[..7]).is_err());
+
+        // not encoded to non-bcd bytes
+        assert!(PlcDateTime {
+            year: 150,
+            ..date_time
+        }
+        .encode()
+        .is_err());
+        assert!(PlcDateTime {
+            month: 13,
+            ..date_time
+        }
+        .encode()
+        .is_err());
+    }
+
+    #[test]
+    fn check_plc_date_time_system_time() {
+        // 2023-06-15 13:45:30.123
+        let time = UNIX_EPOCH + Duration::from_millis(1_686_836_730_123);
+        let date_time = PlcDateTime::from_system_time(time);
+        assert_eq!(
+            date_time.encode().unwrap(),
+            [0x23, 0x06, 0x15, 0x13, 0x45, 0x30, 0x12, 0x35]
+        );
+        assert_eq!(date_time.to_system_time(), Some(time));
+
+        // 1999-12-31 23:59:59.999, a friday
+        let time = UNIX_EPOCH + Duration::from_millis(946_684_799_999);
+        let date_time = PlcDateTime::from_system_time(time);
+        assert_eq!(
+            date_time.encode().unwrap(),
+            [0x99, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x96]
+        );
+        assert_eq!(date_time.to_system_time(), Some(time));
+
+        // 2024-02-29 is a thursday
+        let date_time =
+            PlcDateTime::from_system_time(UNIX_EPOCH + Duration::from_secs(1_709_164_800));
+        assert_eq!(
+            (date_time.year, date_time.month, date_time.day),
+            (24, 2, 29)
+        );
+        assert_eq!(date_time.weekday, 5);
+        assert!(PlcDateTime {
+            year: 23,
+            ..date_time
+        }
+        .to_system_time()
+        .is_none());
+
+        // saturated to 1990..=2089
+        let date_time = PlcDateTime::from_system_time(UNIX_EPOCH);
+        assert_eq!(
+            date_time.encode().unwrap(),
+            [0x90, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02]
+        );
+        let date_time = PlcDateTime::from_system_time(
+            UNIX_EPOCH + Duration::from_secs(100 * 365 * 86400 + 50 * 365 * 86400),
+        );
+        assert_eq!(
+            date_time.encode().unwrap(),
+            [0x89, 0x12, 0x31, 0x23, 0x59, 0x59, 0x99, 0x97]
+        );
+    }
+}
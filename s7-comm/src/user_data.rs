@@ -0,0 +1,304 @@
+use crate::{error::*, packet::*};
+use bytes::{Buf, BufMut, BytesMut};
+use num_enum::{FromPrimitive, IntoPrimitive};
+
+/// head of the user data parameter
+const USER_DATA_PARAMETER_HEAD: [u8; 3] = [0x00, 0x01, 0x12];
+/// method of request
+pub const USER_DATA_METHOD_REQUEST: u8 = 0x11;
+/// method of response and of continued request
+pub const USER_DATA_METHOD_RESPONSE: u8 = 0x12;
+/// high nibble of type_and_function of request
+pub const USER_DATA_TYPE_REQUEST: u8 = 0x40;
+/// high nibble of type_and_function of response
+pub const USER_DATA_TYPE_RESPONSE: u8 = 0x80;
+
+#[derive(Debug, Copy, Clone, IntoPrimitive, FromPrimitive, Eq, PartialEq)]
+#[repr(u8)]
+pub enum UserDataFunctionGroup {
+    ProgrammerCommands = 0x01,
+    CyclicData = 0x02,
+    BlockFunctions = 0x03,
+    CpuFunctions = 0x04,
+    Security = 0x05,
+    TimeFunctions = 0x07,
+    #[num_enum(catch_all)]
+    NotSupport(u8),
+}
+
+/// parameter of user data (rosctr 0x07)
+///
+/// the parameter of request is 8 bytes, the parameter of response and of
+/// continued request carries 4 more bytes: data unit reference, last data
+/// unit and error code
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UserDataParameter {
+    /// 0x11: request, 0x12: response
+    pub method: u8,
+    /// high nibble: 0x4 request, 0x8 response; low nibble: function group
+    pub type_and_function: u8,
+    pub subfunction: u8,
+    pub sequence_number: u8,
+    pub data_unit_reference: u8,
+    pub last_data_unit: bool,
+    pub error_code: u16,
+}
+
+impl UserDataParameter {
+    pub fn init_request(function_group: UserDataFunctionGroup, subfunction: u8) -> Self {
+        let function_group: u8 = function_group.into();
+        Self {
+            method: USER_DATA_METHOD_REQUEST,
+            type_and_function: USER_DATA_TYPE_REQUEST | function_group & 0x0f,
+            subfunction,
+            sequence_number: 0,
+            data_unit_reference: 0,
+            last_data_unit: true,
+            error_code: 0,
+        }
+    }
+
+    /// The request of the next data unit of a response whose
+    /// `last_data_unit` is false, it carries the sequence number of the
+    /// response.
+    pub fn init_continuation(response: &UserDataParameter) -> Self {
+        let function_group = response.type_and_function & 0x0f;
+        Self {
+            method: USER_DATA_METHOD_RESPONSE,
+            type_and_function: USER_DATA_TYPE_REQUEST | function_group,
+            subfunction: response.subfunction,
+            sequence_number: response.sequence_number,
+            data_unit_reference: 0,
+            last_data_unit: true,
+            error_code: 0,
+        }
+    }
+
+    pub fn sequence_number(mut self, sequence_number: u8) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    pub fn function_group(&self) -> UserDataFunctionGroup {
+        UserDataFunctionGroup::from(self.type_and_function & 0x0f)
+    }
+
+    pub fn is_response(&self) -> bool {
+        self.type_and_function & 0xf0 == USER_DATA_TYPE_RESPONSE
+    }
+
+    /// `Err(Error::NakResponse)` if the plc rejected the request
+    pub fn check(&self) -> Result<()> {
+        if self.error_code == 0 {
+            Ok(())
+        } else {
+            let [error_class, error_code] = self.error_code.to_be_bytes();
+            Err(Error::NakResponse {
+                error_class,
+                error_code,
+            })
+        }
+    }
+
+    fn has_response_fields(&self) -> bool {
+        self.method == USER_DATA_METHOD_RESPONSE
+    }
+
+    pub fn bytes_len(&self) -> u16 {
+        if self.has_response_fields() {
+            12
+        } else {
+            8
+        }
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        dst.extend_from_slice(USER_DATA_PARAMETER_HEAD.as_slice());
+        dst.put_u8(self.bytes_len() as u8 - 4);
+        dst.put_u8(self.method);
+        dst.put_u8(self.type_and_function);
+        dst.put_u8(self.subfunction);
+        dst.put_u8(self.sequence_number);
+        if self.has_response_fields() {
+            dst.put_u8(self.data_unit_reference);
+            dst.put_u8(if self.last_data_unit { 0x00 } else { 0x01 });
+            dst.put_u16(self.error_code);
+        }
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut, parameter_len: u16) -> Result<Self> {
+        if src.len() < 8 {
+            return Err(Error::InsufficientData {
+                needed: 8,
+                got: src.len(),
+            });
+        }
+        src.advance(USER_DATA_PARAMETER_HEAD.len());
+        let length = src.get_u8() as usize;
+        if length < 4 {
+            return Err(Error::InsufficientData {
+                needed: 4,
+                got: length,
+            });
+        }
+        if parameter_len as usize != length + 4 {
+            return Err(Error::Other(format!(
+                "user data parameter length {} doesn't match the header's parameter length {}",
+                length + 4,
+                parameter_len
+            )));
+        }
+        if src.len() < length {
+            return Err(Error::InsufficientData {
+                needed: length,
+                got: src.len(),
+            });
+        }
+        let mut src = src.split_to(length);
+        let method = src.get_u8();
+        let type_and_function = src.get_u8();
+        let subfunction = src.get_u8();
+        let sequence_number = src.get_u8();
+        let (data_unit_reference, last_data_unit, error_code) = if src.len() >= 4 {
+            (src.get_u8(), src.get_u8() == 0x00, src.get_u16())
+        } else {
+            (0, true, 0)
+        };
+        Ok(Self {
+            method,
+            type_and_function,
+            subfunction,
+            sequence_number,
+            data_unit_reference,
+            last_data_unit,
+            error_code,
+        })
+    }
+}
+
+/// data of user data
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UserDataData {
+    pub return_code: ReturnCode,
+    pub transport_size: DataTransportSize,
+    pub data: Vec<u8>,
+}
+
+impl UserDataData {
+    /// the data of a request which carries no data
+    pub fn init_empty() -> Self {
+        Self {
+            return_code: ReturnCode::Err,
+            transport_size: DataTransportSize::NotSupport(0),
+            data: Vec::new(),
+        }
+    }
+
+    pub fn init_with_octet_string(data: Vec<u8>) -> Self {
+        Self {
+            return_code: ReturnCode::Success,
+            transport_size: DataTransportSize::OctetString,
+            data,
+        }
+    }
+
+    pub fn bytes_len(&self) -> u16 {
+        self.data.len() as u16 + 4
+    }
+
+    pub(crate) fn encode(self, dst: &mut BytesMut) {
+        let length = if self.transport_size.is_length_in_bits() {
+            (self.data.len() as u16) << 3
+        } else {
+            self.data.len() as u16
+        };
+        dst.put_u8(self.return_code.into());
+        dst.put_u8(self.transport_size.into());
+        dst.put_u16(length);
+        dst.extend_from_slice(self.data.as_slice());
+    }
+
+    pub(crate) fn decode(src: &mut BytesMut) -> Result<Self> {
+        if src.len() < 4 {
+            return Err(Error::InsufficientData {
+                needed: 4,
+                got: src.len(),
+            });
+        }
+        let return_code = ReturnCode::from_u8_lossy(src.get_u8());
+        let transport_size = DataTransportSize::from(src.get_u8());
+        let mut length = src.get_u16() as usize;
+        if transport_size.is_length_in_bits() {
+            length >>= 3;
+        }
+        if src.len() < length {
+            return Err(Error::InsufficientData {
+                needed: length,
+                got: src.len(),
+            });
+        }
+        Ok(Self {
+            return_code,
+            transport_size,
+            data: src.split_to(length).to_vec(),
+        })
+    }
+}
+
+impl Frame {
+    /// a user data request of the function group and subfunction
+    pub fn user_data_request(
+        pdu_ref: u16,
+        function_group: UserDataFunctionGroup,
+        subfunction: u8,
+        data: UserDataData,
+    ) -> Frame {
+        let parameter = UserDataParameter::init_request(function_group, subfunction);
+        Frame::UserData {
+            header: Header::init(pdu_ref, parameter.bytes_len(), data.bytes_len()),
+            parameter,
+            data,
+        }
+    }
+
+    /// the request of the next data unit of the user data response
+    /// `response`, see `UserDataParameter::init_continuation`
+    pub fn user_data_continuation(pdu_ref: u16, response: &UserDataParameter) -> Frame {
+        let parameter = UserDataParameter::init_continuation(response);
+        let data = UserDataData::init_empty();
+        Frame::UserData {
+            header: Header::init(pdu_ref, parameter.bytes_len(), data.bytes_len()),
+            parameter,
+            data,
+        }
+    }
+
+    /// the data of a successful user data response of the function group and
+    /// subfunction
+    pub fn user_data_response(
+        &self,
+        function_group: UserDataFunctionGroup,
+        subfunction: u8,
+    ) -> Result<&[u8]> {
+        let Frame::UserData {
+            parameter, data, ..
+        } = self
+        else {
+            return Err(Error::Other(format!("not a user data frame: {:?}", self)));
+        };
+        if parameter.function_group() != function_group || parameter.subfunction != subfunction {
+            return Err(Error::Other(format!(
+                "not a response of {:?} {:#04x}: {:?}",
+                function_group, subfunction, parameter
+            )));
+        }
+        parameter.check()?;
+        if !data.return_code.is_success() {
+            return Err(Error::ItemError {
+                index: 0,
+                return_code: data.return_code.clone(),
+            });
+        }
+        Ok(data.data.as_slice())
+    }
+}
@@ -12,7 +12,7 @@ use tokio_util::codec::Decoder;
 
 #[test]
 fn test_dt_data_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder::default());
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_dt_data_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -27,7 +27,7 @@ fn test_dt_data_decode() {
 
 #[test]
 fn test_connect_request_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder::default());
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_connect_request_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -42,7 +42,7 @@ fn test_connect_request_decode() {
 
 #[test]
 fn test_connect_confirm_decode() {
-    let mut decoder = CoptDecoder(S7CommDecoder);
+    let mut decoder = CoptDecoder::new(S7CommDecoder::default());
     let mut src = BytesMut::new();
     src.extend_from_slice(init_copt_connect_confirm_frame_bytes());
     let rs = decoder.decode(&mut src);
@@ -39,3 +39,13 @@ fn test_connect_confirm_encode() {
     assert!(res.is_ok());
     assert_eq!(dst.as_ref(), init_copt_connect_confirm_frame_bytes());
 }
+
+#[test]
+fn test_disconnect_request_encode() {
+    let frame = copt::CoptFrame::<s7_comm::Frame>::disconnect_request([0x00, 0x01], [0x00, 0x02]);
+    let mut encoder = CoptEncoder(S7CommEncoder);
+    let mut dst = BytesMut::new();
+    let res = encoder.encode(frame, &mut dst);
+    assert!(res.is_ok());
+    assert_eq!(dst.as_ref(), [0x06, 0x80, 0x00, 0x01, 0x00, 0x02, 0x80]);
+}
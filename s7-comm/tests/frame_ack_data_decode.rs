@@ -1,9 +1,10 @@
 use bytes::BytesMut;
 use s7_comm::{
-    AckData, DataItemVal, DataItemWriteResponse, Frame, HearderAckData, ReadVarAckData, ReturnCode,
-    S7CommDecoder, SetupCommunication, WriteVarAckData,
+    nak_description, AckData, DataItemVal, DataItemWriteResponse, DataTransportSize, Error,
+    ErrorClass, Frame, HearderAckData, MissingItems, ReadVarAckData, ReturnCode, S7CommDecoder,
+    S7CommEncoder, SetupCommunication, WriteVarAckData,
 };
-use tokio_util::codec::Decoder;
+use tokio_util::codec::{Decoder, Encoder};
 
 #[test]
 fn setup_decode() {
@@ -12,7 +13,7 @@ fn setup_decode() {
         0x01, 0x00, 0x01, 0x00, 0xf0,
     ];
     let mut src = BytesMut::from(bytes.as_ref());
-    let mut decoder = S7CommDecoder;
+    let mut decoder = S7CommDecoder::default();
     let frame_builder = decoder.decode(&mut src);
     assert!(frame_builder.is_ok());
     if let Ok(res) = frame_builder {
@@ -37,7 +38,7 @@ fn write_var_decode() {
         0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x05, 0x01, 0xff,
     ];
     let mut src = BytesMut::from(bytes.as_ref());
-    let mut decoder = S7CommDecoder;
+    let mut decoder = S7CommDecoder::default();
     let frame_builder = decoder.decode(&mut src);
     assert!(frame_builder.is_ok());
     if let Ok(res) = frame_builder {
@@ -49,6 +50,9 @@ fn write_var_decode() {
 
             let header_right = HearderAckData::init(1280, 2, 1, 0, 0);
             assert_eq!(header_right, header);
+            assert!(header.is_ok());
+            assert_eq!(header.pdu_ref(), 1280);
+            assert_eq!(header.error_class(), ErrorClass::NoError);
 
             let ack = WriteVarAckData::default()
                 .add_response(DataItemWriteResponse::init(ReturnCode::Success));
@@ -64,7 +68,7 @@ fn read_var_decode() {
         0x04, 0x00, 0x20, 0x00, 0x00, 0x00, 0x79,
     ];
     let mut src = BytesMut::from(bytes.as_ref());
-    let mut decoder = S7CommDecoder;
+    let mut decoder = S7CommDecoder::default();
     let frame_builder = decoder.decode(&mut src);
     assert!(frame_builder.is_ok());
     if let Ok(res) = frame_builder {
@@ -83,3 +87,364 @@ fn read_var_decode() {
         }
     }
 }
+
+#[test]
+fn error_ack_decode() {
+    let bytes: [u8; 14] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x00, 0x85, 0x00, 0x04, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::AckData { header, ack_data })) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(!header.is_ok());
+    assert_eq!(header.pdu_ref(), 1280);
+    assert_eq!(header.parameter_len(), 2);
+    assert_eq!(header.data_len(), 0);
+    assert_eq!(header.error_class(), ErrorClass::ErrorOnSupplies);
+    assert_eq!(header.error_code(), 0);
+    assert_eq!(ack_data, AckData::ReadVar(ReadVarAckData::default()));
+    let Err(
+        err @ Error::NakResponse {
+            error_class: 0x85,
+            error_code: 0x00,
+        },
+    ) = header.check()
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        err.to_string(),
+        "nak response, error class: 0x85, error code: 0x00, S7 protocol error: Wrong frames"
+    );
+    assert_eq!(
+        nak_description(0x81, 0x04),
+        "This service is not implemented on the module or a frame error was reported"
+    );
+    assert_eq!(nak_description(0x87, 0x7f), "Access error");
+}
+
+#[test]
+fn item_error_decode() {
+    let bytes: [u8; 26] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x0c, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x20, 0x00, 0x00, 0x00, 0x79, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::AckData {
+        header,
+        ack_data: AckData::ReadVar(data),
+    })) = decoder.decode(&mut src)
+    else {
+        unreachable!()
+    };
+    assert!(header.check().is_ok());
+    let Err(Error::ItemError {
+        index: 1,
+        return_code: ReturnCode::Err,
+    }) = data.try_data_item()
+    else {
+        unreachable!()
+    };
+
+    let ack = WriteVarAckData::default()
+        .add_response(DataItemWriteResponse::init(ReturnCode::Success))
+        .add_response(DataItemWriteResponse::init(ReturnCode::NotAllow));
+    let Err(Error::ItemError {
+        index: 1,
+        return_code: ReturnCode::NotAllow,
+    }) = ack.check()
+    else {
+        unreachable!()
+    };
+}
+
+#[test]
+fn read_var_results_decode() {
+    let bytes: [u8; 26] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x0c, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x20, 0x00, 0x00, 0x00, 0x79, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::AckData {
+        ack_data: AckData::ReadVar(data),
+        ..
+    })) = decoder.decode(&mut src)
+    else {
+        unreachable!()
+    };
+    let results = data.results();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0],
+        Ok(DataItemVal::init_with_bytes(
+            ReturnCode::Success,
+            [0x00, 0x00, 0x00, 0x79].as_ref()
+        ))
+    );
+    assert_eq!(results[1], Err(ReturnCode::Err));
+    assert!(ReturnCode::Success.is_success());
+    assert!(!ReturnCode::Err.is_success());
+    assert_eq!(ReturnCode::Err.to_string(), "Object does not exist");
+    assert_eq!(ReturnCode::Success.to_string(), "Success");
+}
+
+#[test]
+fn read_var_items_in_request_order() {
+    // a word, 3 bytes followed by the fill byte and an int
+    let bytes: [u8; 34] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x14, 0x00, 0x00, 0x04, 0x03, 0xff,
+        0x04, 0x00, 0x10, 0x12, 0x34, 0xff, 0x04, 0x00, 0x18, 0x01, 0x02, 0x03, 0x00, 0xff, 0x05,
+        0x00, 0x10, 0xff, 0xfe,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let Ok(Some(Frame::AckData {
+        ack_data: AckData::ReadVar(data),
+        ..
+    })) = S7CommDecoder::default().decode(&mut src)
+    else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    let items = data.data_item_of(3, MissingItems::Error).unwrap();
+    assert_eq!(items[0].as_word(), Some(0x1234));
+    assert_eq!(items[1].as_bytes(), [0x01, 0x02, 0x03]);
+    assert_eq!(items[2].as_int(), Some(-2));
+}
+
+#[test]
+fn read_var_missing_items() {
+    // the response of a 3 items request carries 2 items
+    let bytes: [u8; 25] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x0b, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x08, 0x01, 0x00, 0xff, 0x04, 0x00, 0x08, 0x02,
+    ];
+    let decode = || {
+        let mut src = BytesMut::from(bytes.as_ref());
+        let Ok(Some(Frame::AckData {
+            ack_data: AckData::ReadVar(data),
+            ..
+        })) = S7CommDecoder::default().decode(&mut src)
+        else {
+            unreachable!()
+        };
+        data
+    };
+    assert!(matches!(
+        decode().data_item_of(3, MissingItems::default()),
+        Err(Error::ItemCountMismatch {
+            expected: 3,
+            actual: 2
+        })
+    ));
+
+    let items = decode().data_item_of(3, MissingItems::Pad).unwrap();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].as_bytes(), [0x01]);
+    assert_eq!(items[1].as_bytes(), [0x02]);
+    assert_eq!(items[2].return_code, ReturnCode::Err);
+
+    assert_eq!(
+        decode().data_item_of(2, MissingItems::Error).unwrap().len(),
+        2
+    );
+    // more items than requested
+    assert!(matches!(
+        decode().data_item_of(1, MissingItems::Pad),
+        Err(Error::ItemCountMismatch {
+            expected: 1,
+            actual: 2
+        })
+    ));
+}
+
+#[test]
+fn read_var_byte_array_and_bool_decode() {
+    let bytes: [u8; 27] = [
+        0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x0d, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x18, 0x01, 0x02, 0x03, 0x00, 0xff, 0x03, 0x00, 0x01, 0x01,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    let Frame::AckData {
+        ack_data: AckData::ReadVar(data),
+        ..
+    } = &frame
+    else {
+        unreachable!()
+    };
+    assert_eq!(data.bytes_len_data(), 13);
+
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let Ok(Some(Frame::AckData {
+        ack_data: AckData::ReadVar(data),
+        ..
+    })) = decoder.decode(&mut dst)
+    else {
+        unreachable!()
+    };
+    let items = data.data_item();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].transport_size_type, DataTransportSize::NoBit);
+    assert_eq!(items[0].as_bytes(), [0x01, 0x02, 0x03]);
+    assert!(items[0].as_bool().is_err());
+    assert_eq!(items[1].transport_size_type, DataTransportSize::Bit);
+    assert!(items[1].as_bool().unwrap());
+    assert!(items[1].as_i16().is_err());
+
+    let item = DataItemVal::init_with_int(ReturnCode::Success, -2);
+    assert_eq!(item.as_i16().unwrap(), -2);
+    let item = DataItemVal::init_with_real(ReturnCode::Success, 1.5);
+    assert_eq!(item.as_f32().unwrap(), 1.5);
+}
+
+#[test]
+fn read_var_dinteger_decode() {
+    // the ack of a read of a DINT, the length of transport size 0x06 is in
+    // bytes
+    let bytes: [u8; 22] = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x08, 0x00, 0x00, 0x04, 0x01, 0xff,
+        0x06, 0x00, 0x04, 0xff, 0xfe, 0x79, 0x60,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let Ok(Some(Frame::AckData {
+        ack_data: AckData::ReadVar(data),
+        ..
+    })) = S7CommDecoder::default().decode(&mut src)
+    else {
+        unreachable!()
+    };
+    let items = data.data_item();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].transport_size_type, DataTransportSize::DInteger);
+    assert_eq!(items[0].length, 4);
+    assert_eq!(items[0].as_dint(), Some(-100000));
+    assert!(src.is_empty());
+}
+
+#[test]
+fn structured_decode_errors() {
+    let mut decoder = S7CommDecoder::default();
+    let mut src =
+        BytesMut::from([0x32, 0x02, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00].as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(Error::UnknownRosctr(0x02))
+    ));
+
+    let mut src = BytesMut::from(
+        [
+            0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x2a, 0x00,
+        ]
+        .as_ref(),
+    );
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(Error::UnknownFunction(0x2a))
+    ));
+
+    // the data item declares 4 bytes, only 2 follow
+    let mut src = BytesMut::from(
+        [
+            0x32, 0x03, 0x00, 0x00, 0x05, 0x00, 0x00, 0x02, 0x00, 0x06, 0x00, 0x00, 0x04, 0x01,
+            0xff, 0x04, 0x00, 0x20, 0x00, 0x00,
+        ]
+        .as_ref(),
+    );
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(Error::InsufficientData { needed: 4, got: 2 })
+    ));
+}
+
+#[test]
+fn read_var_decode_lenient_padding() {
+    // the firmware skips the fill byte after the 3 bytes of the first item
+    let bytes: [u8; 27] = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x0d, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x18, 0x01, 0x02, 0x03, 0xff, 0x04, 0x00, 0x10, 0x0a, 0x0b,
+    ];
+    let expected = AckData::ReadVar(
+        ReadVarAckData::default()
+            .add_response(DataItemVal::init_with_bytes(
+                ReturnCode::Success,
+                &[0x01, 0x02, 0x03],
+            ))
+            .add_response(DataItemVal::init_with_bytes(
+                ReturnCode::Success,
+                &[0x0a, 0x0b],
+            )),
+    );
+    let decode = |bytes: &[u8], lenient_padding| {
+        S7CommDecoder::default()
+            .lenient_padding(lenient_padding)
+            .decode(&mut BytesMut::from(bytes))
+    };
+
+    assert!(decode(&bytes, false).is_err());
+    let Ok(Some(Frame::AckData { ack_data, .. })) = decode(&bytes, true) else {
+        unreachable!()
+    };
+    assert_eq!(ack_data, expected);
+
+    // the fill byte is still skipped when present
+    let mut padded = bytes.to_vec();
+    padded[9] = 0x0e;
+    padded.insert(21, 0x00);
+    let Ok(Some(Frame::AckData { ack_data, .. })) = decode(&padded, true) else {
+        unreachable!()
+    };
+    assert_eq!(ack_data, expected);
+}
+
+#[test]
+fn read_var_decode_lenient_padding_before_bit_and_int() {
+    let decode = |bytes: &[u8]| {
+        let Ok(Some(Frame::AckData {
+            ack_data: AckData::ReadVar(data),
+            ..
+        })) = S7CommDecoder::default()
+            .lenient_padding(true)
+            .decode(&mut BytesMut::from(bytes))
+        else {
+            unreachable!()
+        };
+        data.data_item()
+    };
+
+    // the transport size 0x03 of the bit item follows the omitted fill byte
+    let items = decode(&[
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x08, 0x01, 0xff, 0x03, 0x00, 0x01, 0x01,
+    ]);
+    assert_eq!(
+        items,
+        [
+            DataItemVal::init_with_bytes(ReturnCode::Success, &[0x01]),
+            DataItemVal::init_with_bit(ReturnCode::Success, true),
+        ]
+    );
+
+    // the transport size 0x05 of the int item follows the omitted fill byte
+    let items = decode(&[
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x0b, 0x00, 0x00, 0x04, 0x02, 0xff,
+        0x04, 0x00, 0x08, 0x01, 0xff, 0x05, 0x00, 0x10, 0xff, 0xfe,
+    ]);
+    assert_eq!(
+        items,
+        [
+            DataItemVal::init_with_bytes(ReturnCode::Success, &[0x01]),
+            DataItemVal::init_with_int(ReturnCode::Success, -2),
+        ]
+    );
+}
@@ -0,0 +1,127 @@
+use bytes::BytesMut;
+use s7_comm::{
+    block_mc7_length, check_request_download, decode_start_upload, decode_upload, BlockListEntry,
+    BlockType, Error, Frame, Job, S7CommDecoder, S7CommEncoder,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+fn encode(frame: Frame) -> BytesMut {
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    dst
+}
+
+fn decode(bytes: &[u8]) -> Frame {
+    let mut src = BytesMut::from(bytes);
+    let frame = S7CommDecoder::default().decode(&mut src).unwrap().unwrap();
+    assert!(src.is_empty());
+    frame
+}
+
+#[test]
+fn start_upload_encode() {
+    let bytes: [u8; 28] = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x12, 0x00, 0x00, 0x1d, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x5f, 0x30, 0x41, 0x30, 0x30, 0x30, 0x30, 0x31, 0x41,
+    ];
+    let dst = encode(Frame::start_upload(5, BlockType::Db, 1));
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+    assert_eq!(decode(&bytes), Frame::start_upload(5, BlockType::Db, 1));
+
+    let ack = encode(Frame::start_upload_ack(5, 7, 158));
+    assert_eq!(decode_start_upload(&decode(&ack)).unwrap(), (7, 158));
+}
+
+#[test]
+fn start_upload_rejected() {
+    // block not found, the parameter carries only the function
+    let bytes: [u8; 13] = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x05, 0x00, 0x01, 0x00, 0x00, 0xd2, 0x09, 0x1d,
+    ];
+    assert!(matches!(
+        decode_start_upload(&decode(&bytes)),
+        Err(Error::NakResponse {
+            error_class: 0xd2,
+            error_code: 0x09
+        })
+    ));
+}
+
+#[test]
+fn upload_ack_decode() {
+    let bytes: [u8; 22] = [
+        0x32, 0x03, 0x00, 0x00, 0x00, 0x07, 0x00, 0x02, 0x00, 0x08, 0x00, 0x00, 0x1e, 0x01, 0x00,
+        0x04, 0x00, 0xfb, 0x70, 0x70, 0x01, 0x02,
+    ];
+    let frame = decode(&bytes);
+    assert_eq!(
+        decode_upload(&frame).unwrap(),
+        (&[0x70u8, 0x70, 0x01, 0x02][..], true)
+    );
+    assert_eq!(
+        frame,
+        Frame::upload_ack(7, true, vec![0x70, 0x70, 0x01, 0x02])
+    );
+    assert!(matches!(
+        decode_upload(&Frame::upload(7, 1)),
+        Err(Error::Other(_))
+    ));
+}
+
+#[test]
+fn request_download_encode() {
+    let bytes: [u8; 42] = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x06, 0x00, 0x20, 0x00, 0x00, 0x1a, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x09, 0x5f, 0x30, 0x41, 0x30, 0x30, 0x30, 0x30, 0x31, 0x50, 0x0d, 0x31,
+        0x30, 0x30, 0x30, 0x31, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x32, 0x30,
+    ];
+    let frame = Frame::request_download(6, BlockType::Db, 1, 100, 20);
+    assert_eq!(encode(frame).as_ref(), bytes.as_ref());
+    assert_eq!(
+        decode(&bytes),
+        Frame::request_download(6, BlockType::Db, 1, 100, 20)
+    );
+
+    let ack = encode(Frame::request_download_ack(6));
+    assert!(check_request_download(&decode(&ack)).is_ok());
+}
+
+#[test]
+fn download_block_of_plc() {
+    let bytes = encode(Frame::download_block(0x0100, BlockType::Fc, 12));
+    let Frame::Job {
+        job: Job::DownloadBlock(job),
+        ..
+    } = decode(&bytes)
+    else {
+        unreachable!()
+    };
+    assert_eq!(
+        job.block(),
+        Some(BlockListEntry {
+            block_type: BlockType::Fc,
+            block_num: 12
+        })
+    );
+
+    let ack = encode(Frame::download_block_ack(0x0100, false, vec![0x01; 3]));
+    assert_eq!(
+        decode(&ack),
+        Frame::download_block_ack(0x0100, false, vec![0x01; 3])
+    );
+}
+
+#[test]
+fn check_block_mc7_length() {
+    let mut block = vec![0u8; 56];
+    block[34..36].copy_from_slice(&[0x00, 0x14]);
+    assert_eq!(block_mc7_length(&block).unwrap(), 20);
+    assert!(matches!(
+        block_mc7_length(&block[..35]),
+        Err(Error::InsufficientData {
+            needed: 36,
+            got: 35
+        })
+    ));
+}
@@ -0,0 +1,88 @@
+use bytes::BytesMut;
+use s7_comm::{AckData, Frame, S7CommDecoder, S7CommEncoder};
+use tokio_util::codec::{Decoder, Encoder};
+
+fn encode(frame: Frame) -> BytesMut {
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    dst
+}
+
+#[test]
+fn plc_stop_encode() {
+    let bytes: [u8; 26] = [
+        0x32, 0x01, 0x00, 0x00, 0x0e, 0x00, 0x00, 0x10, 0x00, 0x00, 0x29, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x09, 0x50, 0x5f, 0x50, 0x52, 0x4f, 0x47, 0x52, 0x41, 0x4d,
+    ];
+    let mut dst = encode(Frame::plc_stop(0x0e00));
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(
+        decoder.decode(&mut dst).unwrap(),
+        Some(Frame::plc_stop(0x0e00))
+    );
+}
+
+#[test]
+fn plc_start_encode() {
+    let bytes: [u8; 30] = [
+        0x32, 0x01, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x14, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0xfd, 0x00, 0x00, 0x09, 0x50, 0x5f, 0x50, 0x52, 0x4f, 0x47, 0x52, 0x41, 0x4d,
+    ];
+    let mut dst = encode(Frame::plc_start(0x0c00));
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(
+        decoder.decode(&mut dst).unwrap(),
+        Some(Frame::plc_start(0x0c00))
+    );
+}
+
+#[test]
+fn plc_cold_start_encode() {
+    let bytes: [u8; 32] = [
+        0x32, 0x01, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x16, 0x00, 0x00, 0x28, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0xfd, 0x00, 0x02, 0x43, 0x20, 0x09, 0x50, 0x5f, 0x50, 0x52, 0x4f, 0x47, 0x52,
+        0x41, 0x4d,
+    ];
+    let mut dst = encode(Frame::plc_cold_start(0x0f00));
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(
+        decoder.decode(&mut dst).unwrap(),
+        Some(Frame::plc_cold_start(0x0f00))
+    );
+}
+
+#[test]
+fn plc_control_ack_decode() {
+    let bytes: [u8; 13] = [
+        0x32, 0x03, 0x00, 0x00, 0x0e, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x29,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::AckData { header, ack_data })) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(header.check().is_ok());
+    assert!(matches!(ack_data, AckData::PlcStop(ack) if ack.parameter.is_empty()));
+
+    // the plc rejected the start: error class 0x85, error code 0x00
+    let bytes: [u8; 14] = [
+        0x32, 0x03, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x02, 0x00, 0x00, 0x85, 0x00, 0x28, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let Ok(Some(Frame::AckData { header, ack_data })) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(header.check().is_err());
+    assert!(matches!(ack_data, AckData::PlcControl(ref ack) if ack.parameter == [0x00]));
+    assert_eq!(
+        encode(Frame::AckData { header, ack_data }).as_ref(),
+        bytes.as_ref()
+    );
+}
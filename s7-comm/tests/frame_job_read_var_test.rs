@@ -0,0 +1,34 @@
+use bytes::BytesMut;
+use s7_comm::{Area, Frame, ItemRequest, S7CommDecoder, TransportSize};
+use tokio_util::codec::Decoder;
+
+#[test]
+fn read_var_job_items() {
+    // read DB1.DBB10 (4 bytes) and M3.5
+    let bytes: [u8; 36] = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x07, 0x00, 0x1a, 0x00, 0x00, 0x04, 0x02, 0x12, 0x0a, 0x10,
+        0x02, 0x00, 0x04, 0x00, 0x01, 0x84, 0x00, 0x00, 0x50, 0x12, 0x0a, 0x10, 0x01, 0x00, 0x01,
+        0x00, 0x00, 0x83, 0x00, 0x00, 0x1d,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::Job { header, job })) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    assert_eq!(header.pdu_ref, 7);
+    assert_eq!(job.function(), 0x04);
+
+    let items = job.items();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0], ItemRequest::init_db_byte(1, 10, 4));
+    assert_eq!(items[1].transport_size_type(), TransportSize::Bit);
+    assert_eq!(*items[1].area(), Area::Merker);
+    assert_eq!((items[1].byte_addr(), items[1].bit_addr()), (3, 5));
+
+    let Frame::Job { job, .. } = Frame::job_setup(1).build() else {
+        unreachable!()
+    };
+    assert_eq!(job.function(), 0xf0);
+    assert!(job.items().is_empty());
+}
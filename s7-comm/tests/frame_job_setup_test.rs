@@ -1,9 +1,9 @@
 mod test_data;
 
-use crate::test_data::test_s7_comm_data::init_job_setup_frame;
+use crate::test_data::test_s7_comm_data::{init_job_setup_frame, init_job_setup_frame_bytes};
 use bytes::BytesMut;
-use s7_comm::S7CommEncoder;
-use tokio_util::codec::Encoder;
+use s7_comm::{AckData, Frame, HearderAckData, S7CommDecoder, S7CommEncoder, SetupCommunication};
+use tokio_util::codec::{Decoder, Encoder};
 
 #[test]
 fn encode() {
@@ -17,3 +17,28 @@ fn encode() {
     assert!(encoder.encode(frame, &mut dst).is_ok());
     assert_eq!(dst.as_ref(), bytes.as_ref())
 }
+
+#[test]
+fn setup_communication_round_trip() {
+    let frame = Frame::setup_communication(1024, 1, 1, 480);
+    assert_eq!(frame, init_job_setup_frame());
+    assert_eq!(frame.negotiated_pdu_length(), None);
+
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), init_job_setup_frame_bytes());
+
+    let ack = Frame::AckData {
+        header: HearderAckData::init(1024, 8, 0, 0, 0),
+        ack_data: AckData::SetupCommunication(SetupCommunication::init(1, 1, 480)),
+    };
+    let mut dst = BytesMut::new();
+    assert!(encoder.encode(ack, &mut dst).is_ok());
+
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut dst) else {
+        unreachable!()
+    };
+    assert_eq!(frame.negotiated_pdu_length(), Some(480));
+}
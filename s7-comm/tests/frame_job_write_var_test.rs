@@ -0,0 +1,60 @@
+use bytes::BytesMut;
+use s7_comm::{Frame, Job, S7CommDecoder, S7CommEncoder};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn write_db_masked_encode() {
+    // clear DB1.DBX2.0, set DB1.DBX2.7
+    let bytes: [u8; 47] = [
+        0x32, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x1a, 0x00, 0x0b, 0x05, 0x02, 0x12, 0x0a, 0x10,
+        0x01, 0x00, 0x01, 0x00, 0x01, 0x84, 0x00, 0x00, 0x10, 0x12, 0x0a, 0x10, 0x01, 0x00, 0x01,
+        0x00, 0x01, 0x84, 0x00, 0x00, 0x17, 0x00, 0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x00,
+        0x01, 0x01,
+    ];
+    let frame = || {
+        Frame::job_write_var(1)
+            .write_db_masked(1, 2, 0b1111_1110, 0b1000_0000)
+            .build()
+    };
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame(), &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(decoder.decode(&mut dst).unwrap(), Some(frame()));
+}
+
+#[test]
+fn write_db_masked_unchanged_bits() {
+    let Frame::Job {
+        job: Job::WriteVar(job),
+        ..
+    } = Frame::job_write_var(1)
+        .write_db_masked(1, 0, 0xff, 0x00)
+        .build()
+    else {
+        unreachable!()
+    };
+    assert!(job.parameters_item().is_empty());
+
+    let Frame::Job {
+        job: Job::WriteVar(job),
+        ..
+    } = Frame::job_write_var(1)
+        .write_db_masked(1, 0, 0x0f, 0x01)
+        .build()
+    else {
+        unreachable!()
+    };
+    let bits: Vec<(u8, bool)> = job
+        .parameters_item()
+        .iter()
+        .zip(job.data_item())
+        .map(|(req, val)| (req.bit_addr(), val.as_bool().unwrap()))
+        .collect();
+    assert_eq!(
+        bits,
+        [(0, true), (4, false), (5, false), (6, false), (7, false)]
+    );
+}
@@ -0,0 +1,167 @@
+use bytes::BytesMut;
+use s7_comm::{
+    decode_block_info, decode_list_blocks, decode_list_blocks_of_type, BlockType, Error, Frame,
+    S7CommDecoder, S7CommEncoder,
+};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio_util::codec::{Decoder, Encoder};
+
+fn decode(bytes: &[u8]) -> Frame {
+    let mut src = BytesMut::from(bytes);
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    frame
+}
+
+#[test]
+fn list_blocks_encode() {
+    let bytes: [u8; 22] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x04, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x43, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(Frame::list_blocks(1), &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
+
+#[test]
+fn list_blocks_decode() {
+    let bytes: [u8; 50] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x0c, 0x00, 0x1c, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x83, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0x09, 0x00, 0x18, 0x30, 0x38, 0x00, 0x03,
+        0x30, 0x41, 0x00, 0x05, 0x30, 0x43, 0x00, 0x02, 0x30, 0x45, 0x00, 0x01, 0x30, 0x44, 0x00,
+        0x5a, 0x30, 0x46, 0x00, 0x18,
+    ];
+    assert_eq!(
+        decode_list_blocks(&decode(&bytes)).unwrap(),
+        vec![
+            (BlockType::Ob, 3),
+            (BlockType::Db, 5),
+            (BlockType::Fc, 2),
+            (BlockType::Fb, 1),
+            (BlockType::Sfc, 90),
+            (BlockType::Sfb, 24),
+        ]
+    );
+}
+
+#[test]
+fn list_blocks_of_type_encode() {
+    let bytes: [u8; 24] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x08, 0x00, 0x06, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x43, 0x02, 0x00, 0xff, 0x09, 0x00, 0x02, 0x30, 0x41,
+    ];
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder
+        .encode(Frame::list_blocks_of_type(2, BlockType::Db), &mut dst)
+        .is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
+
+#[test]
+fn list_blocks_of_db_decode() {
+    // DB1, DB2, DB10 and DB100
+    let bytes: [u8; 42] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x0c, 0x00, 0x14, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x83, 0x02, 0x02, 0x00, 0x00, 0x00, 0x00, 0xff, 0x09, 0x00, 0x10, 0x00, 0x01, 0x22, 0x05,
+        0x00, 0x02, 0x22, 0x05, 0x00, 0x0a, 0x22, 0x01, 0x00, 0x64, 0x42, 0x05,
+    ];
+    let frame = decode(&bytes);
+    assert_eq!(
+        decode_list_blocks_of_type(&frame).unwrap(),
+        vec![(1, 0x22), (2, 0x22), (10, 0x22), (100, 0x42)]
+    );
+    // not a list blocks response
+    assert!(matches!(decode_list_blocks(&frame), Err(Error::Other(_))));
+}
+
+#[test]
+fn list_blocks_of_type_rejected() {
+    // no block of the type: error code 0xd20e, return code 0x0a
+    let bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x02, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x83, 0x02, 0x02, 0x00, 0x00, 0xd2, 0x0e, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    assert!(matches!(
+        decode_list_blocks_of_type(&decode(&bytes)),
+        Err(Error::NakResponse {
+            error_class: 0xd2,
+            error_code: 0x0e
+        })
+    ));
+}
+
+#[test]
+fn get_block_info_encode() {
+    let bytes: [u8; 30] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x03, 0x00, 0x08, 0x00, 0x0c, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x43, 0x03, 0x00, 0xff, 0x09, 0x00, 0x08, 0x30, 0x41, 0x30, 0x30, 0x31, 0x30, 0x30, 0x41,
+    ];
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder
+        .encode(Frame::get_block_info(3, BlockType::Db, 100), &mut dst)
+        .is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
+
+#[test]
+fn block_info_of_db_decode() {
+    let mut bytes = vec![
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x03, 0x00, 0x0c, 0x00, 0x52, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x83, 0x03, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0x09, 0x00, 0x4e,
+    ];
+    // DB100, non-retain, language DB, 158 bytes of load memory, unprotected
+    bytes.extend_from_slice(&[
+        0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x70, 0x70, 0x01, 0x01, 0x05, 0x0a, 0x00, 0x64, 0x00,
+        0x00, 0x00, 0x9e, 0x00, 0x00, 0x00, 0x00,
+    ]);
+    // modified at 2014-05-10 13:34:28.664, interface changed at 2014-05-09
+    bytes.extend_from_slice(&[
+        0x02, 0xe9, 0xad, 0x38, 0x2b, 0x4f, 0x00, 0x00, 0x00, 0x00, 0x2b, 0x4e,
+    ]);
+    // sbb length, additional length, local data and mc7 code length
+    bytes.extend_from_slice(&[0x00, 0x1c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14]);
+    bytes.extend_from_slice(b"ME\0\0\0\0\0\0TEST    DB_TEST\0");
+    // version 1.2, checksum and reserved
+    bytes.extend_from_slice(&[0x12, 0x00, 0xab, 0xcd]);
+    bytes.extend_from_slice(&[0x00; 8]);
+    assert_eq!(bytes.len(), 104);
+
+    let info = decode_block_info(&decode(&bytes)).unwrap();
+    assert_eq!(info.block_type, BlockType::Db);
+    assert_eq!(info.block_number, 100);
+    assert_eq!(info.language, 0x05);
+    assert_eq!(info.load_memory_size, 158);
+    assert!(!info.protection.know_how_protected);
+    assert_eq!(
+        info.last_modified,
+        UNIX_EPOCH + Duration::from_millis(1_399_728_868_664)
+    );
+    assert_eq!(
+        info.last_interface_change,
+        UNIX_EPOCH + Duration::from_secs(1_399_593_600)
+    );
+    assert_eq!(info.mc7_code_length, 20);
+    assert_eq!(info.author, "ME");
+    assert_eq!(info.family, "TEST");
+    assert_eq!(info.name, "DB_TEST");
+    assert_eq!(info.version, 0x12);
+    assert_eq!(info.checksum, 0xabcd);
+
+    bytes.truncate(100);
+    bytes[9] = 0x4e;
+    bytes[25] = 0x4a;
+    assert!(matches!(
+        decode_block_info(&decode(&bytes)),
+        Err(Error::InsufficientData {
+            needed: 78,
+            got: 74
+        })
+    ));
+}
@@ -0,0 +1,124 @@
+use bytes::BytesMut;
+use s7_comm::{
+    check_set_clock, decode_clock, DateTimeParts, Error, Frame, S7CommDecoder, S7CommEncoder,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn read_clock_encode() {
+    let bytes: [u8; 22] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x04, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x47, 0x01, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(Frame::read_clock(5), &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(
+        decoder.decode(&mut dst).unwrap(),
+        Some(Frame::read_clock(5))
+    );
+}
+
+#[test]
+fn read_clock_decode() {
+    let bytes: [u8; 36] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x0c, 0x00, 0x0e, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x87, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0x09, 0x00, 0x0a, 0x00, 0x20, 0x23, 0x06,
+        0x15, 0x13, 0x45, 0x30, 0x12, 0x35,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    assert_eq!(
+        decode_clock(&frame).unwrap(),
+        DateTimeParts {
+            year: 2023,
+            month: 6,
+            day: 15,
+            hour: 13,
+            minute: 45,
+            second: 30,
+            millisecond: 123,
+            weekday: 5,
+        }
+    );
+
+    // not a read clock response
+    assert!(matches!(
+        decode_clock(&Frame::read_szl(5, 0x0011, 0)),
+        Err(Error::Other(_))
+    ));
+}
+
+#[test]
+fn set_clock_encode() {
+    let bytes: [u8; 32] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x06, 0x00, 0x08, 0x00, 0x0e, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x47, 0x02, 0x00, 0xff, 0x09, 0x00, 0x0a, 0x00, 0x19, 0x99, 0x12, 0x31, 0x23, 0x59, 0x59,
+        0x99, 0x96,
+    ];
+    let date_time = DateTimeParts {
+        year: 1999,
+        month: 12,
+        day: 31,
+        hour: 23,
+        minute: 59,
+        second: 59,
+        millisecond: 999,
+        weekday: 6,
+    };
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder
+        .encode(Frame::set_clock(6, &date_time).unwrap(), &mut dst)
+        .is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    // out of the window of DATE_AND_TIME
+    assert!(Frame::set_clock(
+        6,
+        &DateTimeParts {
+            year: 2090,
+            ..date_time
+        }
+    )
+    .is_err());
+}
+
+#[test]
+fn set_clock_response() {
+    let mut bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x06, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x87, 0x02, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut decoder = S7CommDecoder::default();
+    let frame = decoder
+        .decode(&mut BytesMut::from(bytes.as_ref()))
+        .unwrap()
+        .unwrap();
+    assert!(check_set_clock(&frame).is_ok());
+    assert!(matches!(
+        check_set_clock(&Frame::read_clock(6)),
+        Err(Error::Other(_))
+    ));
+
+    // rejected by the protection level
+    bytes[20..22].copy_from_slice(&[0xd6, 0x02]);
+    let frame = decoder
+        .decode(&mut BytesMut::from(bytes.as_ref()))
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        check_set_clock(&frame),
+        Err(Error::NakResponse {
+            error_class: 0xd6,
+            error_code: 0x02
+        })
+    ));
+}
@@ -0,0 +1,187 @@
+use bytes::BytesMut;
+use s7_comm::{
+    Error, Frame, Header, ReturnCode, S7CommDecoder, S7CommEncoder, SzlHeader, SzlId, SzlResponse,
+    UserDataData, UserDataFunctionGroup, UserDataParameter,
+};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[test]
+fn read_szl_module_identification_encode() {
+    let bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x08, 0x00, 0x08, 0x00, 0x01, 0x12, 0x04, 0x11,
+        0x44, 0x01, 0x00, 0xff, 0x09, 0x00, 0x04, 0x00, 0x11, 0x00, 0x00,
+    ];
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder
+        .encode(Frame::read_szl(5, 0x0011, 0x0000), &mut dst)
+        .is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+
+    let mut decoder = S7CommDecoder::default();
+    assert_eq!(
+        decoder.decode(&mut dst).unwrap(),
+        Some(Frame::read_szl(5, 0x0011, 0x0000))
+    );
+
+    assert_eq!(u16::from(SzlId::ModuleIdent), 0x0011);
+    assert_eq!(SzlId::from(0x0424), SzlId::CpuState);
+    assert_eq!(SzlId::from(0x0131), SzlId::NotSupport(0x0131));
+}
+
+#[test]
+fn read_szl_module_identification_decode() {
+    let bytes: [u8; 90] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x0c, 0x00, 0x44, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x84, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0xff, 0x09, 0x00, 0x40, 0x00, 0x11, 0x00, 0x00,
+        0x00, 0x1c, 0x00, 0x02, 0x00, 0x01, 0x36, 0x45, 0x53, 0x37, 0x20, 0x33, 0x31, 0x35, 0x2d,
+        0x32, 0x45, 0x48, 0x31, 0x34, 0x2d, 0x30, 0x41, 0x42, 0x30, 0x20, 0x00, 0x00, 0x00, 0x04,
+        0x00, 0x01, 0x00, 0x06, 0x36, 0x45, 0x53, 0x37, 0x20, 0x33, 0x31, 0x35, 0x2d, 0x32, 0x45,
+        0x48, 0x31, 0x34, 0x2d, 0x30, 0x41, 0x42, 0x30, 0x20, 0x00, 0x00, 0x00, 0x04, 0x00, 0x01,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    let Frame::UserData { parameter, .. } = &frame else {
+        unreachable!()
+    };
+    assert!(parameter.is_response());
+    assert!(parameter.last_data_unit);
+    assert_eq!(parameter.sequence_number, 1);
+    assert_eq!(
+        parameter.function_group(),
+        UserDataFunctionGroup::CpuFunctions
+    );
+
+    let szl = SzlResponse::from_frame(&frame).unwrap();
+    assert_eq!(
+        szl.header,
+        SzlHeader {
+            szl_id: 0x0011,
+            szl_index: 0x0000,
+            item_size: 28,
+            item_count: 2,
+        }
+    );
+    assert_eq!(szl.items.len(), 2);
+    assert_eq!(szl.items[0][..2], [0x00, 0x01]);
+    assert_eq!(&szl.items[0][2..22], b"6ES7 315-2EH14-0AB0 ");
+    assert_eq!(szl.items[1][..2], [0x00, 0x06]);
+
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+}
+
+#[test]
+fn read_szl_error_response() {
+    // szl id isn't available: error code 0xd401, return code 0x0a
+    let bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x84, 0x01, 0x01, 0x00, 0x00, 0xd4, 0x01, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(matches!(
+        SzlResponse::from_frame(&frame),
+        Err(Error::NakResponse {
+            error_class: 0xd4,
+            error_code: 0x01
+        })
+    ));
+    let Frame::UserData { data, .. } = frame else {
+        unreachable!()
+    };
+    assert_eq!(data.return_code, ReturnCode::Err);
+    assert!(data.data.is_empty());
+}
+
+#[test]
+fn user_data_sequence_number() {
+    // the first data unit of a read szl response, more data units follow
+    let bytes: [u8; 34] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x05, 0x00, 0x0c, 0x00, 0x0c, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x84, 0x01, 0x0b, 0x00, 0x01, 0x00, 0x00, 0xff, 0x09, 0x00, 0x08, 0x00, 0x11, 0x00, 0x00,
+        0x00, 0x1c, 0x00, 0x02,
+    ];
+    let mut src = BytesMut::from(bytes.as_ref());
+    let mut decoder = S7CommDecoder::default();
+    let Ok(Some(Frame::UserData { parameter, .. })) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert_eq!(parameter.sequence_number, 0x0b);
+    assert!(!parameter.last_data_unit);
+
+    // the request of the next data unit echoes the sequence number
+    let bytes: [u8; 26] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x06, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x01, 0x12, 0x08, 0x12,
+        0x44, 0x01, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    ];
+    let frame = || Frame::user_data_continuation(6, &parameter);
+    let mut dst = BytesMut::new();
+    let mut encoder = S7CommEncoder;
+    assert!(encoder.encode(frame(), &mut dst).is_ok());
+    assert_eq!(dst.as_ref(), bytes.as_ref());
+    assert_eq!(decoder.decode(&mut dst).unwrap(), Some(frame()));
+
+    // a request with a sequence number
+    let parameter = UserDataParameter::init_request(UserDataFunctionGroup::CpuFunctions, 0x01)
+        .sequence_number(3);
+    let data = UserDataData::init_with_octet_string(vec![0x00, 0x11, 0x00, 0x00]);
+    let frame = Frame::UserData {
+        header: Header::init(7, parameter.bytes_len(), data.bytes_len()),
+        parameter,
+        data,
+    };
+    let mut dst = BytesMut::new();
+    assert!(encoder.encode(frame, &mut dst).is_ok());
+    assert_eq!(dst[17], 3);
+    let Ok(Some(Frame::UserData { parameter, .. })) = decoder.decode(&mut dst) else {
+        unreachable!()
+    };
+    assert_eq!(parameter.sequence_number, 3);
+}
+
+#[test]
+fn user_data_short_parameter_decode() {
+    let bytes: [u8; 18] = [
+        0x32, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x12, 0x02, 0x11,
+        0x44, 0x01, 0x00,
+    ];
+    let mut decoder = S7CommDecoder::default();
+    assert!(matches!(
+        decoder.decode(&mut BytesMut::from(bytes.as_ref())),
+        Err(Error::InsufficientData { needed: 4, got: 2 })
+    ));
+
+    // the parameter length of the header disagrees with the parameter
+    let mut bytes = bytes;
+    bytes[13] = 0x04;
+    bytes[7] = 0x06;
+    assert!(matches!(
+        decoder.decode(&mut BytesMut::from(bytes.as_ref())),
+        Err(Error::Other(_))
+    ));
+}
+
+#[test]
+fn overflowing_lengths_decode() {
+    // the parameter and data lengths overflow a u16 together, the frame is
+    // incomplete rather than a panic
+    for rosctr in [0x01, 0x03, 0x07] {
+        let bytes = [
+            0x32, rosctr, 0x00, 0x00, 0x00, 0x01, 0xff, 0xf0, 0x00, 0x20, 0x00, 0x00,
+        ];
+        let mut src = BytesMut::from(bytes.as_ref());
+        let mut decoder = S7CommDecoder::default();
+        assert!(matches!(decoder.decode(&mut src), Ok(None)));
+        assert_eq!(src.len(), bytes.len());
+    }
+}
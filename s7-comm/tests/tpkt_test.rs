@@ -4,19 +4,23 @@ use crate::test_data::test_tpkt_data::{init_tpkt_frame, init_tpkt_frame_bytes};
 use bytes::BytesMut;
 use copt::{CoptDecoder, CoptEncoder};
 use s7_comm::{S7CommDecoder, S7CommEncoder};
+use std::io;
 use tokio_util::codec::{Decoder, Encoder};
-use tpkt::{TpktDecoder, TpktEncoder};
+use tpkt::{TpktDecoder, TpktEncoder, TpktFrame};
 
 #[test]
 fn test_decode() {
-    let mut decoder = TpktDecoder(CoptDecoder(S7CommDecoder));
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
     let mut src = BytesMut::new();
     src.extend_from_slice(init_tpkt_frame_bytes());
     let rs = decoder.decode(&mut src);
     assert!(rs.is_ok());
     if let Ok(Some(frame)) = rs {
         let dst_frame = init_tpkt_frame();
+        assert_eq!(dst_frame.payload_ref(), frame.payload_ref());
         assert_eq!(dst_frame, frame);
+        assert_eq!(frame.declared_length(), Some(0x16));
+        assert_eq!(dst_frame.declared_length(), None);
     } else {
         unreachable!()
     }
@@ -31,3 +35,106 @@ fn test_encode() {
     assert!(rs.is_ok());
     assert_eq!(init_tpkt_frame_bytes(), dst.as_ref())
 }
+
+#[test]
+fn test_decode_split_reads() {
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
+    let bytes = init_tpkt_frame_bytes();
+    let mut src = BytesMut::new();
+    // the header is split
+    src.extend_from_slice(&bytes[..2]);
+    assert!(matches!(decoder.decode(&mut src), Ok(None)));
+    src.extend_from_slice(&bytes[2..10]);
+    assert!(matches!(decoder.decode(&mut src), Ok(None)));
+    assert_eq!(src.len(), 10);
+    src.extend_from_slice(&bytes[10..]);
+    let Ok(Some(frame)) = decoder.decode(&mut src) else {
+        unreachable!()
+    };
+    assert_eq!(frame, init_tpkt_frame());
+    assert!(src.is_empty());
+}
+
+#[test]
+fn test_decode_invalid_length() {
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default())).max_length(0x15);
+    let mut src = BytesMut::from(init_tpkt_frame_bytes());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(tpkt::Error::LengthExceeded {
+            length: 0x16,
+            max_length: 0x15
+        })
+    ));
+
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
+    let mut src = BytesMut::from([3u8, 0, 0, 3].as_ref());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(tpkt::Error::InvalidLength(3))
+    ));
+}
+
+#[test]
+fn test_decode_unsupported_version() {
+    let mut bytes = init_tpkt_frame_bytes().to_vec();
+    bytes[0] = 2;
+
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(S7CommDecoder::default()));
+    let mut src = BytesMut::from(bytes.as_slice());
+    assert!(matches!(
+        decoder.decode(&mut src),
+        Err(tpkt::Error::UnsupportedVersion(2))
+    ));
+
+    let mut src = BytesMut::from(bytes.as_slice());
+    assert!(matches!(
+        TpktFrame::decode(&mut src),
+        Err(tpkt::Error::UnsupportedVersion(2))
+    ));
+}
+
+#[test]
+fn test_decode_raw() {
+    let bytes = init_tpkt_frame_bytes();
+    let mut src = BytesMut::from(&bytes[..10]);
+    assert!(matches!(TpktFrame::decode(&mut src), Ok(None)));
+    src.extend_from_slice(&bytes[10..]);
+    let Ok(Some(frame)) = TpktFrame::decode(&mut src) else {
+        unreachable!()
+    };
+    assert!(src.is_empty());
+    assert_eq!(
+        frame.length(),
+        u16::from_be_bytes([bytes[2], bytes[3]]),
+        "the length of the header"
+    );
+    assert_eq!(frame.declared_length(), Some(frame.length()));
+    assert_eq!(frame.payload_bytes(), &bytes[4..]);
+    assert_eq!(frame.payload_ref().as_ref(), &bytes[4..]);
+}
+
+/// a payload decoder of a connection reset by the peer
+struct ResetDecoder;
+
+impl Decoder for ResetDecoder {
+    type Item = ();
+    type Error = s7_comm::Error;
+
+    fn decode(&mut self, _src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        Err(io::Error::from(io::ErrorKind::ConnectionReset).into())
+    }
+}
+
+#[test]
+fn test_decode_io_error_kind() {
+    let mut decoder = TpktDecoder::new(CoptDecoder::new(ResetDecoder));
+    let mut src = BytesMut::from([0x03, 0x00, 0x00, 0x08, 0x02, 0xf0, 0x80, 0x32].as_ref());
+    let err = decoder.decode(&mut src).unwrap_err();
+    assert_eq!(err.io_kind(), Some(io::ErrorKind::ConnectionReset));
+    assert!(matches!(err, tpkt::Error::IoErr(_)));
+
+    let err = s7_comm::Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+    assert_eq!(err.io_kind(), Some(io::ErrorKind::UnexpectedEof));
+    assert_eq!(s7_comm::Error::Other("x".to_string()).io_kind(), None);
+}
@@ -6,10 +6,31 @@ pub enum Error {
     #[error(transparent)]
     IoErr(#[from] io::Error),
 
+    /// the declared length is less than the header
+    #[error("invalid tpkt length: {0}")]
+    InvalidLength(u16),
+
+    /// the version of the header is not 3
+    #[error("unsupported tpkt version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("tpkt length {length} exceeds the max length {max_length}")]
+    LengthExceeded { length: u16, max_length: u16 },
+
     #[error("Error: {0}")]
     Error(String),
 }
 
+impl Error {
+    /// the kind of the io error of any layer below tpkt
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            Error::IoErr(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
 pub trait ToTpktError {
     fn to_err(self) -> Error;
 }
@@ -8,7 +8,30 @@ pub use packet::TpktFrame;
 use tokio_util::codec::{Decoder, Encoder};
 
 pub struct TpktEncoder<E>(pub E);
-pub struct TpktDecoder<D>(pub D);
+/// the length of the tpkt header
+pub const TPKT_HEADER_LEN: u16 = 4;
+/// the version of the header, frames of other versions are rejected
+pub const TPKT_VERSION: u8 = 3;
+
+pub struct TpktDecoder<D> {
+    decoder: D,
+    /// frames whose declared length exceeds it are rejected
+    pub max_length: u16,
+}
+
+impl<D> TpktDecoder<D> {
+    pub fn new(decoder: D) -> Self {
+        Self {
+            decoder,
+            max_length: u16::MAX,
+        }
+    }
+
+    pub fn max_length(mut self, max_length: u16) -> Self {
+        self.max_length = max_length;
+        self
+    }
+}
 
 impl<F, E: Encoder<F>> Encoder<TpktFrame<F>> for TpktEncoder<E>
 where
@@ -36,27 +59,52 @@ where
     type Error = Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if src.len() < 4 {
+        let Some((version, length, mut framed_datas)) = split_frame(src, self.max_length)? else {
             return Ok(None);
-        }
-        let (Some(index_0), Some(index_1)) = (src.get(2), src.get(3)) else {
-            unreachable!()
         };
-        let length = u16::from_be_bytes([*index_0, *index_1]);
-        let lenght_usize = length as usize;
-        if src.len() < lenght_usize {
-            return Ok(None);
-        }
-        let mut framed_datas = src.split_to(lenght_usize);
-        let version = framed_datas.get_u8();
-        let _reserved = framed_datas.get_u8();
-        let _ = framed_datas.get_u16();
-        let Some(payload) = self.0.decode(&mut framed_datas)? else {
+        let Some(payload) = self.decoder.decode(&mut framed_datas)? else {
             // maybe return none
             return Err(Error::Error("payload decode fail!".to_string()));
         };
-        Ok(Some(TpktFrame { version, payload }))
+        Ok(Some(TpktFrame {
+            version,
+            payload,
+            declared_length: Some(length),
+        }))
+    }
+}
+
+/// Split a whole frame off `src`, return its version, declared length and
+/// payload.
+pub(crate) fn split_frame(
+    src: &mut BytesMut,
+    max_length: u16,
+) -> Result<Option<(u8, u16, BytesMut)>, Error> {
+    if src.len() < 4 {
+        return Ok(None);
+    }
+    if src[0] != TPKT_VERSION {
+        return Err(Error::UnsupportedVersion(src[0]));
+    }
+    let (Some(index_0), Some(index_1)) = (src.get(2), src.get(3)) else {
+        unreachable!()
+    };
+    let length = u16::from_be_bytes([*index_0, *index_1]);
+    if length < TPKT_HEADER_LEN {
+        return Err(Error::InvalidLength(length));
+    }
+    if length > max_length {
+        return Err(Error::LengthExceeded { length, max_length });
+    }
+    let lenght_usize = length as usize;
+    if src.len() < lenght_usize {
+        return Ok(None);
     }
+    let mut framed_datas = src.split_to(lenght_usize);
+    let version = framed_datas.get_u8();
+    let _reserved = framed_datas.get_u8();
+    let _ = framed_datas.get_u16();
+    Ok(Some((version, length, framed_datas)))
 }
 
 impl<E: Default> Default for TpktEncoder<E> {
@@ -67,6 +115,6 @@ impl<E: Default> Default for TpktEncoder<E> {
 
 impl<D: Default> Default for TpktDecoder<D> {
     fn default() -> Self {
-        Self(D::default())
+        Self::new(D::default())
     }
 }
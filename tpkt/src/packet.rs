@@ -1,18 +1,31 @@
-use crate::{ToTpktError, TpktEncoder};
-use bytes::BytesMut;
+use crate::{split_frame, ToTpktError, TpktEncoder, TPKT_HEADER_LEN, TPKT_VERSION};
+use bytes::{Bytes, BytesMut};
 use tokio_util::codec::Encoder;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug)]
 pub struct TpktFrame<F> {
     pub(crate) version: u8,
     pub(crate) payload: F,
+    /// the length declared by the header of a decoded frame
+    pub(crate) declared_length: Option<u16>,
 }
 
+/// the declared length is left out, a decoded frame equals the frame built
+/// of its payload
+impl<F: PartialEq> PartialEq for TpktFrame<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.version == other.version && self.payload == other.payload
+    }
+}
+
+impl<F: Eq> Eq for TpktFrame<F> {}
+
 impl<F> TpktFrame<F> {
     pub fn new(payload: F) -> Self {
         Self {
-            version: 3,
+            version: TPKT_VERSION,
             payload,
+            declared_length: None,
         }
     }
     pub fn version_mut(&mut self, version: u8) {
@@ -23,6 +36,17 @@ impl<F> TpktFrame<F> {
         self.payload
     }
 
+    /// the payload without taking the frame
+    pub fn payload_ref(&self) -> &F {
+        &self.payload
+    }
+
+    /// the length of the frame, including the header, declared by the header
+    /// of a decoded frame, `None` for a frame built by `new`
+    pub fn declared_length(&self) -> Option<u16> {
+        self.declared_length
+    }
+
     pub fn to_bytes<E>(self) -> Result<BytesMut, crate::error::Error>
     where
         E: Encoder<F> + Default,
@@ -34,3 +58,27 @@ impl<F> TpktFrame<F> {
         Ok(dst)
     }
 }
+
+impl TpktFrame<Bytes> {
+    /// Decode a frame without decoding its payload, `Ok(None)` if `src`
+    /// doesn't hold a whole frame yet.
+    pub fn decode(src: &mut BytesMut) -> Result<Option<Self>, crate::error::Error> {
+        Ok(
+            split_frame(src, u16::MAX)?.map(|(version, length, payload)| Self {
+                version,
+                payload: payload.freeze(),
+                declared_length: Some(length),
+            }),
+        )
+    }
+
+    /// the raw payload, `payload()` takes the frame
+    pub fn payload_bytes(&self) -> &[u8] {
+        self.payload.as_ref()
+    }
+
+    /// the length of the frame, including the header
+    pub fn length(&self) -> u16 {
+        self.payload.len() as u16 + TPKT_HEADER_LEN
+    }
+}